@@ -1,10 +1,14 @@
 // 管理后台处理器模块
 
-use crate::config::Config;
-use actix_web::{get, post, web, HttpResponse};
+use crate::config::{
+    mask_api_key, Config, MemorySettings, ModelParams, ParamLimits, WebhookSettings, CONFIG_PATH,
+};
+use crate::glossary::GlossaryEntry;
+use crate::history::{self, VersionSummary};
+use actix_web::{get, post, web, HttpRequest, HttpResponse};
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use parking_lot::RwLock;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConfigUpdate {
@@ -12,6 +16,15 @@ pub struct ConfigUpdate {
     pub llm_api_url: Option<String>,
     pub llm_model: Option<String>,
     pub system_prompt: Option<String>,
+    pub llm_provider: Option<String>,
+    pub request_timeout_secs: Option<u64>,
+    pub model_params: Option<ModelParams>,
+    pub param_limits: Option<ParamLimits>,
+    pub glossary: Option<Vec<GlossaryEntry>>,
+    pub few_shot_count: Option<usize>,
+    /// 回调设置，`secret` 为空时保留原密钥
+    pub webhooks: Option<WebhookSettings>,
+    pub memory: Option<MemorySettings>,
 }
 
 #[derive(Debug, Serialize)]
@@ -20,6 +33,15 @@ pub struct ConfigResponse {
     pub llm_model: String,
     pub llm_api_key_masked: String,
     pub system_prompt: String,
+    pub llm_provider: String,
+    pub request_timeout_secs: u64,
+    pub model_params: ModelParams,
+    pub param_limits: ParamLimits,
+    pub glossary: Vec<GlossaryEntry>,
+    pub few_shot_count: usize,
+    /// 回调设置，密钥已脱敏
+    pub webhooks: WebhookSettings,
+    pub memory: MemorySettings,
 }
 
 /// 获取当前配置（API Key 会被脱敏）
@@ -32,25 +54,44 @@ pub async fn get_config(config: web::Data<Arc<RwLock<Config>>>) -> HttpResponse
         llm_model: config.model().to_string(),
         llm_api_key_masked: mask_api_key(config.api_key()),
         system_prompt: config.system_prompt().to_string(),
+        llm_provider: config.llm_provider.clone(),
+        request_timeout_secs: config.request_timeout_secs,
+        model_params: config.model_params.clone(),
+        param_limits: config.param_limits.clone(),
+        glossary: config.glossary.clone(),
+        few_shot_count: config.few_shot_count,
+        webhooks: WebhookSettings {
+            secret: if config.webhooks.secret.is_empty() {
+                String::new()
+            } else {
+                mask_api_key(&config.webhooks.secret)
+            },
+            ..config.webhooks.clone()
+        },
+        memory: config.memory.clone(),
     };
 
     HttpResponse::Ok().json(response)
 }
 
 /// 更新配置
+///
+/// 先在副本上应用修改并校验，校验和保存都成功后才替换运行中的配置。
 #[post("/admin/config")]
 pub async fn update_config(
+    req: HttpRequest,
     update: web::Json<ConfigUpdate>,
     config: web::Data<Arc<RwLock<Config>>>,
 ) -> HttpResponse {
     let mut config = config.write();
+    let mut candidate = config.clone();
 
     let mut updated_fields = Vec::new();
 
     // 更新 API Key（如果提供）
     if let Some(api_key) = &update.llm_api_key {
         if !api_key.is_empty() && api_key != "your-api-key-here" {
-            config.llm_api_key = api_key.clone();
+            candidate.llm_api_key = api_key.clone();
             updated_fields.push("API Key");
         }
     }
@@ -58,7 +99,7 @@ pub async fn update_config(
     // 更新 API URL（如果提供）
     if let Some(api_url) = &update.llm_api_url {
         if !api_url.is_empty() {
-            config.llm_api_url = api_url.clone();
+            candidate.llm_api_url = api_url.clone();
             updated_fields.push("API URL");
         }
     }
@@ -66,41 +107,252 @@ pub async fn update_config(
     // 更新模型（如果提供）
     if let Some(model) = &update.llm_model {
         if !model.is_empty() {
-            config.llm_model = model.clone();
+            candidate.llm_model = model.clone();
             updated_fields.push("模型");
         }
     }
 
-    // 更新系统提示词（如果提供）
+    // 更新系统提示词（如果提供）；提示词来自外部文件时修改会在下次重新加载时被覆盖，因此拒绝
     if let Some(system_prompt) = &update.system_prompt {
         if !system_prompt.is_empty() {
-            config.system_prompt = system_prompt.clone();
+            if let Some(file) = candidate
+                .prompt_file()
+                .filter(|_| *system_prompt != candidate.system_prompt)
+            {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "message": format!("系统提示词由文件 {} 提供，请直接修改该文件", file)
+                }));
+            }
+            candidate.system_prompt = system_prompt.clone();
             updated_fields.push("系统提示词");
         }
     }
 
-    if !updated_fields.is_empty() {
-        // 保存配置到文件
-        if let Err(e) = config.save_to_file("config.json") {
-            log::error!("保存配置失败: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "message": format!("保存配置失败: {}", e)
-            }));
+    // 更新服务商（如果提供）
+    if let Some(provider) = &update.llm_provider {
+        if !provider.is_empty() {
+            candidate.llm_provider = provider.clone();
+            updated_fields.push("服务商");
         }
+    }
+
+    // 更新请求超时（如果提供）
+    if let Some(timeout) = update.request_timeout_secs {
+        candidate.request_timeout_secs = timeout;
+        updated_fields.push("请求超时");
+    }
+
+    // 更新默认模型参数（如果提供）
+    if let Some(params) = &update.model_params {
+        candidate.model_params = params.clone();
+        updated_fields.push("模型参数");
+    }
+
+    // 更新请求级参数上下限（如果提供）
+    if let Some(limits) = &update.param_limits {
+        candidate.param_limits = limits.clone();
+        updated_fields.push("参数上下限");
+    }
+
+    // 更新术语表（如果提供，整体替换）
+    if let Some(glossary) = &update.glossary {
+        candidate.glossary = glossary.clone();
+        updated_fields.push("术语表");
+    }
+
+    // 更新每次插入的示例数量（如果提供）
+    if let Some(count) = update.few_shot_count {
+        candidate.few_shot_count = count;
+        updated_fields.push("示例数量");
+    }
+
+    // 更新回调设置（如果提供）
+    if let Some(webhooks) = &update.webhooks {
+        let secret = if webhooks.secret.is_empty() {
+            candidate.webhooks.secret.clone()
+        } else {
+            webhooks.secret.clone()
+        };
+        candidate.webhooks = WebhookSettings {
+            secret,
+            ..webhooks.clone()
+        };
+        updated_fields.push("回调设置");
+    }
+
+    // 更新翻译记忆设置（如果提供）
+    if let Some(memory) = &update.memory {
+        candidate.memory = memory.clone();
+        updated_fields.push("翻译记忆设置");
+    }
 
-        let updated_items = updated_fields.join("、");
-        log::info!("配置已更新: {}", updated_items);
-        HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": format!("配置已更新并保存（{}）", updated_items)
-        }))
-    } else {
-        HttpResponse::BadRequest().json(serde_json::json!({
+    if updated_fields.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
             "success": false,
             "message": "没有有效的更新内容"
-        }))
+        }));
     }
+
+    let updated_items = updated_fields.join("、");
+    save_candidate(&req, &mut config, candidate, &updated_items)
+}
+
+/// 校验并原子保存候选配置，成功后替换运行中的配置
+///
+/// `description` 用于日志和响应消息，描述本次更新的内容。
+pub(crate) fn save_candidate(
+    req: &HttpRequest,
+    config: &mut Config,
+    candidate: Config,
+    description: &str,
+) -> HttpResponse {
+    if let Err(e) = candidate.validate() {
+        log::warn!("拒绝无效的配置更新: {}", e);
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "message": e.to_string(),
+            "errors": e.field_errors()
+        }));
+    }
+
+    // 原子保存配置并记录历史版本
+    if let Err(e) = history::commit(CONFIG_PATH.as_ref(), config, &candidate, &actor(req)) {
+        log::error!("保存配置失败: {}", e);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "message": format!("保存配置失败: {}", e)
+        }));
+    }
+
+    *config = candidate;
+
+    log::info!("配置已更新: {}", description);
+    HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "message": format!("配置已更新并保存（{}）", description)
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffQuery {
+    /// 对比的目标版本，缺省为当前配置
+    pub against: Option<String>,
+}
+
+/// 列出配置历史版本
+#[get("/admin/config/versions")]
+pub async fn list_config_versions() -> HttpResponse {
+    match history::list(CONFIG_PATH.as_ref()) {
+        Ok(versions) => {
+            let summaries: Vec<VersionSummary> = versions.iter().map(Into::into).collect();
+            HttpResponse::Ok().json(summaries)
+        }
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "message": format!("读取配置历史失败: {}", e)
+        })),
+    }
+}
+
+/// 对比历史版本与当前配置（或另一个版本）
+#[get("/admin/config/versions/{id}/diff")]
+pub async fn diff_config_version(
+    path: web::Path<String>,
+    query: web::Query<DiffQuery>,
+    config: web::Data<Arc<RwLock<Config>>>,
+) -> HttpResponse {
+    let version = match load_version(&path) {
+        Ok(version) => version,
+        Err(response) => return *response,
+    };
+
+    let (against, target) = match query.against.as_deref() {
+        None | Some("current") => ("current".to_string(), config.read().clone()),
+        Some(other) => match load_version(other) {
+            Ok(other) => (other.id, other.config),
+            Err(response) => return *response,
+        },
+    };
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "version": version.id,
+        "against": against,
+        "changes": version.config.diff(&target)
+    }))
+}
+
+/// 回滚到指定历史版本
+#[post("/admin/config/versions/{id}/rollback")]
+pub async fn rollback_config(
+    req: HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Arc<RwLock<Config>>>,
+) -> HttpResponse {
+    let version = match load_version(&path) {
+        Ok(version) => version,
+        Err(response) => return *response,
+    };
+
+    if let Err(e) = version.config.validate() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "message": format!("历史版本未通过当前校验规则: {}", e),
+            "errors": e.field_errors()
+        }));
+    }
+
+    let mut config = config.write();
+    let actor = format!("{}（回滚到 {}）", actor(&req), version.id);
+    match history::commit(CONFIG_PATH.as_ref(), &config, &version.config, &actor) {
+        Ok(changes) => {
+            *config = version.config;
+            log::info!("配置已回滚到版本 {}: {}", version.id, changes.join("; "));
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "message": format!("已回滚到版本 {}", version.id),
+                "changes": changes
+            }))
+        }
+        Err(e) => {
+            log::error!("回滚配置失败: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": format!("回滚配置失败: {}", e)
+            }))
+        }
+    }
+}
+
+/// 读取历史版本，失败时返回对应的 HTTP 响应
+fn load_version(id: &str) -> Result<history::ConfigVersion, Box<HttpResponse>> {
+    match history::get(CONFIG_PATH.as_ref(), id) {
+        Ok(Some(version)) => Ok(version),
+        Ok(None) => Err(Box::new(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "message": format!("版本 {} 不存在", id)
+        })))),
+        Err(e) => Err(Box::new(HttpResponse::InternalServerError().json(
+            serde_json::json!({
+                "success": false,
+                "message": format!("读取历史版本失败: {}", e)
+            }),
+        ))),
+    }
+}
+
+/// 操作者标识：优先使用 X-Admin-User 请求头，否则记录来源 IP
+fn actor(req: &HttpRequest) -> String {
+    if let Some(user) = req
+        .headers()
+        .get("X-Admin-User")
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+    {
+        return user.to_string();
+    }
+    let conn = req.connection_info();
+    format!("admin@{}", conn.realip_remote_addr().unwrap_or("unknown"))
 }
 
 /// 管理后台首页
@@ -112,14 +364,6 @@ pub async fn admin_index() -> HttpResponse {
         .body(html)
 }
 
-/// 脱敏 API Key
-fn mask_api_key(key: &str) -> String {
-    if key.len() <= 8 {
-        return "***".to_string();
-    }
-    format!("{}...{}", &key[..4], &key[key.len() - 4..])
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,5 +372,7 @@ mod tests {
     fn test_mask_api_key() {
         assert_eq!(mask_api_key("sk-1234567890abcdef"), "sk-1...cdef");
         assert_eq!(mask_api_key("short"), "***");
+        assert_eq!(mask_api_key("我的回调签名密钥口令"), "我的回调...密钥口令");
+        assert_eq!(mask_api_key("回调签名密钥"), "***");
     }
 }
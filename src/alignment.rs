@@ -0,0 +1,268 @@
+// 原文/译文对齐模块
+//
+// 基于长度比例的动态规划对齐（Gale-Church 思路）：译文与原文的长度大致成比例，
+// 允许 1-1、1-2、2-1、1-0、0-1 几种对应关系，选出总代价最小的对齐方式。
+// 先对齐段落，再在每组段落内对齐句子。
+
+use crate::chunker;
+use serde::Serialize;
+use std::ops::Range;
+
+/// 一组对应的原文与译文
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlignedPair {
+    pub source: String,
+    pub target: String,
+}
+
+/// 段落级对齐结果，附带段落内的句子对齐
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParagraphAlignment {
+    pub source: String,
+    pub target: String,
+    pub sentences: Vec<AlignedPair>,
+}
+
+/// 允许的对应关系及其先验代价（约为 -ln 概率）
+const BEADS: &[(usize, usize, f64)] = &[
+    (1, 1, 0.0),
+    (2, 1, 2.3),
+    (1, 2, 2.3),
+    (1, 0, 4.5),
+    (0, 1, 4.5),
+];
+
+/// 长度差的方差系数
+const VARIANCE: f64 = 6.8;
+
+/// 动态规划带宽在单元数量差之外额外留出的余量
+const BAND_MARGIN: usize = 20;
+
+/// 动态规划表的最大单元格数，超过时按位置比例分组
+const MAX_CELLS: usize = 2_000_000;
+
+/// 对齐两组文本单元，返回对应的下标区间
+///
+/// 只在对角线 `i·m/n` 附近的带状区域内搜索；单元过多时退化为按位置比例分组，
+/// 避免超长文本占用过多内存。
+pub fn align_units(source: &[&str], target: &[&str]) -> Vec<(Range<usize>, Range<usize>)> {
+    let (n, m) = (source.len(), target.len());
+    let band = n.abs_diff(m) + BAND_MARGIN;
+    let width = (2 * band + 1).min(m + 1);
+    if (n + 1).saturating_mul(width) > MAX_CELLS {
+        return proportional(n, m);
+    }
+
+    let source_lens: Vec<f64> = source.iter().map(|s| text_len(s)).collect();
+    let target_lens: Vec<f64> = target.iter().map(|t| text_len(t)).collect();
+    let total_source: f64 = source_lens.iter().sum();
+    let total_target: f64 = target_lens.iter().sum();
+    let ratio = if total_source > 0.0 {
+        (total_target / total_source).max(0.01)
+    } else {
+        1.0
+    };
+
+    // 第 i 行只保存 lo(i)..=hi(i) 列
+    let lo = |i: usize| (i * m).checked_div(n).unwrap_or(0).saturating_sub(band);
+    let hi = |i: usize| ((i * m).checked_div(n).unwrap_or(0) + band).min(m);
+    let mut cost: Vec<Vec<f64>> = (0..=n)
+        .map(|i| vec![f64::INFINITY; hi(i) - lo(i) + 1])
+        .collect();
+    let mut back: Vec<Vec<(usize, usize)>> =
+        cost.iter().map(|row| vec![(0, 0); row.len()]).collect();
+    cost[0][0] = 0.0;
+    for i in 0..=n {
+        for j in lo(i)..=hi(i) {
+            let current = cost[i][j - lo(i)];
+            if current.is_infinite() {
+                continue;
+            }
+            for &(ds, dt, prior) in BEADS {
+                let (ni, nj) = (i + ds, j + dt);
+                if ni > n || nj < lo(ni) || nj > hi(ni) {
+                    continue;
+                }
+                let s: f64 = source_lens[i..ni].iter().sum();
+                let t: f64 = target_lens[j..nj].iter().sum();
+                let next = current + prior + length_cost(s, t, ratio);
+                let cell = nj - lo(ni);
+                if next < cost[ni][cell] {
+                    cost[ni][cell] = next;
+                    back[ni][cell] = (ds, dt);
+                }
+            }
+        }
+    }
+
+    let mut beads = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        let (ds, dt) = back[i][j - lo(i)];
+        beads.push((i - ds..i, j - dt..j));
+        i -= ds;
+        j -= dt;
+    }
+    beads.reverse();
+    beads
+}
+
+/// 按位置比例分组：第 i 个原文单元对应译文中 `i·m/n` 起的单元
+fn proportional(n: usize, m: usize) -> Vec<(Range<usize>, Range<usize>)> {
+    if n == 0 {
+        return if m == 0 {
+            Vec::new()
+        } else {
+            vec![(0..0, 0..m)]
+        };
+    }
+    (0..n)
+        .map(|i| (i..i + 1, i * m / n..(i + 1) * m / n))
+        .collect()
+}
+
+/// 按段落对齐原文与译文（忽略空行），多段对应时以换行连接
+pub fn paragraph_pairs(source: &str, target: &str) -> Vec<AlignedPair> {
+    let source_paragraphs = paragraphs(source);
+    let target_paragraphs = paragraphs(target);
+
+    align_units(&source_paragraphs, &target_paragraphs)
+        .into_iter()
+        .map(|(s, t)| AlignedPair {
+            source: source_paragraphs[s].join("\n"),
+            target: target_paragraphs[t].join("\n"),
+        })
+        .collect()
+}
+
+/// 按段落和句子对齐原文与译文
+pub fn align_text(source: &str, target: &str) -> Vec<ParagraphAlignment> {
+    paragraph_pairs(source, target)
+        .into_iter()
+        .map(|pair| ParagraphAlignment {
+            sentences: align_sentences(&pair.source, &pair.target),
+            source: pair.source,
+            target: pair.target,
+        })
+        .collect()
+}
+
+fn align_sentences(source: &str, target: &str) -> Vec<AlignedPair> {
+    let source_sentences = chunker::split_sentences(source);
+    let target_sentences = chunker::split_sentences(target);
+
+    align_units(&source_sentences, &target_sentences)
+        .into_iter()
+        .map(|(s, t)| AlignedPair {
+            source: source_sentences[s].concat().trim().to_string(),
+            target: target_sentences[t].concat().trim().to_string(),
+        })
+        .collect()
+}
+
+fn paragraphs(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// 文本长度：不计空白的字符数
+fn text_len(text: &str) -> f64 {
+    text.chars().filter(|c| !c.is_whitespace()).count() as f64
+}
+
+/// 长度差代价：按比例换算后的长度差越大，代价越高
+fn length_cost(source: f64, target: f64, ratio: f64) -> f64 {
+    if source == 0.0 && target == 0.0 {
+        return 0.0;
+    }
+    let mean = (source + target / ratio) / 2.0;
+    let delta = (target - source * ratio) / (VARIANCE * mean * ratio).max(1.0).sqrt();
+    delta * delta / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_align_units_handles_merges() {
+        let source = [
+            "Short one.",
+            "This is a much longer sentence that goes on.",
+            "And it continues here with more words.",
+            "End.",
+        ];
+        let target = [
+            "短句。",
+            "这是一个很长的句子，并且在这里继续，还有更多的词。",
+            "结束。",
+        ];
+        assert_eq!(
+            align_units(&source, &target),
+            vec![(0..1, 0..1), (1..3, 1..2), (3..4, 2..3)]
+        );
+    }
+
+    #[test]
+    fn test_align_units_large_input() {
+        let source: Vec<String> = (0..12_000).map(|i| format!("Line number {}.", i)).collect();
+        let target: Vec<String> = (0..11_000).map(|i| format!("第 {} 行。", i)).collect();
+        let source: Vec<&str> = source.iter().map(String::as_str).collect();
+        let target: Vec<&str> = target.iter().map(String::as_str).collect();
+
+        // 数量差较大时按位置比例分组，仍然覆盖全部单元
+        let beads = align_units(&source, &target);
+        assert_eq!(beads.len(), 12_000);
+        assert_eq!(beads.last().unwrap().1.end, 11_000);
+
+        // 数量接近时在带状区域内对齐
+        let beads = align_units(&source[..10_000], &target[..9_990]);
+        assert_eq!(beads.first().unwrap(), &(0..1, 0..1));
+        assert_eq!(beads.last().unwrap().0.end, 10_000);
+        assert_eq!(beads.last().unwrap().1.end, 9_990);
+    }
+
+    #[test]
+    fn test_paragraph_pairs_skips_blank_lines() {
+        let pairs = paragraph_pairs("Good morning.\n\nGood night.", "早上好。\n晚安。");
+        assert_eq!(
+            pairs,
+            vec![
+                AlignedPair {
+                    source: "Good morning.".to_string(),
+                    target: "早上好。".to_string(),
+                },
+                AlignedPair {
+                    source: "Good night.".to_string(),
+                    target: "晚安。".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_align_text_pairs_sentences() {
+        let alignment = align_text(
+            "Hello world. How are you?\n\nSee you tomorrow.",
+            "你好世界。你好吗？\n明天见。",
+        );
+        assert_eq!(alignment.len(), 2);
+        assert_eq!(alignment[0].source, "Hello world. How are you?");
+        assert_eq!(
+            alignment[0].sentences,
+            vec![
+                AlignedPair {
+                    source: "Hello world.".to_string(),
+                    target: "你好世界。".to_string(),
+                },
+                AlignedPair {
+                    source: "How are you?".to_string(),
+                    target: "你好吗？".to_string(),
+                },
+            ]
+        );
+        assert_eq!(alignment[1].target, "明天见。");
+    }
+}
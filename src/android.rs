@@ -0,0 +1,377 @@
+// Android 字符串资源模块
+//
+// 解析 res/values/strings.xml 中的 <string>、<string-array> 和 <plurals>，只替换文案内容：
+// 行内标签、<xliff:g> 元素、字符实体、反斜杠转义和格式化参数作为占位符保护，
+// 译文中的撇号、引号和换行按 Android 的规则转义。复数文案按目标语言重新生成 <item>；
+// translatable="false" 的条目不属于翻译文件，从输出中移除。
+
+use crate::error::{AppError, AppResult};
+use crate::markup::{self, Token, TokenKind};
+use crate::mobile::{Message, Translation};
+use crate::placeholder::Masked;
+use crate::variables;
+use std::ops::Range;
+
+lazy_static::lazy_static! {
+    /// `\'`、`\n`、`\u00A0` 等反斜杠转义
+    static ref ESCAPE: regex::Regex = regex::Regex::new(r"\\(u[0-9a-fA-F]{4}|.)").unwrap();
+}
+
+/// 解析后的 strings.xml
+pub struct Document {
+    pub messages: Vec<Message>,
+    /// 各文案在原文中的写回位置，与 `messages` 一一对应
+    targets: Vec<Target>,
+    /// 需要移除的不可翻译条目
+    removals: Vec<Range<usize>>,
+}
+
+enum Target {
+    /// `<string>` 或 `<item>` 的内容（不含首尾空白）
+    Content(Range<usize>),
+    /// `<plurals>` 的全部内容，以及 `<item>` 和结束标签前的缩进
+    Plurals {
+        content: Range<usize>,
+        indent: String,
+        closing: String,
+    },
+}
+
+/// 解析 strings.xml
+pub fn parse(text: &str) -> AppResult<Document> {
+    let tokens = markup::tokenize(text, &[]);
+    let is_resources =
+        |t: &Token| matches!(&t.kind, TokenKind::Start { name, .. } if name == "resources");
+    if !tokens.iter().any(is_resources) {
+        return Err(AppError::Validation(
+            "不是 Android 字符串资源文件：缺少 <resources> 元素".to_string(),
+        ));
+    }
+
+    let mut doc = Document {
+        messages: Vec::new(),
+        targets: Vec::new(),
+        removals: Vec::new(),
+    };
+    let mut comment: Option<String> = None;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        match &token.kind {
+            TokenKind::Comment => {
+                let raw = &text[token.range.clone()];
+                comment = Some(raw[4..raw.len() - 3].trim().to_string());
+                i += 1;
+                continue;
+            }
+            TokenKind::Text if text[token.range.clone()].trim().is_empty() => {
+                i += 1;
+                continue;
+            }
+            TokenKind::Start {
+                name,
+                self_closing: false,
+                ..
+            } if matches!(name.as_str(), "string" | "string-array" | "plurals") => {
+                let end = find_end(&tokens, i, name).ok_or_else(|| {
+                    AppError::Validation(format!(
+                        "Android 字符串资源第 {} 行的 <{}> 没有结束标签",
+                        line_number(text, token.range.start),
+                        name
+                    ))
+                })?;
+                let key = attr(text, token, "name").unwrap_or_default();
+                if attr(text, token, "translatable").as_deref() == Some("false") {
+                    let element = token.range.start..tokens[end].range.end;
+                    doc.removals.push(markup::line_range(text, element));
+                } else {
+                    let element = &tokens[i..=end];
+                    match name.as_str() {
+                        "string" => doc.add_string(text, key, comment.take(), element),
+                        "string-array" => doc.add_array(text, &key, comment.take(), element),
+                        _ => doc.add_plurals(text, key, comment.take(), element),
+                    }
+                }
+                comment = None;
+                i = end + 1;
+                continue;
+            }
+            _ => {}
+        }
+        comment = None;
+        i += 1;
+    }
+    Ok(doc)
+}
+
+impl Document {
+    fn add_string(&mut self, text: &str, key: String, comment: Option<String>, element: &[Token]) {
+        if let Some((range, masked)) = content(text, element) {
+            self.messages.push(Message::single(key, comment, masked));
+            self.targets.push(Target::Content(range));
+        }
+    }
+
+    fn add_array(&mut self, text: &str, key: &str, comment: Option<String>, element: &[Token]) {
+        for (n, item) in items(element).into_iter().enumerate() {
+            self.add_string(text, format!("{}[{}]", key, n), comment.clone(), item);
+        }
+    }
+
+    fn add_plurals(&mut self, text: &str, key: String, comment: Option<String>, element: &[Token]) {
+        let mut forms = Vec::new();
+        let mut first_item = None;
+        for item in items(element) {
+            let Some(quantity) = attr(text, &item[0], "quantity") else {
+                continue;
+            };
+            // 引用其他资源的复数文案无法重新生成
+            let Some((_, masked)) = content(text, item) else {
+                return;
+            };
+            first_item.get_or_insert(item[0].range.start);
+            forms.push((quantity, masked));
+        }
+        let Some(first_item) = first_item else {
+            return;
+        };
+
+        let content = element[0].range.end..element[element.len() - 1].range.start;
+        let line_start = text[..first_item].rfind('\n').map_or(0, |i| i + 1);
+        let indent = &text[line_start.max(content.start)..first_item];
+        let closing = text[content.clone()]
+            .rsplit_once('\n')
+            .map_or("", |(_, last)| last);
+        self.messages.push(Message {
+            key,
+            comment,
+            forms,
+        });
+        self.targets.push(Target::Plurals {
+            indent: if indent.trim().is_empty() { indent } else { "" }.to_string(),
+            closing: if closing.trim().is_empty() {
+                closing
+            } else {
+                ""
+            }
+            .to_string(),
+            content,
+        });
+    }
+
+    /// 写回译文，翻译失败的文案保留原文
+    pub fn render(&self, text: &str, translations: &[Option<Translation>]) -> String {
+        let mut edits: Vec<(Range<usize>, String)> = self
+            .removals
+            .iter()
+            .map(|range| (range.clone(), String::new()))
+            .collect();
+        for (target, translation) in self.targets.iter().zip(translations) {
+            let Some(forms) = translation else { continue };
+            match target {
+                Target::Content(range) => edits.push((range.clone(), forms[0].1.clone())),
+                Target::Plurals {
+                    content,
+                    indent,
+                    closing,
+                } => {
+                    let multiline = text[content.clone()].contains('\n');
+                    let mut items = String::new();
+                    for (quantity, value) in forms {
+                        if multiline {
+                            items.push('\n');
+                            items.push_str(indent);
+                        }
+                        items
+                            .push_str(&format!("<item quantity=\"{}\">{}</item>", quantity, value));
+                    }
+                    if multiline {
+                        items.push('\n');
+                        items.push_str(closing);
+                    }
+                    edits.push((content.clone(), items));
+                }
+            }
+        }
+        markup::apply_edits(text, edits)
+    }
+}
+
+/// `tokens[start]` 开始标签对应的结束标签
+fn find_end(tokens: &[Token], start: usize, name: &str) -> Option<usize> {
+    (start + 1..tokens.len())
+        .find(|&j| matches!(&tokens[j].kind, TokenKind::End { name: n } if n == name))
+}
+
+/// 元素内的 `<item>` 子元素
+fn items(element: &[Token]) -> Vec<&[Token]> {
+    let mut items = Vec::new();
+    let mut i = 1;
+    while i < element.len() {
+        if let TokenKind::Start {
+            name,
+            self_closing: false,
+            ..
+        } = &element[i].kind
+        {
+            if name == "item" {
+                if let Some(end) = find_end(element, i, "item") {
+                    items.push(&element[i..=end]);
+                    i = end + 1;
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+    items
+}
+
+/// 元素的内容区间及替换占位符后的文案；空内容和资源引用（`@string/...`）返回 None
+fn content(text: &str, element: &[Token]) -> Option<(Range<usize>, Masked)> {
+    let inner = element[0].range.end..element[element.len() - 1].range.start;
+    let slice = &text[inner.clone()];
+    let trimmed = slice.trim();
+    if trimmed.is_empty() || trimmed.starts_with(['@', '?']) {
+        return None;
+    }
+    let start = inner.start + (slice.len() - slice.trim_start().len());
+    let range = start..start + trimmed.len();
+
+    let mut protected: Vec<Range<usize>> = variables::find(trimmed)
+        .into_iter()
+        .map(|r| start + r.start..start + r.end)
+        .collect();
+    // 整体加引号的文案保留首尾引号
+    if trimmed.len() > 1 && trimmed.starts_with('"') && trimmed.ends_with('"') {
+        protected.push(range.start..range.start + 1);
+        protected.push(range.end - 1..range.end);
+    }
+    let mut k = 1;
+    while k < element.len() - 1 {
+        let token = &element[k];
+        match &token.kind {
+            TokenKind::Text => {
+                protected.extend(markup::entity_ranges(text, token.range.clone()));
+                protected.extend(
+                    ESCAPE
+                        .find_iter(&text[token.range.clone()])
+                        .map(|m| token.range.start + m.start()..token.range.start + m.end()),
+                );
+            }
+            // <xliff:g> 中的内容（变量示例、产品名等）不翻译
+            TokenKind::Start {
+                name,
+                self_closing: false,
+                ..
+            } if name == "xliff:g" => {
+                let end = find_end(element, k, name).unwrap_or(element.len() - 2);
+                protected.push(token.range.start..element[end].range.end);
+                k = end;
+            }
+            _ => protected.push(token.range.clone()),
+        }
+        k += 1;
+    }
+    Some((
+        range.clone(),
+        Masked::new(text, range, &protected).with_escape(escape),
+    ))
+}
+
+/// 按 strings.xml 的规则转义译文
+fn escape(text: &str) -> String {
+    markup::escape_text(text)
+        .replace('\'', "\\'")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// 开始标签中属性的值
+fn attr(text: &str, token: &Token, name: &str) -> Option<String> {
+    token.attr(name).map(|range| text[range].to_string())
+}
+
+fn line_number(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRINGS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<resources xmlns:xliff="urn:oasis:names:tc:xliff:document:1.2">
+    <string name="app_name" translatable="false">Acme</string>
+    <!-- Shown on the home screen -->
+    <string name="welcome">Welcome, <b>%1$s</b>! Don\'t miss <xliff:g id="app">Acme</xliff:g>.</string>
+    <string name="alias">@string/welcome</string>
+    <string-array name="planets">
+        <item>Mercury</item>
+        <item>Venus</item>
+    </string-array>
+    <plurals name="songs">
+        <item quantity="one">%d song</item>
+        <item quantity="other">%d songs</item>
+    </plurals>
+</resources>
+"#;
+
+    #[test]
+    fn test_parse_android() {
+        let doc = parse(STRINGS).unwrap();
+        let messages: Vec<(String, Option<String>, Vec<String>)> = doc
+            .messages
+            .iter()
+            .map(|m| {
+                let forms = m
+                    .forms
+                    .iter()
+                    .map(|(c, masked)| format!("{}:{}", c, masked.text));
+                (m.key.clone(), m.comment.clone(), forms.collect())
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "welcome".into(),
+                    Some("Shown on the home screen".into()),
+                    vec![":Welcome, ⟦1⟧! Don⟦2⟧t miss ⟦3⟧.".into()]
+                ),
+                ("planets[0]".into(), None, vec![":Mercury".into()]),
+                ("planets[1]".into(), None, vec![":Venus".into()]),
+                (
+                    "songs".into(),
+                    None,
+                    vec!["one:⟦1⟧ song".into(), "other:⟦1⟧ songs".into()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_android() {
+        let doc = parse(STRINGS).unwrap();
+        let translations = vec![
+            Some(vec![(
+                String::new(),
+                doc.messages[0].forms[0]
+                    .1
+                    .substitute("欢迎，⟦1⟧！别错过 ⟦3⟧ 的 \"新版\"⟦2⟧"),
+            )]),
+            Some(vec![(String::new(), "水星".to_string())]),
+            None,
+            Some(vec![("other".to_string(), "%d 首歌曲".to_string())]),
+        ];
+        let output = doc.render(STRINGS, &translations);
+        assert!(!output.contains("app_name"));
+        assert!(output.contains(
+            r#"<string name="welcome">欢迎，<b>%1$s</b>！别错过 <xliff:g id="app">Acme</xliff:g> 的 \"新版\"\'</string>"#
+        ));
+        assert!(output.contains("<item>水星</item>\n        <item>Venus</item>"));
+        assert!(output.contains(
+            "<plurals name=\"songs\">\n        <item quantity=\"other\">%d 首歌曲</item>\n    </plurals>"
+        ));
+    }
+}
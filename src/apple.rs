@@ -0,0 +1,754 @@
+// Apple 字符串资源模块
+//
+// - `.strings`：`"key" = "value";` 列表，只替换值，注释和键原样保留；
+// - `.stringsdict`：属性列表，翻译 NSStringLocalizedFormatKey 中的文字，
+//   复数规则字典按目标语言的复数类别重新生成；
+// - `.xcstrings`：Xcode 字符串目录（JSON），为尚未本地化的键添加目标语言的本地化，
+//   已有的本地化和 `shouldTranslate: false` 的键保持不变。
+//
+// 格式化参数（`%@`、`%lld`、`%#@files@`）和转义序列作为占位符保护。
+
+use crate::error::{AppError, AppResult};
+use crate::language::language_code;
+use crate::markup::{self, Token, TokenKind};
+use crate::mobile::{Message, Translation};
+use crate::placeholder::Masked;
+use crate::variables;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::io;
+use std::ops::Range;
+
+lazy_static::lazy_static! {
+    /// `.strings` 中的 `\"`、`\n`、`\U00A0` 等转义
+    static ref ESCAPE: regex::Regex = regex::Regex::new(r"\\([Uu][0-9a-fA-F]{4}|.)").unwrap();
+    /// stringsdict 中引用复数规则的变量，如 `%#@files@`
+    static ref RULE_VARIABLE: regex::Regex = regex::Regex::new(r"%(\d+\$)?#@\w+@").unwrap();
+}
+
+/// CLDR 复数类别
+const PLURAL_CATEGORIES: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+/// 格式化参数和复数规则变量的区间
+fn format_specifiers(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = variables::find(text);
+    ranges.extend(RULE_VARIABLE.find_iter(text).map(|m| m.range()));
+    ranges
+}
+
+/// 解析后的 `.strings` 文件
+pub struct StringsFile {
+    pub messages: Vec<Message>,
+    /// 各值（不含引号）在原文中的区间
+    values: Vec<Range<usize>>,
+}
+
+/// 解析 `.strings` 文件
+pub fn parse_strings(text: &str) -> AppResult<StringsFile> {
+    let mut file = StringsFile {
+        messages: Vec::new(),
+        values: Vec::new(),
+    };
+    let invalid = |pos: usize| {
+        AppError::Validation(format!(
+            "strings 文件第 {} 行无法解析",
+            text[..pos].matches('\n').count() + 1
+        ))
+    };
+
+    let bytes = text.as_bytes();
+    let mut comment: Option<String> = None;
+    let mut pos = 0;
+    loop {
+        pos = skip_whitespace(text, pos);
+        if pos >= bytes.len() {
+            break;
+        }
+        let rest = &text[pos..];
+        if rest.starts_with("/*") {
+            let end = rest.find("*/").ok_or_else(|| invalid(pos))?;
+            comment = Some(rest[2..end].trim().to_string());
+            pos += end + 2;
+            continue;
+        }
+        if rest.starts_with("//") {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            comment = Some(rest[2..end].trim().to_string());
+            pos += end;
+            continue;
+        }
+
+        let (key, after_key) = match bytes[pos] {
+            b'"' => {
+                let end = closing_quote(text, pos).ok_or_else(|| invalid(pos))?;
+                (text[pos + 1..end].replace("\\\"", "\""), end + 1)
+            }
+            _ => {
+                let len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || "_.-$".contains(c)))
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return Err(invalid(pos));
+                }
+                (rest[..len].to_string(), pos + len)
+            }
+        };
+        pos = skip_whitespace(text, after_key);
+        if bytes.get(pos) != Some(&b'=') {
+            return Err(invalid(pos));
+        }
+        pos = skip_whitespace(text, pos + 1);
+        if bytes.get(pos) != Some(&b'"') {
+            return Err(invalid(pos));
+        }
+        let end = closing_quote(text, pos).ok_or_else(|| invalid(pos))?;
+        let value = pos + 1..end;
+        pos = skip_whitespace(text, end + 1);
+        if bytes.get(pos) != Some(&b';') {
+            return Err(invalid(pos));
+        }
+        pos += 1;
+
+        let mut protected: Vec<Range<usize>> = ESCAPE
+            .find_iter(&text[value.clone()])
+            .map(|m| value.start + m.start()..value.start + m.end())
+            .collect();
+        protected.extend(
+            format_specifiers(&text[value.clone()])
+                .into_iter()
+                .map(|r| value.start + r.start..value.start + r.end),
+        );
+        let masked = Masked::new(text, value.clone(), &protected).with_escape(escape_strings);
+        file.messages
+            .push(Message::single(key, comment.take(), masked));
+        file.values.push(value);
+    }
+    Ok(file)
+}
+
+impl StringsFile {
+    /// 写回译文，翻译失败的值保留原文
+    pub fn render(&self, text: &str, translations: &[Option<Translation>]) -> String {
+        let edits = self
+            .values
+            .iter()
+            .zip(translations)
+            .filter_map(|(range, translation)| {
+                let forms = translation.as_ref()?;
+                Some((range.clone(), forms[0].1.clone()))
+            })
+            .collect();
+        markup::apply_edits(text, edits)
+    }
+}
+
+fn skip_whitespace(text: &str, pos: usize) -> usize {
+    let rest = &text[pos..];
+    pos + (rest.len() - rest.trim_start().len())
+}
+
+/// 从 `open` 处的引号开始查找未转义的结束引号
+fn closing_quote(text: &str, open: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = open + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// 按 `.strings` 的规则转义译文
+fn escape_strings(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// 属性列表中的值
+enum PlistValue {
+    /// `<string>` 的内容区间
+    String(Range<usize>),
+    Dict(Vec<PlistEntry>),
+    Other,
+}
+
+/// 字典中的一项
+struct PlistEntry {
+    key: String,
+    /// 从 `<key>` 到值结束的区间
+    range: Range<usize>,
+    value: PlistValue,
+}
+
+impl PlistEntry {
+    fn string<'a>(&self, text: &'a str) -> Option<&'a str> {
+        match &self.value {
+            PlistValue::String(range) => Some(&text[range.clone()]),
+            _ => None,
+        }
+    }
+}
+
+/// 解析后的 `.stringsdict` 文件
+pub struct StringsdictFile {
+    pub messages: Vec<Message>,
+    targets: Vec<DictTarget>,
+}
+
+enum DictTarget {
+    /// NSStringLocalizedFormatKey 的值
+    Format(Range<usize>),
+    /// 复数规则字典中各复数类别的键值对
+    Plural(Vec<Range<usize>>),
+}
+
+/// 属性列表分词后的读取位置
+struct PlistReader<'a> {
+    text: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl PlistReader<'_> {
+    fn error(&self, message: &str) -> AppError {
+        let offset = self
+            .tokens
+            .get(self.pos)
+            .map_or(self.text.len(), |t| t.range.start);
+        AppError::Validation(format!(
+            "stringsdict 文件第 {} 行{}",
+            self.text[..offset].matches('\n').count() + 1,
+            message
+        ))
+    }
+
+    /// 下一个有意义的标记（跳过空白文本和注释）
+    fn next(&mut self) -> Option<Token> {
+        while let Some(token) = self.tokens.get(self.pos) {
+            self.pos += 1;
+            match token.kind {
+                TokenKind::Comment | TokenKind::Declaration => continue,
+                TokenKind::Text if self.text[token.range.clone()].trim().is_empty() => continue,
+                _ => return Some(token.clone()),
+            }
+        }
+        None
+    }
+
+    /// 读取元素内容直到结束标签，返回内容区间和结束标签的结束位置
+    fn element_content(&mut self, start: &Token, name: &str) -> AppResult<(Range<usize>, usize)> {
+        let from = start.range.end;
+        while let Some(token) = self.tokens.get(self.pos) {
+            self.pos += 1;
+            if matches!(&token.kind, TokenKind::End { name: n } if n == name) {
+                return Ok((from..token.range.start, token.range.end));
+            }
+        }
+        Err(self.error(&format!("的 <{}> 没有结束标签", name)))
+    }
+
+    /// 读取 `<dict>` 开始标签之后的字典内容
+    fn dict(&mut self) -> AppResult<Vec<PlistEntry>> {
+        let mut entries = Vec::new();
+        loop {
+            let token = self
+                .next()
+                .ok_or_else(|| self.error("的 <dict> 没有结束标签"))?;
+            match &token.kind {
+                TokenKind::End { name } if name == "dict" => return Ok(entries),
+                TokenKind::Start {
+                    name,
+                    self_closing: false,
+                    ..
+                } if name == "key" => {
+                    let (key_range, _) = self.element_content(&token, "key")?;
+                    let key = self.text[key_range].trim().to_string();
+                    let (value, end) = self.value()?;
+                    entries.push(PlistEntry {
+                        key,
+                        range: token.range.start..end,
+                        value,
+                    });
+                }
+                _ => return Err(self.error("的字典中缺少 <key>")),
+            }
+        }
+    }
+
+    /// 读取一个值，返回值及其结束位置
+    fn value(&mut self) -> AppResult<(PlistValue, usize)> {
+        let token = self.next().ok_or_else(|| self.error("的键缺少值"))?;
+        match &token.kind {
+            TokenKind::Start {
+                self_closing: true, ..
+            } => Ok((PlistValue::Other, token.range.end)),
+            TokenKind::Start { name, .. } if name == "dict" => {
+                let entries = self.dict()?;
+                let end = self.tokens[self.pos - 1].range.end;
+                Ok((PlistValue::Dict(entries), end))
+            }
+            TokenKind::Start { name, .. } if name == "string" => {
+                let (range, end) = self.element_content(&token, "string")?;
+                Ok((PlistValue::String(range), end))
+            }
+            TokenKind::Start { name, .. } => {
+                // 数组、数字等其他值不翻译，跳到对应的结束标签
+                let name = name.clone();
+                let mut depth = 1;
+                while let Some(next) = self.tokens.get(self.pos) {
+                    self.pos += 1;
+                    match &next.kind {
+                        TokenKind::Start {
+                            name: n,
+                            self_closing: false,
+                            ..
+                        } if *n == name => depth += 1,
+                        TokenKind::End { name: n } if *n == name => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return Ok((PlistValue::Other, next.range.end));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Err(self.error(&format!("的 <{}> 没有结束标签", name)))
+            }
+            _ => Err(self.error("的键缺少值")),
+        }
+    }
+}
+
+/// 解析 `.stringsdict` 文件
+pub fn parse_stringsdict(text: &str) -> AppResult<StringsdictFile> {
+    let mut reader = PlistReader {
+        text,
+        tokens: markup::tokenize(text, &[]),
+        pos: 0,
+    };
+    let root = loop {
+        let token = reader
+            .next()
+            .ok_or_else(|| AppError::Validation("stringsdict 文件缺少 <dict>".to_string()))?;
+        if matches!(&token.kind, TokenKind::Start { name, self_closing: false, .. } if name == "dict")
+        {
+            break reader.dict()?;
+        }
+    };
+
+    let mut file = StringsdictFile {
+        messages: Vec::new(),
+        targets: Vec::new(),
+    };
+    for entry in &root {
+        let PlistValue::Dict(fields) = &entry.value else {
+            continue;
+        };
+        for field in fields {
+            match &field.value {
+                PlistValue::String(range) if field.key == "NSStringLocalizedFormatKey" => {
+                    let masked = plist_string(text, range.clone());
+                    if masked.has_prose() {
+                        file.messages
+                            .push(Message::single(entry.key.clone(), None, masked));
+                        file.targets.push(DictTarget::Format(range.clone()));
+                    }
+                }
+                PlistValue::Dict(rule) => {
+                    let is_plural = rule.iter().any(|f| {
+                        f.key == "NSStringFormatSpecTypeKey"
+                            && f.string(text) == Some("NSStringPluralRuleType")
+                    });
+                    if !is_plural {
+                        continue;
+                    }
+                    let mut forms = Vec::new();
+                    let mut ranges = Vec::new();
+                    for f in rule {
+                        if let PlistValue::String(range) = &f.value {
+                            if PLURAL_CATEGORIES.contains(&f.key.as_str()) {
+                                forms.push((f.key.clone(), plist_string(text, range.clone())));
+                                ranges.push(f.range.clone());
+                            }
+                        }
+                    }
+                    if !forms.is_empty() {
+                        file.messages.push(Message {
+                            key: format!("{}.{}", entry.key, field.key),
+                            comment: None,
+                            forms,
+                        });
+                        file.targets.push(DictTarget::Plural(ranges));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(file)
+}
+
+/// 属性列表字符串：字符实体和格式化参数受保护，译文按 XML 转义
+fn plist_string(text: &str, range: Range<usize>) -> Masked {
+    let mut protected = markup::entity_ranges(text, range.clone());
+    protected.extend(
+        format_specifiers(&text[range.clone()])
+            .into_iter()
+            .map(|r| range.start + r.start..range.start + r.end),
+    );
+    Masked::new(text, range, &protected).with_escape(markup::escape_text)
+}
+
+impl StringsdictFile {
+    /// 写回译文：复数规则字典中原有的类别替换为目标语言的类别，翻译失败的保留原文
+    pub fn render(&self, text: &str, translations: &[Option<Translation>]) -> String {
+        let mut edits = Vec::new();
+        for (target, translation) in self.targets.iter().zip(translations) {
+            let Some(forms) = translation else { continue };
+            match target {
+                DictTarget::Format(range) => edits.push((range.clone(), forms[0].1.clone())),
+                DictTarget::Plural(ranges) => {
+                    let first = markup::line_range(text, ranges[0].clone());
+                    let whole_line = first != ranges[0];
+                    let indent = &text[first.start..ranges[0].start];
+                    let pairs: String = forms
+                        .iter()
+                        .map(|(category, value)| {
+                            let pair = format!("<key>{}</key>", category);
+                            let value = format!("<string>{}</string>", value);
+                            if whole_line {
+                                format!("{indent}{pair}\n{indent}{value}\n")
+                            } else {
+                                format!("{pair}{value}")
+                            }
+                        })
+                        .collect();
+                    edits.push((first, pairs));
+                    for range in &ranges[1..] {
+                        edits.push((markup::line_range(text, range.clone()), String::new()));
+                    }
+                }
+            }
+        }
+        markup::apply_edits(text, edits)
+    }
+}
+
+/// 字符串目录中文案的写回位置
+enum CatalogSlot {
+    /// `stringUnit`
+    Unit,
+    /// `variations.plural`
+    Plural,
+    /// `substitutions.{name}.variations.plural`
+    Substitution(String),
+}
+
+/// 解析后的 `.xcstrings` 字符串目录
+pub struct Catalog {
+    pub messages: Vec<Message>,
+    value: Value,
+    source_language: String,
+    /// 各文案所属的键和写回位置，与 `messages` 一一对应
+    slots: Vec<(String, CatalogSlot)>,
+}
+
+/// 字符串目录中目标语言的标识，如 `zh-Hans`、`ja`
+fn catalog_language(to_lang: &str) -> &str {
+    language_code(to_lang).unwrap_or(to_lang)
+}
+
+/// 解析 `.xcstrings` 字符串目录；指定目标语言时跳过已有该语言本地化的键
+pub fn parse_xcstrings(text: &str, to_lang: Option<&str>) -> AppResult<Catalog> {
+    let value: Value = serde_json::from_str(text)
+        .map_err(|e| AppError::Validation(format!("xcstrings 文件不是合法的 JSON: {}", e)))?;
+    let source_language = value["sourceLanguage"]
+        .as_str()
+        .ok_or_else(|| AppError::Validation("xcstrings 文件缺少 sourceLanguage".to_string()))?
+        .to_string();
+    let strings = value["strings"]
+        .as_object()
+        .ok_or_else(|| AppError::Validation("xcstrings 文件缺少 strings".to_string()))?;
+
+    let target = to_lang.map(catalog_language);
+    let mut messages = Vec::new();
+    let mut slots = Vec::new();
+    for (key, entry) in strings {
+        let localized = target.is_some_and(|t| !entry["localizations"][t].is_null());
+        if localized || entry["shouldTranslate"] == Value::Bool(false) || key.trim().is_empty() {
+            continue;
+        }
+        let comment = entry["comment"].as_str().map(str::to_string);
+        let source = &entry["localizations"][&source_language];
+        if source.is_null() {
+            // 没有源语言本地化时，键本身就是原文
+            messages.push(Message::single(key.clone(), comment, catalog_string(key)));
+            slots.push((key.clone(), CatalogSlot::Unit));
+            continue;
+        }
+        if let Some(value) = source["stringUnit"]["value"].as_str() {
+            messages.push(Message::single(
+                key.clone(),
+                comment.clone(),
+                catalog_string(value),
+            ));
+            slots.push((key.clone(), CatalogSlot::Unit));
+        }
+        if let Some(forms) = plural_forms(&source["variations"]["plural"]) {
+            messages.push(Message {
+                key: key.clone(),
+                comment: comment.clone(),
+                forms,
+            });
+            slots.push((key.clone(), CatalogSlot::Plural));
+        }
+        if let Some(substitutions) = source["substitutions"].as_object() {
+            for (name, substitution) in substitutions {
+                if let Some(forms) = plural_forms(&substitution["variations"]["plural"]) {
+                    messages.push(Message {
+                        key: format!("{}.{}", key, name),
+                        comment: comment.clone(),
+                        forms,
+                    });
+                    slots.push((key.clone(), CatalogSlot::Substitution(name.clone())));
+                }
+            }
+        }
+    }
+    Ok(Catalog {
+        messages,
+        value,
+        source_language,
+        slots,
+    })
+}
+
+fn catalog_string(text: &str) -> Masked {
+    Masked::new(text, 0..text.len(), &format_specifiers(text))
+}
+
+/// `variations.plural` 中各类别的原文
+fn plural_forms(plural: &Value) -> Option<Vec<(String, Masked)>> {
+    let forms: Vec<(String, Masked)> = plural
+        .as_object()?
+        .iter()
+        .filter_map(|(category, form)| {
+            let value = form["stringUnit"]["value"].as_str()?;
+            Some((category.clone(), catalog_string(value)))
+        })
+        .collect();
+    (!forms.is_empty()).then_some(forms)
+}
+
+fn string_unit(value: &str) -> Value {
+    json!({"stringUnit": {"state": "translated", "value": value}})
+}
+
+impl Catalog {
+    /// 为目标语言添加本地化；已有本地化的键以及有文案翻译失败的键保持不变
+    pub fn render(&self, to_lang: &str, translations: &[Option<Translation>]) -> AppResult<String> {
+        let language = catalog_language(to_lang);
+        let failed: HashSet<&str> = self
+            .slots
+            .iter()
+            .zip(translations)
+            .filter(|(_, translation)| translation.is_none())
+            .map(|((key, _), _)| key.as_str())
+            .collect();
+
+        let mut value = self.value.clone();
+        for ((key, slot), translation) in self.slots.iter().zip(translations) {
+            let Some(forms) = translation else { continue };
+            if failed.contains(key.as_str()) {
+                continue;
+            }
+            let entry = &mut value["strings"][key];
+            if !entry["localizations"].is_object() {
+                entry["localizations"] = json!({});
+            }
+            let source = entry["localizations"][&self.source_language].clone();
+            let localizations = entry["localizations"].as_object_mut().unwrap();
+            let target = localizations.entry(language).or_insert_with(|| json!({}));
+            let plural: Map<String, Value> = forms
+                .iter()
+                .map(|(category, text)| (category.clone(), string_unit(text)))
+                .collect();
+            match slot {
+                CatalogSlot::Unit => {
+                    target["stringUnit"] = string_unit(&forms[0].1)["stringUnit"].clone()
+                }
+                CatalogSlot::Plural => target["variations"] = json!({"plural": plural}),
+                CatalogSlot::Substitution(name) => {
+                    let mut substitution = source["substitutions"][name].clone();
+                    substitution["variations"] = json!({"plural": plural});
+                    target["substitutions"][name] = substitution;
+                }
+            }
+        }
+        to_xcode_json(&value)
+    }
+}
+
+/// 按 Xcode 的风格输出 JSON（两空格缩进，键值之间为 ` : `），减少与 Xcode 保存结果的差异
+fn to_xcode_json(value: &Value) -> AppResult<String> {
+    use serde::Serialize;
+
+    struct XcodeFormatter(serde_json::ser::PrettyFormatter<'static>);
+
+    impl serde_json::ser::Formatter for XcodeFormatter {
+        fn begin_array<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+            self.0.begin_array(w)
+        }
+        fn end_array<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+            self.0.end_array(w)
+        }
+        fn begin_array_value<W: ?Sized + io::Write>(
+            &mut self,
+            w: &mut W,
+            first: bool,
+        ) -> io::Result<()> {
+            self.0.begin_array_value(w, first)
+        }
+        fn end_array_value<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+            self.0.end_array_value(w)
+        }
+        fn begin_object<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+            self.0.begin_object(w)
+        }
+        fn end_object<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+            self.0.end_object(w)
+        }
+        fn begin_object_key<W: ?Sized + io::Write>(
+            &mut self,
+            w: &mut W,
+            first: bool,
+        ) -> io::Result<()> {
+            self.0.begin_object_key(w, first)
+        }
+        fn begin_object_value<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+            w.write_all(b" : ")
+        }
+        fn end_object_value<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+            self.0.end_object_value(w)
+        }
+    }
+
+    let mut output = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(
+        &mut output,
+        XcodeFormatter(serde_json::ser::PrettyFormatter::new()),
+    );
+    value.serialize(&mut serializer)?;
+    Ok(String::from_utf8(output).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strings_and_stringsdict() {
+        let strings = "/* Greeting */\n\"hello\" = \"Hello, %@!\\n\";\nbye = \"Bye\";\n";
+        let file = parse_strings(strings).unwrap();
+        assert_eq!(file.messages[0].comment.as_deref(), Some("Greeting"));
+        assert_eq!(file.messages[0].forms[0].1.text, "Hello, ⟦1⟧!⟦2⟧");
+        let translations = vec![
+            Some(vec![(
+                String::new(),
+                file.messages[0].forms[0]
+                    .1
+                    .substitute("你好，⟦1⟧！「\"」⟦2⟧"),
+            )]),
+            None,
+        ];
+        assert_eq!(
+            file.render(strings, &translations),
+            "/* Greeting */\n\"hello\" = \"你好，%@！「\\\"」\\n\";\nbye = \"Bye\";\n"
+        );
+
+        let dict = r#"<plist version="1.0">
+<dict>
+    <key>files</key>
+    <dict>
+        <key>NSStringLocalizedFormatKey</key>
+        <string>%#@count@</string>
+        <key>count</key>
+        <dict>
+            <key>NSStringFormatSpecTypeKey</key>
+            <string>NSStringPluralRuleType</string>
+            <key>NSStringFormatValueTypeKey</key>
+            <string>d</string>
+            <key>one</key>
+            <string>%d file</string>
+            <key>other</key>
+            <string>%d files</string>
+        </dict>
+    </dict>
+</dict>
+</plist>
+"#;
+        let file = parse_stringsdict(dict).unwrap();
+        assert_eq!(file.messages.len(), 1);
+        assert_eq!(file.messages[0].key, "files.count");
+        let translations = vec![Some(vec![
+            ("one".to_string(), "%d файл".to_string()),
+            ("few".to_string(), "%d файла".to_string()),
+            ("many".to_string(), "%d файлов".to_string()),
+            ("other".to_string(), "%d файла".to_string()),
+        ])];
+        let output = file.render(dict, &translations);
+        assert!(output.contains(
+            "<string>d</string>\n            <key>one</key>\n            <string>%d файл</string>\n            <key>few</key>"
+        ));
+        assert!(output.contains("<string>%d файла</string>\n        </dict>"));
+        assert!(!output.contains("files</string>"));
+    }
+
+    #[test]
+    fn test_xcstrings() {
+        let catalog = r#"{
+  "sourceLanguage" : "en",
+  "strings" : {
+    "Done" : {},
+    "Hello" : { "localizations" : { "zh-Hans" : { "stringUnit" : { "state" : "translated", "value" : "你好" } } } },
+    "Internal" : { "shouldTranslate" : false },
+    "items" : {
+      "comment" : "Cart badge",
+      "localizations" : {
+        "en" : { "variations" : { "plural" : {
+          "one" : { "stringUnit" : { "state" : "translated", "value" : "%lld item" } },
+          "other" : { "stringUnit" : { "state" : "translated", "value" : "%lld items" } }
+        } } }
+      }
+    }
+  },
+  "version" : "1.0"
+}"#;
+        assert_eq!(parse_xcstrings(catalog, None).unwrap().messages.len(), 3);
+        let parsed = parse_xcstrings(catalog, Some("中文(简体)")).unwrap();
+        let keys: Vec<&str> = parsed.messages.iter().map(|m| m.key.as_str()).collect();
+        assert_eq!(keys, vec!["Done", "items"]);
+        assert_eq!(parsed.messages[1].forms[0].1.text, "⟦1⟧ item");
+
+        let translations = vec![
+            Some(vec![(String::new(), "完成".to_string())]),
+            Some(vec![("other".to_string(), "%lld 件商品".to_string())]),
+        ];
+        let output = parsed.render("中文(简体)", &translations).unwrap();
+        let value: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            value["strings"]["Done"]["localizations"]["zh-Hans"]["stringUnit"]["value"],
+            "完成"
+        );
+        assert_eq!(
+            value["strings"]["items"]["localizations"]["zh-Hans"]["variations"]["plural"]["other"]
+                ["stringUnit"]["value"],
+            "%lld 件商品"
+        );
+        assert!(output.contains("\"sourceLanguage\" : \"en\""));
+    }
+}
@@ -0,0 +1,530 @@
+// 书籍翻译模块
+//
+// 支持 EPUB 和纯文本书籍，作为后台任务执行：
+// - EPUB 按书脊顺序逐章翻译 XHTML（HTML 方式处理，保留标签），目录（nav 文档和 NCX）一并翻译，
+//   书脊、清单和其余元数据保持不变，只把 `dc:language` 和各章的 `lang` 改为目标语言；
+// - 纯文本按长度切成若干节逐节翻译，前一节的结尾作为下一节的上文；
+// - 双语版本中每个段落之后紧跟其译文。
+
+use crate::alignment;
+use crate::chunker;
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::html;
+use crate::jobs::Progress;
+use crate::language::language_code;
+use crate::markup::{self, TokenKind};
+use crate::office::{read_package, write_package, PackageEntry};
+use crate::placeholder::Masked;
+use crate::segments::translate_segments;
+use crate::translator::{translate_long_text, TranslateOptions};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+lazy_static::lazy_static! {
+    /// 译文副本中需要去掉的属性：重复的 id 会破坏链接，语言属性改为目标语言
+    static ref COPIED_ATTRS: regex::Regex =
+        regex::Regex::new(r#"\s(?:id|lang|xml:lang)\s*=\s*(?:"[^"]*"|'[^']*')"#).unwrap();
+}
+
+/// 双语版本中原文与译文交替排列的元素
+const BILINGUAL_ELEMENTS: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "dt",
+    "dd",
+    "figcaption",
+];
+
+/// 纯文本书籍每节包含的分块数，每翻译完一节报告一次进度
+const SECTION_CHUNKS: usize = 8;
+
+/// 语种检测使用的样本长度
+const SAMPLE_CHARS: usize = 2000;
+
+/// 书籍格式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookFormat {
+    Epub,
+    Text,
+}
+
+impl BookFormat {
+    /// 根据内容判断格式：ZIP 包按 EPUB 处理，其余须为 UTF-8 文本
+    pub fn detect(bytes: &[u8]) -> AppResult<Self> {
+        if bytes.starts_with(b"PK") {
+            Ok(BookFormat::Epub)
+        } else if std::str::from_utf8(bytes).is_ok() {
+            Ok(BookFormat::Text)
+        } else {
+            Err(AppError::Validation(
+                "不支持的书籍格式，仅支持 EPUB 和 UTF-8 纯文本".to_string(),
+            ))
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            BookFormat::Epub => "application/epub+zip",
+            BookFormat::Text => "text/plain; charset=utf-8",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            BookFormat::Epub => "epub",
+            BookFormat::Text => "txt",
+        }
+    }
+}
+
+/// 待翻译的书籍
+pub struct Book {
+    pub content: Vec<u8>,
+    pub format: BookFormat,
+    /// 生成双语版本
+    pub bilingual: bool,
+}
+
+/// 书籍开头的一段文字，用于语种检测
+pub fn sample_text(bytes: &[u8], format: BookFormat) -> AppResult<String> {
+    let text = match format {
+        BookFormat::Text => String::from_utf8_lossy(bytes).into_owned(),
+        BookFormat::Epub => {
+            let entries = read_package(bytes)?;
+            let book = Epub::open(&entries)?;
+            let mut sample = String::new();
+            for &i in &book.chapters {
+                let chapter = String::from_utf8_lossy(&entries[i].data);
+                let body = chapter
+                    .find("<body")
+                    .map_or(&chapter[..], |p| &chapter[p..]);
+                for token in markup::tokenize(body, &["script", "style"]) {
+                    if token.kind == TokenKind::Text {
+                        sample.push_str(&markup::unescape(&body[token.range]));
+                    }
+                }
+                if sample.chars().count() >= SAMPLE_CHARS {
+                    break;
+                }
+            }
+            sample
+        }
+    };
+    Ok(text.chars().take(SAMPLE_CHARS).collect())
+}
+
+/// 翻译书籍，返回翻译后的文件
+pub async fn translate(
+    config: &Config,
+    book: &Book,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+    progress: &Progress,
+) -> AppResult<Vec<u8>> {
+    let bilingual = book.bilingual;
+    match book.format {
+        BookFormat::Epub => {
+            translate_epub(
+                config,
+                &book.content,
+                from_lang,
+                to_lang,
+                options,
+                bilingual,
+                progress,
+            )
+            .await
+        }
+        BookFormat::Text => {
+            let text = String::from_utf8_lossy(&book.content);
+            translate_text(
+                config, &text, from_lang, to_lang, options, bilingual, progress,
+            )
+            .await
+            .map(String::into_bytes)
+        }
+    }
+}
+
+/// 纯文本书籍逐节翻译
+async fn translate_text(
+    config: &Config,
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+    bilingual: bool,
+    progress: &Progress,
+) -> AppResult<String> {
+    let sections = chunker::split(text, config.chunking.max_tokens * SECTION_CHUNKS);
+    progress.start(sections.len());
+    let mut result = String::with_capacity(text.len() * 2);
+    for (i, section) in sections.iter().enumerate() {
+        let mut options = options.clone();
+        options.previous_text = i
+            .checked_sub(1)
+            .map(|prev| chunker::context_tail(sections[prev]).to_string());
+        let translated = translate_long_text(config, section, from_lang, to_lang, &options).await?;
+        if bilingual {
+            for pair in alignment::paragraph_pairs(section, &translated) {
+                result.push_str(&format!("{}\n\n{}\n\n", pair.source, pair.target));
+            }
+        } else {
+            result.push_str(&translated);
+        }
+        progress.advance();
+    }
+    Ok(result)
+}
+
+/// EPUB 的包文档（OPF）及需要翻译的部件，均为条目下标
+struct Epub {
+    opf: usize,
+    /// 书脊中的章节在前，其余 XHTML 文档（如未列入书脊的 nav 目录）在后
+    chapters: Vec<usize>,
+    /// EPUB 3 的 nav 目录，双语版本中只保留译文
+    nav: Option<usize>,
+    ncx: Option<usize>,
+}
+
+impl Epub {
+    fn open(entries: &[PackageEntry]) -> AppResult<Self> {
+        let invalid = |message: &str| AppError::Validation(format!("无效的 EPUB: {}", message));
+        let find = |name: &str| entries.iter().position(|e| e.name == name);
+
+        let container = find("META-INF/container.xml")
+            .map(|i| String::from_utf8_lossy(&entries[i].data).into_owned())
+            .ok_or_else(|| invalid("缺少 META-INF/container.xml"))?;
+        let opf_path = start_tags(&container, "rootfile")
+            .find_map(|attrs| attrs.get("full-path"))
+            .ok_or_else(|| invalid("container.xml 中没有 rootfile"))?;
+        let opf = find(&opf_path).ok_or_else(|| invalid("找不到包文档"))?;
+        let package = String::from_utf8_lossy(&entries[opf].data).into_owned();
+        let base = opf_path.rfind('/').map_or("", |i| &opf_path[..=i]);
+
+        // 清单：id -> (条目下标, 媒体类型)
+        let mut manifest = Vec::new();
+        let mut nav = None;
+        for attrs in start_tags(&package, "item") {
+            let (Some(id), Some(href)) = (attrs.get("id"), attrs.get("href")) else {
+                continue;
+            };
+            let Some(index) = find(&resolve(base, &href)) else {
+                continue;
+            };
+            let properties = attrs.get("properties").unwrap_or_default();
+            if properties.split_whitespace().any(|p| p == "nav") {
+                nav = Some(index);
+            }
+            manifest.push((id, index, attrs.get("media-type").unwrap_or_default()));
+        }
+        let is_xhtml = |media_type: &str| media_type == "application/xhtml+xml";
+
+        let mut chapters = Vec::new();
+        for attrs in start_tags(&package, "itemref") {
+            let idref = attrs.get("idref").unwrap_or_default();
+            if let Some((_, index, media_type)) = manifest.iter().find(|(id, ..)| *id == idref) {
+                if is_xhtml(media_type) && !chapters.contains(index) {
+                    chapters.push(*index);
+                }
+            }
+        }
+        for (_, index, media_type) in &manifest {
+            if is_xhtml(media_type) && !chapters.contains(index) {
+                chapters.push(*index);
+            }
+        }
+        let ncx = manifest
+            .iter()
+            .find(|(.., media_type)| media_type == "application/x-dtbncx+xml")
+            .map(|(_, index, _)| *index);
+        Ok(Epub {
+            opf,
+            chapters,
+            nav,
+            ncx,
+        })
+    }
+}
+
+/// 逐章翻译 EPUB
+async fn translate_epub(
+    config: &Config,
+    bytes: &[u8],
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+    bilingual: bool,
+    progress: &Progress,
+) -> AppResult<Vec<u8>> {
+    let mut entries = read_package(bytes)?;
+    let book = Epub::open(&entries)?;
+    let code = language_code(to_lang).unwrap_or(to_lang).to_string();
+    progress.start(book.chapters.len() + usize::from(book.ncx.is_some()));
+
+    for &i in &book.chapters {
+        let chapter = String::from_utf8_lossy(&entries[i].data).into_owned();
+        let translated = html::translate(config, &chapter, from_lang, to_lang, options).await?;
+        let output = if bilingual && book.nav != Some(i) {
+            interleave(&chapter, &translated, &code)
+        } else {
+            set_language(&translated, "html", &code)
+        };
+        entries[i].data = output.into_bytes();
+        progress.advance();
+    }
+
+    if let Some(i) = book.ncx {
+        let ncx = String::from_utf8_lossy(&entries[i].data).into_owned();
+        let labels = element_contents(&ncx, "text");
+        let masked: Vec<Masked> = labels
+            .iter()
+            .map(|range| {
+                let entities = markup::entity_ranges(&ncx, range.clone());
+                Masked::new(&ncx, range.clone(), &entities).with_escape(markup::escape_text)
+            })
+            .collect();
+        let translated = translate_segments(config, &masked, from_lang, to_lang, options).await?;
+        let ncx = markup::apply_edits(&ncx, labels.into_iter().zip(translated).collect());
+        entries[i].data = set_language(&ncx, "ncx", &code).into_bytes();
+        progress.advance();
+    }
+
+    let package = String::from_utf8_lossy(&entries[book.opf].data).into_owned();
+    let edits = element_contents(&package, "dc:language")
+        .into_iter()
+        .map(|range| (range, markup::escape_text(&code)))
+        .collect();
+    entries[book.opf].data = markup::apply_edits(&package, edits).into_bytes();
+
+    write_package(&entries)
+}
+
+/// 双语版本：原文中每个段落之后插入译文中对应的段落；两者段落数不一致时只保留译文
+fn interleave(original: &str, translated: &str, code: &str) -> String {
+    let source_blocks = bilingual_blocks(original);
+    let target_blocks = bilingual_blocks(translated);
+    if source_blocks.len() != target_blocks.len() {
+        log::warn!("原文与译文的段落数不一致，无法生成双语版本");
+        return set_language(translated, "html", code);
+    }
+    let edits = source_blocks
+        .into_iter()
+        .zip(target_blocks)
+        .map(|(source, target)| {
+            let copy = copy_element(&translated[target], code);
+            (source.end..source.end, copy)
+        })
+        .collect();
+    markup::apply_edits(original, edits)
+}
+
+/// 译文段落的副本：去掉 id 和语言属性，首个标签标注目标语言
+fn copy_element(element: &str, code: &str) -> String {
+    let mut edits = Vec::new();
+    for (n, token) in markup::tokenize(element, &[]).into_iter().enumerate() {
+        let TokenKind::Start { name, .. } = &token.kind else {
+            continue;
+        };
+        let tag = &element[token.range.clone()];
+        let mut cleaned = COPIED_ATTRS.replace_all(tag, "").into_owned();
+        if n == 0 {
+            let at = 1 + name.len();
+            let code = markup::escape_attr(code);
+            cleaned.insert_str(at, &format!(" lang=\"{0}\" xml:lang=\"{0}\"", code));
+        }
+        if cleaned != tag {
+            edits.push((token.range, cleaned));
+        }
+    }
+    markup::apply_edits(element, edits)
+}
+
+/// 最外层的可对照段落元素区间
+fn bilingual_blocks(text: &str) -> Vec<Range<usize>> {
+    let tokens = markup::tokenize(text, &["script", "style"]);
+    let mut blocks = Vec::new();
+    let mut open: Option<(String, usize, usize)> = None;
+    for token in &tokens {
+        match (&token.kind, &mut open) {
+            (
+                TokenKind::Start {
+                    name,
+                    self_closing: false,
+                    ..
+                },
+                None,
+            ) if BILINGUAL_ELEMENTS.contains(&name.as_str()) => {
+                open = Some((name.clone(), token.range.start, 1));
+            }
+            (
+                TokenKind::Start {
+                    name,
+                    self_closing: false,
+                    ..
+                },
+                Some((open_name, _, depth)),
+            ) if name == open_name => *depth += 1,
+            (TokenKind::End { name }, Some((open_name, start, depth))) if name == open_name => {
+                *depth -= 1;
+                if *depth == 0 {
+                    blocks.push(*start..token.range.end);
+                    open = None;
+                }
+            }
+            _ => {}
+        }
+    }
+    blocks
+}
+
+/// 把根元素的 `lang`、`xml:lang` 属性改为目标语言
+fn set_language(text: &str, root: &str, code: &str) -> String {
+    let tokens = markup::tokenize(text, &["script", "style"]);
+    let Some(token) = tokens
+        .iter()
+        .find(|t| matches!(&t.kind, TokenKind::Start { name, .. } if name == root))
+    else {
+        return text.to_string();
+    };
+    let edits = ["lang", "xml:lang"]
+        .iter()
+        .filter_map(|name| token.attr(name))
+        .map(|range| (range, markup::escape_attr(code)))
+        .collect();
+    markup::apply_edits(text, edits)
+}
+
+/// 指定元素的内容区间（不含标签，内容为空的除外）
+fn element_contents(text: &str, element: &str) -> Vec<Range<usize>> {
+    let tokens = markup::tokenize(text, &[]);
+    tokens
+        .windows(2)
+        .filter_map(|pair| match (&pair[0].kind, &pair[1].kind) {
+            (
+                TokenKind::Start {
+                    name,
+                    self_closing: false,
+                    ..
+                },
+                TokenKind::Text,
+            ) if name == element => Some(pair[1].range.clone()),
+            _ => None,
+        })
+        .filter(|range| !text[range.clone()].trim().is_empty())
+        .collect()
+}
+
+/// 开始标签的属性值（已还原实体）
+struct Attrs(Vec<(String, String)>);
+
+impl Attrs {
+    fn get(&self, name: &str) -> Option<String> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+/// 文档中所有指定名称的开始标签的属性
+fn start_tags<'a>(text: &'a str, element: &'a str) -> impl Iterator<Item = Attrs> + 'a {
+    markup::tokenize(text, &[])
+        .into_iter()
+        .filter_map(move |token| match token.kind {
+            TokenKind::Start { name, attrs, .. } if name == element => Some(Attrs(
+                attrs
+                    .into_iter()
+                    .filter_map(|a| Some((a.name, markup::unescape(&text[a.value?]))))
+                    .collect(),
+            )),
+            _ => None,
+        })
+}
+
+/// 把清单中的相对路径解析为包内路径：去掉片段、解码 `%XX`、处理 `..`
+fn resolve(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let bytes = href.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| href.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(b) => {
+                decoded.push(b);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    let href = String::from_utf8_lossy(&decoded);
+
+    let mut parts: Vec<&str> = base.split('/').filter(|p| !p.is_empty()).collect();
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_and_language() {
+        assert_eq!(
+            resolve("OEBPS/", "Text/ch%201.xhtml#s1"),
+            "OEBPS/Text/ch 1.xhtml"
+        );
+        assert_eq!(resolve("OEBPS/Text/", "../nav.xhtml"), "OEBPS/nav.xhtml");
+        assert_eq!(resolve("", "ch1.xhtml"), "ch1.xhtml");
+
+        let page =
+            r#"<html xmlns="http://www.w3.org/1999/xhtml" xml:lang="en" lang="en"><body/></html>"#;
+        assert_eq!(
+            set_language(page, "html", "zh-Hans"),
+            r#"<html xmlns="http://www.w3.org/1999/xhtml" xml:lang="zh-Hans" lang="zh-Hans"><body/></html>"#
+        );
+        // 目标语种是客户端传入的任意字符串，写入属性前转义
+        assert_eq!(
+            set_language(page, "html", r#"x" onload="&"#),
+            r#"<html xmlns="http://www.w3.org/1999/xhtml" xml:lang="x&quot; onload=&quot;&amp;" lang="x&quot; onload=&quot;&amp;"><body/></html>"#
+        );
+        assert_eq!(
+            copy_element("<p>一</p>", "<x>"),
+            r#"<p lang="&lt;x&gt;" xml:lang="&lt;x&gt;">一</p>"#
+        );
+    }
+
+    #[test]
+    fn test_interleave() {
+        let original = r#"<body><h1 id="c1">Chapter</h1><div><p>One <b>bold</b></p></div><ul><li><p>Item</p></li></ul></body>"#;
+        let translated = r#"<body><h1 id="c1">章</h1><div><p>一 <b>粗</b></p></div><ul><li><p>项</p></li></ul></body>"#;
+        assert_eq!(
+            interleave(original, translated, "zh-Hans"),
+            r#"<body><h1 id="c1">Chapter</h1><h1 lang="zh-Hans" xml:lang="zh-Hans">章</h1><div><p>One <b>bold</b></p><p lang="zh-Hans" xml:lang="zh-Hans">一 <b>粗</b></p></div><ul><li><p>Item</p></li><li lang="zh-Hans" xml:lang="zh-Hans"><p>项</p></li></ul></body>"#
+        );
+    }
+}
@@ -0,0 +1,174 @@
+// 长文本分块模块
+//
+// 按段落切分文本，单个段落超过上限时再按句子切分，仍然过长的句子按字符硬切。
+// 切出的分块是原文的连续片段，按顺序拼接即可还原原文，换行结构不会丢失。
+
+use crate::translator::{estimate_text_tokens, is_cjk};
+
+/// 作为上文提供给下一个分块的最大字符数
+pub const CONTEXT_TAIL_CHARS: usize = 500;
+
+/// 将文本切分为估算 token 数不超过 `max_tokens` 的连续片段
+pub fn split(text: &str, max_tokens: usize) -> Vec<&str> {
+    let max_tokens = max_tokens.max(1);
+    let mut units = Vec::new();
+    for paragraph in text.split_inclusive('\n') {
+        if estimate_text_tokens(paragraph) <= max_tokens {
+            units.push(paragraph);
+            continue;
+        }
+        for sentence in split_sentences(paragraph) {
+            if estimate_text_tokens(sentence) <= max_tokens {
+                units.push(sentence);
+            } else {
+                units.extend(split_hard(sentence, max_tokens));
+            }
+        }
+    }
+
+    // 贪心合并相邻片段，只在片段边界处断开
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+    let mut tokens = 0;
+    for unit in units {
+        let unit_tokens = estimate_text_tokens(unit);
+        if end > start && tokens + unit_tokens > max_tokens {
+            chunks.push(&text[start..end]);
+            start = end;
+            tokens = 0;
+        }
+        end += unit.len();
+        tokens += unit_tokens;
+    }
+    if end > start {
+        chunks.push(&text[start..end]);
+    }
+    chunks
+}
+
+/// 按句末标点切分，标点后的空白归入前一句
+pub fn split_sentences(paragraph: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = paragraph.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let is_end = match c {
+            '。' | '！' | '？' | '；' => true,
+            '.' | '!' | '?' | ';' => chars.peek().is_none_or(|(_, next)| next.is_whitespace()),
+            _ => false,
+        };
+        if !is_end {
+            continue;
+        }
+        let mut end = i + c.len_utf8();
+        while let Some(&(j, next)) = chars.peek() {
+            if !next.is_whitespace() {
+                break;
+            }
+            end = j + next.len_utf8();
+            chars.next();
+        }
+        sentences.push(&paragraph[start..end]);
+        start = end;
+    }
+    if start < paragraph.len() {
+        sentences.push(&paragraph[start..]);
+    }
+    sentences
+}
+
+/// 按字符硬切超长句子
+///
+/// 逐字符累加估算值（与 [`estimate_text_tokens`] 的算法一致），避免对不断增长的片段反复估算。
+fn split_hard(sentence: &str, max_tokens: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let (mut cjk, mut other) = (0usize, 0usize);
+    for (i, c) in sentence.char_indices() {
+        let (next_cjk, next_other) = if is_cjk(c) {
+            (cjk + 1, other)
+        } else {
+            (cjk, other + 1)
+        };
+        if i > start && next_cjk + next_other.div_ceil(4) > max_tokens {
+            pieces.push(&sentence[start..i]);
+            start = i;
+            (cjk, other) = if is_cjk(c) { (1, 0) } else { (0, 1) };
+        } else {
+            (cjk, other) = (next_cjk, next_other);
+        }
+    }
+    if start < sentence.len() {
+        pieces.push(&sentence[start..]);
+    }
+    pieces
+}
+
+/// 拆出片段首尾的空白，返回 `(前导空白, 正文, 尾随空白)`
+pub fn trim_edges(chunk: &str) -> (&str, &str, &str) {
+    let body = chunk.trim();
+    if body.is_empty() {
+        return (chunk, "", "");
+    }
+    let lead = chunk.len() - chunk.trim_start().len();
+    let trail_start = lead + body.len();
+    (&chunk[..lead], body, &chunk[trail_start..])
+}
+
+/// 取文本末尾最多 [`CONTEXT_TAIL_CHARS`] 个字符作为下一个分块的上文
+pub fn context_tail(text: &str) -> &str {
+    let text = text.trim();
+    match text.char_indices().rev().nth(CONTEXT_TAIL_CHARS - 1) {
+        Some((i, _)) => &text[i..],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_hard_long_unbroken_text() {
+        let text = "a".repeat(200_000) + &"字".repeat(50_000);
+        let pieces = split_hard(&text, 1000);
+        assert_eq!(pieces.concat(), text);
+        assert!(pieces.iter().all(|p| estimate_text_tokens(p) <= 1000));
+        assert_eq!(pieces.len(), 100);
+    }
+
+    #[test]
+    fn test_split_preserves_text_and_limit() {
+        let text =
+            "First paragraph here.\n\nSecond one. It has two sentences.\n第三段。这是中文句子！\n";
+        for max_tokens in [1, 5, 10, 1000] {
+            let chunks = split(text, max_tokens);
+            assert_eq!(chunks.concat(), text);
+            if max_tokens >= 10 {
+                assert!(chunks.iter().all(|c| estimate_text_tokens(c) <= max_tokens));
+            }
+        }
+        assert_eq!(split(text, 1000).len(), 1);
+    }
+
+    #[test]
+    fn test_split_prefers_paragraph_then_sentence_boundaries() {
+        let text = "Alpha beta gamma.\nDelta epsilon. Zeta eta theta.\n";
+        let chunks = split(text, 6);
+        assert_eq!(
+            chunks,
+            vec![
+                "Alpha beta gamma.\n",
+                "Delta epsilon. ",
+                "Zeta eta theta.\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trim_edges() {
+        assert_eq!(trim_edges("\n\n  Hello\n"), ("\n\n  ", "Hello", "\n"));
+        assert_eq!(trim_edges("\n\n"), ("\n\n", "", ""));
+    }
+}
@@ -0,0 +1,323 @@
+// 命令行参数与子命令模块
+
+use crate::config::{Config, CONFIG_PATH};
+use crate::error::AppError;
+use crate::language::detect_language;
+use crate::memory::{self, DuplicatePolicy, TranslationMemory, MEMORY_PATH};
+use crate::po;
+use crate::presets;
+use crate::tmx;
+use crate::translator::TranslateOptions;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+
+/// 翻译服务
+#[derive(Parser, Debug)]
+#[command(name = "Rustrans")]
+#[command(about = "基于 LLM 的翻译服务", long_about = None)]
+pub struct Args {
+    /// 服务端口
+    #[arg(short, long)]
+    pub port: Option<u16>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// 配置管理
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Gettext PO/POT 文件翻译
+    Po {
+        #[command(subcommand)]
+        action: PoAction,
+    },
+    /// 翻译记忆管理
+    Memory {
+        #[command(subcommand)]
+        action: MemoryAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MemoryAction {
+    /// 从 TMX 文件导入句段
+    Import {
+        /// 输入的 .tmx 文件
+        input: PathBuf,
+
+        /// 只导入该源语言的句段
+        #[arg(long)]
+        from: Option<String>,
+
+        /// 只导入该目标语言的句段
+        #[arg(long)]
+        to: Option<String>,
+
+        /// 原文已存在时保留已有译文（默认以导入的译文为准）
+        #[arg(long)]
+        skip_duplicates: bool,
+
+        /// 翻译记忆文件路径
+        #[arg(long, default_value = MEMORY_PATH)]
+        memory: PathBuf,
+    },
+    /// 导出为 TMX 文件
+    Export {
+        /// 只导出该源语言的句段
+        #[arg(long)]
+        from: Option<String>,
+
+        /// 只导出该目标语言的句段
+        #[arg(long)]
+        to: Option<String>,
+
+        /// 输出文件，缺省时输出到标准输出
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// 翻译记忆文件路径
+        #[arg(long, default_value = MEMORY_PATH)]
+        memory: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PoAction {
+    /// 翻译 PO/POT 文件中未翻译的条目
+    Translate {
+        /// 输入的 .po 或 .pot 文件
+        input: PathBuf,
+
+        /// 目标语种，如 日语
+        #[arg(long)]
+        to: String,
+
+        /// 源语种，缺省时自动检测
+        #[arg(long)]
+        from: Option<String>,
+
+        /// 输出文件，缺省时输出到标准输出
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// 使用的提示词预设
+        #[arg(long)]
+        preset: Option<String>,
+
+        /// 同时翻译标记为 fuzzy 的条目
+        #[arg(long)]
+        include_fuzzy: bool,
+
+        /// 为机器翻译的条目加上 fuzzy 标记
+        #[arg(long)]
+        mark_fuzzy: bool,
+
+        /// 配置文件路径
+        #[arg(long, default_value = CONFIG_PATH)]
+        config: PathBuf,
+
+        /// 翻译记忆文件路径，配置中启用翻译记忆时使用
+        #[arg(long, default_value = MEMORY_PATH)]
+        memory: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// 校验配置文件，输出字段级错误
+    Check {
+        /// 配置文件路径
+        #[arg(default_value = CONFIG_PATH)]
+        path: PathBuf,
+    },
+}
+
+/// 执行子命令，返回进程退出码
+pub fn run(command: Command) -> i32 {
+    match command {
+        Command::Config {
+            action: ConfigAction::Check { path },
+        } => check_config(&path),
+        Command::Po { action } => translate_po(action),
+        Command::Memory { action } => manage_memory(action),
+    }
+}
+
+/// `memory import` / `memory export` 子命令
+///
+/// 直接读写翻译记忆文件；服务运行中时导入的句段在重启后生效，建议改用管理接口导入。
+fn manage_memory(action: MemoryAction) -> i32 {
+    let result = match action {
+        MemoryAction::Import {
+            input,
+            from,
+            to,
+            skip_duplicates,
+            memory,
+        } => (|| -> Result<String, String> {
+            let memory = TranslationMemory::open(&memory)
+                .map_err(|e| format!("加载翻译记忆 {} 失败: {}", memory.display(), e))?;
+            let bytes = std::fs::read(&input)
+                .map_err(|e| format!("读取 {} 失败: {}", input.display(), e))?;
+            let duplicates = if skip_duplicates {
+                DuplicatePolicy::Skip
+            } else {
+                DuplicatePolicy::Overwrite
+            };
+            let result = tmx::import(&memory, &bytes, from.as_deref(), to.as_deref(), duplicates)
+                .map_err(|e| e.to_string())?;
+            Ok(format!(
+                "{}: 新增 {} 条，更新 {} 条，跳过 {} 条，忽略其他语种 {} 条，无效翻译单元 {} 个，共 {} 条",
+                input.display(),
+                result.stats.added,
+                result.stats.updated,
+                result.stats.skipped,
+                result.ignored,
+                result.invalid,
+                memory.len()
+            ))
+        })(),
+        MemoryAction::Export {
+            from,
+            to,
+            output,
+            memory,
+        } => (|| -> Result<String, String> {
+            let memory = TranslationMemory::open(&memory)
+                .map_err(|e| format!("加载翻译记忆 {} 失败: {}", memory.display(), e))?;
+            let content = tmx::export(&memory, from.as_deref(), to.as_deref());
+            let count = content.matches("<tu ").count();
+            match &output {
+                Some(path) => std::fs::write(path, &content)
+                    .map_err(|e| format!("写入 {} 失败: {}", path.display(), e))?,
+                None => print!("{}", content),
+            }
+            Ok(format!("已导出 {} 个翻译单元", count))
+        })(),
+    };
+
+    match result {
+        Ok(message) => {
+            eprintln!("✅ {}", message);
+            0
+        }
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            1
+        }
+    }
+}
+
+/// `po translate` 子命令
+fn translate_po(action: PoAction) -> i32 {
+    let PoAction::Translate {
+        input,
+        to,
+        from,
+        output,
+        preset,
+        include_fuzzy,
+        mark_fuzzy,
+        config,
+        memory,
+    } = action;
+
+    let result = (|| -> Result<(po::PoOutput, PathBuf), String> {
+        let config = Config::load_from_file(&config)
+            .and_then(|c| c.validate().map(|_| c))
+            .map_err(|e| format!("加载配置失败: {}", e))?;
+        if !config.is_configured() {
+            return Err(format!("{}", AppError::ServiceNotConfigured));
+        }
+        if config.memory.enabled {
+            let memory = TranslationMemory::open(&memory)
+                .map_err(|e| format!("加载翻译记忆 {} 失败: {}", memory.display(), e))?;
+            memory::install(memory);
+        }
+        let text = std::fs::read_to_string(&input)
+            .map_err(|e| format!("读取 {} 失败: {}", input.display(), e))?;
+        let source_text = po::source_text(&text).map_err(|e| e.to_string())?;
+        let from = from.unwrap_or_else(|| detect_language(&source_text));
+
+        let (preset, template) =
+            presets::resolve_prompt(&config, preset.as_deref(), None).map_err(|e| e.to_string())?;
+        let options = TranslateOptions {
+            params: config.model_params.clone(),
+            prompt_template: Some(template.to_string()),
+            preset: preset.map(str::to_string),
+            domain: preset.map(str::to_string),
+            ..Default::default()
+        };
+        let po_options = po::PoOptions {
+            include_fuzzy,
+            mark_fuzzy,
+        };
+
+        let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+        let translated = runtime
+            .block_on(po::translate(
+                &config, &text, &from, &to, &options, po_options,
+            ))
+            .map_err(|e| e.to_string())?;
+        Ok((translated, input.clone()))
+    })();
+
+    match result {
+        Ok((translated, input)) => {
+            let written = match &output {
+                Some(path) => std::fs::write(path, &translated.content)
+                    .map_err(|e| format!("写入 {} 失败: {}", path.display(), e)),
+                None => {
+                    print!("{}", translated.content);
+                    Ok(())
+                }
+            };
+            if let Err(e) = written {
+                eprintln!("❌ {}", e);
+                return 1;
+            }
+            eprintln!(
+                "✅ {}: 已翻译 {} 条，失败 {} 条",
+                input.display(),
+                translated.translated,
+                translated.failed
+            );
+            if translated.failed > 0 {
+                2
+            } else {
+                0
+            }
+        }
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            1
+        }
+    }
+}
+
+/// `config check` 子命令
+fn check_config(path: &Path) -> i32 {
+    let result = Config::load_from_file(path).and_then(|config| config.validate());
+
+    match result {
+        Ok(()) => {
+            println!("✅ 配置有效: {}", path.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("❌ 配置无效: {}", path.display());
+            if e.field_errors().is_empty() {
+                eprintln!("  {}", e);
+            }
+            for error in e.field_errors() {
+                eprintln!("  - {}", error);
+            }
+            1
+        }
+    }
+}
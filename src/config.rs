@@ -1,8 +1,254 @@
 // 配置模块
 
+use crate::error::{AppError, AppResult, ConfigError, FieldError};
+use crate::examples::{self, FewShotExample};
+use crate::glossary::{self, GlossaryEntry};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 默认配置文件路径
+pub const CONFIG_PATH: &str = "config.json";
+
+/// 支持的 LLM 服务商（均使用 OpenAI 兼容的 Chat Completions 接口）
+pub const KNOWN_PROVIDERS: &[&str] = &[
+    "openai",
+    "glm",
+    "deepseek",
+    "qwen",
+    "moonshot",
+    "siliconflow",
+    "ollama",
+    "custom",
+];
+
+/// 请求超时时间的允许范围（秒）
+const TIMEOUT_RANGE: std::ops::RangeInclusive<u64> = 1..=600;
+
+/// temperature 的取值范围（OpenAI 兼容接口的通用范围）
+const TEMPERATURE_RANGE: std::ops::RangeInclusive<f32> = 0.0..=2.0;
+
+/// 配置历史版本保留数量的允许范围
+const HISTORY_LIMIT_RANGE: std::ops::RangeInclusive<usize> = 1..=1000;
+
+/// 单次请求输入字符数上限的允许范围
+const MAX_INPUT_CHARS_RANGE: std::ops::RangeInclusive<usize> = 1..=10_000_000;
+
+/// JSON 请求体的大小上限：按输入字符数上限的最大值估算（UTF-8 每字符至多 4 字节，另留 1MB
+/// 给其他字段），热更新后任何合法的 `chunking.max_input_chars` 都由长度检查给出字段级错误
+pub const MAX_JSON_BYTES: usize = *MAX_INPUT_CHARS_RANGE.end() * 4 + 1024 * 1024;
+
+/// 每个分块 token 预算的允许范围
+const CHUNK_TOKENS_RANGE: std::ops::RangeInclusive<usize> = 100..=100_000;
+
+/// 大模型并发请求数的允许范围
+const CONCURRENCY_RANGE: std::ops::RangeInclusive<usize> = 1..=32;
+
+/// 回调最多投递次数的允许范围
+const WEBHOOK_ATTEMPTS_RANGE: std::ops::RangeInclusive<u32> = 1..=10;
+
+/// 单次回调投递超时时间的允许范围（秒）
+const WEBHOOK_TIMEOUT_RANGE: std::ops::RangeInclusive<u64> = 1..=60;
+
+/// 翻译记忆模糊匹配阈值的允许范围
+const FUZZY_THRESHOLD_RANGE: std::ops::RangeInclusive<f64> = 0.5..=1.0;
+
+/// 翻译记忆参考译文数量的允许范围
+const MEMORY_REFERENCES_RANGE: std::ops::RangeInclusive<usize> = 0..=10;
+
+fn default_provider() -> String {
+    "openai".to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    60
+}
+
+fn default_history_limit() -> usize {
+    20
+}
+
+fn default_few_shot_count() -> usize {
+    3
+}
+
+/// 模型采样参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelParams {
+    pub temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl Default for ModelParams {
+    fn default() -> Self {
+        Self {
+            temperature: 0.3,
+            max_tokens: None,
+            top_p: None,
+            seed: None,
+        }
+    }
+}
+
+/// 请求级参数覆盖的上下限，由管理员设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParamLimits {
+    pub min_temperature: f32,
+    pub max_temperature: f32,
+    pub max_tokens: u32,
+}
+
+impl Default for ParamLimits {
+    fn default() -> Self {
+        Self {
+            min_temperature: 0.0,
+            max_temperature: 1.0,
+            max_tokens: 4096,
+        }
+    }
+}
+
+/// 长文本分块设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkSettings {
+    /// 单次请求允许的最大字符数
+    pub max_input_chars: usize,
+    /// 每个分块的最大估算 token 数
+    pub max_tokens: usize,
+    /// 整个服务同时发往大模型的请求数（单次翻译和后台任务共用）
+    pub concurrency: usize,
+}
+
+impl Default for ChunkSettings {
+    fn default() -> Self {
+        Self {
+            max_input_chars: 100_000,
+            max_tokens: 2000,
+            concurrency: 4,
+        }
+    }
+}
+
+/// 后台任务回调设置
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookSettings {
+    /// HMAC-SHA256 签名密钥，为空时不签名
+    pub secret: String,
+    /// 最多投递次数（含首次投递）
+    pub max_attempts: u32,
+    /// 单次投递的超时时间（秒）
+    pub timeout_secs: u64,
+    /// 允许回调的内网主机名或 IP，其余指向内网、回环或链路本地地址的回调地址一律拒绝
+    pub allowed_hosts: Vec<String>,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            secret: String::new(),
+            max_attempts: 5,
+            timeout_secs: 10,
+            allowed_hosts: Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Debug for WebhookSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookSettings")
+            .field("secret", &mask_api_key(&self.secret))
+            .field("max_attempts", &self.max_attempts)
+            .field("timeout_secs", &self.timeout_secs)
+            .field("allowed_hosts", &self.allowed_hosts)
+            .finish()
+    }
+}
+
+/// 翻译记忆设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemorySettings {
+    /// 是否使用翻译记忆（完全匹配直接复用，模糊匹配作为参考）
+    pub enabled: bool,
+    /// 模糊匹配的最低相似度（0-1）
+    pub fuzzy_threshold: f64,
+    /// 每次请求附带的模糊匹配数量上限
+    pub max_references: usize,
+}
+
+impl Default for MemorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            fuzzy_threshold: 0.75,
+            max_references: 3,
+        }
+    }
+}
+
+/// 请求中携带的参数覆盖
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParamOverrides {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub top_p: Option<f32>,
+    pub seed: Option<u64>,
+}
+
+impl ModelParams {
+    /// 合并请求级覆盖：数值本身非法时报错，超出管理员上下限时截断到边界
+    pub fn with_overrides(
+        &self,
+        overrides: &ParamOverrides,
+        limits: &ParamLimits,
+    ) -> AppResult<Self> {
+        let mut params = self.clone();
+
+        if let Some(temperature) = overrides.temperature {
+            if !TEMPERATURE_RANGE.contains(&temperature) {
+                return Err(AppError::Validation(format!(
+                    "temperature 必须在 0-2 之间，当前为 {}",
+                    temperature
+                )));
+            }
+            params.temperature = temperature.clamp(limits.min_temperature, limits.max_temperature);
+        }
+
+        if let Some(max_tokens) = overrides.max_tokens {
+            if max_tokens == 0 {
+                return Err(AppError::Validation("max_tokens 必须大于 0".to_string()));
+            }
+            params.max_tokens = Some(max_tokens.min(limits.max_tokens));
+        }
+
+        if let Some(top_p) = overrides.top_p {
+            if !(top_p > 0.0 && top_p <= 1.0) {
+                return Err(AppError::Validation(format!(
+                    "top_p 必须在 (0, 1] 之间，当前为 {}",
+                    top_p
+                )));
+            }
+            params.top_p = Some(top_p);
+        }
+
+        if overrides.seed.is_some() {
+            params.seed = overrides.seed;
+        }
+
+        Ok(params)
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -10,6 +256,47 @@ pub struct Config {
     pub llm_api_url: String,
     pub llm_model: String,
     pub system_prompt: String,
+    #[serde(default = "default_provider")]
+    pub llm_provider: String,
+    /// 单次 LLM 请求超时时间（秒）
+    #[serde(default = "default_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// 保留的配置历史版本数量
+    #[serde(default = "default_history_limit")]
+    pub config_history_limit: usize,
+    /// 默认模型参数
+    #[serde(default)]
+    pub model_params: ModelParams,
+    /// 请求级参数覆盖的上下限
+    #[serde(default)]
+    pub param_limits: ParamLimits,
+    /// 长文本分块设置
+    #[serde(default)]
+    pub chunking: ChunkSettings,
+    /// 后台任务回调设置
+    #[serde(default)]
+    pub webhooks: WebhookSettings,
+    /// 翻译记忆设置
+    #[serde(default)]
+    pub memory: MemorySettings,
+    /// 命名提示词预设（名称 -> 提示词模板）
+    #[serde(default = "crate::presets::default_presets")]
+    pub prompt_presets: BTreeMap<String, String>,
+    /// 客户端密钥到默认预设名称的映射
+    #[serde(default)]
+    pub client_presets: BTreeMap<String, String>,
+    /// 术语表，命中的条目会通过 `glossary` 变量提供给提示词模板
+    #[serde(default)]
+    pub glossary: Vec<GlossaryEntry>,
+    /// 少样本示例，按语种对和领域选取后作为对话历史插入请求
+    #[serde(default)]
+    pub few_shot_examples: Vec<FewShotExample>,
+    /// 每次请求最多插入的示例数量，0 表示不使用示例
+    #[serde(default = "default_few_shot_count")]
+    pub few_shot_count: usize,
+    /// 外部系统提示词文件，设置后加载时覆盖 `system_prompt`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt_file: Option<String>,
 }
 
 impl Config {
@@ -20,45 +307,447 @@ impl Config {
             llm_api_url: String::new(),
             llm_model: String::new(),
             system_prompt: String::from("你是一个专业的翻译助手。请将用户提供的文本准确、自然地翻译成目标语言。保持原文的语气和风格，确保翻译结果符合目标语言的表达习惯。只返回翻译结果，不要添加额外的解释或说明。"),
+            llm_provider: default_provider(),
+            request_timeout_secs: default_timeout_secs(),
+            config_history_limit: default_history_limit(),
+            model_params: ModelParams::default(),
+            param_limits: ParamLimits::default(),
+            chunking: ChunkSettings::default(),
+            webhooks: WebhookSettings::default(),
+            memory: MemorySettings::default(),
+            prompt_presets: crate::presets::default_presets(),
+            client_presets: BTreeMap::new(),
+            glossary: Vec::new(),
+            few_shot_examples: Vec::new(),
+            few_shot_count: default_few_shot_count(),
+            system_prompt_file: None,
         }
     }
 
-    /// 创建新的配置实例
-    pub fn new(api_url: String, api_key: String, model: String, system_prompt: String) -> Self {
-        Self {
-            llm_api_key: api_key,
-            llm_api_url: api_url,
-            llm_model: model,
-            system_prompt,
+    /// 从配置文件加载
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| ConfigError::io(path, e))?;
+
+        let mut config: Self = serde_json::from_str(&content)?;
+
+        // 外部提示词文件优先于内联的 system_prompt
+        if let Some(prompt_path) = config.system_prompt_path(path) {
+            config.system_prompt =
+                fs::read_to_string(&prompt_path).map_err(|e| ConfigError::io(&prompt_path, e))?;
         }
+
+        Ok(config)
     }
 
-    /// 从配置文件加载
-    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
-        let content = fs::read_to_string(path).map_err(|e| format!("读取配置文件失败: {}", e))?;
+    /// 保存到配置文件
+    ///
+    /// 先写入同目录下的临时文件并刷盘，再重命名覆盖目标文件，避免写到一半的配置被读取。
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let content = serde_json::to_string_pretty(self)?;
 
-        serde_json::from_str(&content).map_err(|e| format!("解析配置文件失败: {}", e))
+        let tmp_path = path.with_extension("json.tmp");
+        let write_tmp = || -> std::io::Result<()> {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        };
+        write_tmp().map_err(|e| ConfigError::io(&tmp_path, e))?;
+
+        if let Err(e) = fs::rename(&tmp_path, path) {
+            // 单文件 bind mount（如 Docker）无法被重命名覆盖，退化为原地写入
+            log::warn!("原子替换 {} 失败（{}），改为原地写入", path.display(), e);
+            let _ = fs::remove_file(&tmp_path);
+            fs::write(path, &content).map_err(|e| ConfigError::io(path, e))?;
+        }
+
+        Ok(())
     }
 
-    /// 保存到配置文件
-    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
-        let content =
-            serde_json::to_string_pretty(self).map_err(|e| format!("序列化配置失败: {}", e))?;
+    /// 加载配置（优先从文件，文件不存在则使用默认配置），并进行校验
+    pub fn load() -> Result<Self, ConfigError> {
+        let config = if Path::new(CONFIG_PATH).exists() {
+            Self::load_from_file(CONFIG_PATH)?
+        } else {
+            Self::default()
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 配置的外部提示词文件，未设置时为 None
+    pub fn prompt_file(&self) -> Option<&str> {
+        self.system_prompt_file.as_deref().filter(|f| !f.is_empty())
+    }
+
+    /// 解析外部提示词文件路径（相对路径以配置文件所在目录为基准）
+    fn system_prompt_path(&self, config_path: &Path) -> Option<PathBuf> {
+        let file = Path::new(self.prompt_file()?);
+        if file.is_absolute() {
+            return Some(file.to_path_buf());
+        }
+        let base = config_path.parent().unwrap_or_else(|| Path::new(""));
+        Some(base.join(file))
+    }
 
-        fs::write(path, content).map_err(|e| format!("写入配置文件失败: {}", e))
+    /// 需要监听变更的文件列表（配置文件本身及其引用的提示词文件）
+    pub fn watched_files(&self, config_path: &Path) -> Vec<PathBuf> {
+        let mut files = vec![config_path.to_path_buf()];
+        files.extend(self.system_prompt_path(config_path));
+        files
     }
 
-    /// 加载配置（优先从文件，文件不存在则使用默认配置）
-    pub fn load() -> Result<Self, String> {
-        let config_path = "config.json";
+    /// 校验配置内容，返回所有字段级错误
+    ///
+    /// API Key、URL、模型全部为空视为尚未配置，不算错误；
+    /// 只要填写了其中之一，三者都必须有效。
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+
+        let llm_touched = !self.llm_api_key.is_empty()
+            || !self.llm_api_url.is_empty()
+            || !self.llm_model.is_empty();
+
+        if llm_touched {
+            if self.llm_api_key.is_empty() {
+                errors.push(FieldError::new("llm_api_key", "不能为空"));
+            } else if self.llm_api_key == "your-api-key-here" {
+                errors.push(FieldError::new(
+                    "llm_api_key",
+                    "请替换示例值为真实的 API Key",
+                ));
+            }
+
+            if let Err(message) = validate_api_url(&self.llm_api_url) {
+                errors.push(FieldError::new("llm_api_url", message));
+            }
+
+            if self.llm_model.trim().is_empty() {
+                errors.push(FieldError::new("llm_model", "不能为空"));
+            } else if self.llm_model.trim() != self.llm_model {
+                errors.push(FieldError::new("llm_model", "首尾不能包含空白字符"));
+            }
+        }
+
+        if !KNOWN_PROVIDERS.contains(&self.llm_provider.as_str()) {
+            errors.push(FieldError::new(
+                "llm_provider",
+                format!(
+                    "未知的服务商 \"{}\"，可选值: {}",
+                    self.llm_provider,
+                    KNOWN_PROVIDERS.join(", ")
+                ),
+            ));
+        }
+
+        if !TIMEOUT_RANGE.contains(&self.request_timeout_secs) {
+            errors.push(FieldError::new(
+                "request_timeout_secs",
+                format!(
+                    "必须在 {}-{} 之间，当前为 {}",
+                    TIMEOUT_RANGE.start(),
+                    TIMEOUT_RANGE.end(),
+                    self.request_timeout_secs
+                ),
+            ));
+        }
+
+        if !HISTORY_LIMIT_RANGE.contains(&self.config_history_limit) {
+            errors.push(FieldError::new(
+                "config_history_limit",
+                format!(
+                    "必须在 {}-{} 之间，当前为 {}",
+                    HISTORY_LIMIT_RANGE.start(),
+                    HISTORY_LIMIT_RANGE.end(),
+                    self.config_history_limit
+                ),
+            ));
+        }
+
+        let chunking = &self.chunking;
+        if !MAX_INPUT_CHARS_RANGE.contains(&chunking.max_input_chars) {
+            errors.push(FieldError::new(
+                "chunking.max_input_chars",
+                format!(
+                    "必须在 {}-{} 之间，当前为 {}",
+                    MAX_INPUT_CHARS_RANGE.start(),
+                    MAX_INPUT_CHARS_RANGE.end(),
+                    chunking.max_input_chars
+                ),
+            ));
+        }
+        if !CHUNK_TOKENS_RANGE.contains(&chunking.max_tokens) {
+            errors.push(FieldError::new(
+                "chunking.max_tokens",
+                format!(
+                    "必须在 {}-{} 之间，当前为 {}",
+                    CHUNK_TOKENS_RANGE.start(),
+                    CHUNK_TOKENS_RANGE.end(),
+                    chunking.max_tokens
+                ),
+            ));
+        }
+        if !CONCURRENCY_RANGE.contains(&chunking.concurrency) {
+            errors.push(FieldError::new(
+                "chunking.concurrency",
+                format!(
+                    "必须在 {}-{} 之间，当前为 {}",
+                    CONCURRENCY_RANGE.start(),
+                    CONCURRENCY_RANGE.end(),
+                    chunking.concurrency
+                ),
+            ));
+        }
+
+        let webhooks = &self.webhooks;
+        if !WEBHOOK_ATTEMPTS_RANGE.contains(&webhooks.max_attempts) {
+            errors.push(FieldError::new(
+                "webhooks.max_attempts",
+                format!(
+                    "必须在 {}-{} 之间，当前为 {}",
+                    WEBHOOK_ATTEMPTS_RANGE.start(),
+                    WEBHOOK_ATTEMPTS_RANGE.end(),
+                    webhooks.max_attempts
+                ),
+            ));
+        }
+        if !WEBHOOK_TIMEOUT_RANGE.contains(&webhooks.timeout_secs) {
+            errors.push(FieldError::new(
+                "webhooks.timeout_secs",
+                format!(
+                    "必须在 {}-{} 之间，当前为 {}",
+                    WEBHOOK_TIMEOUT_RANGE.start(),
+                    WEBHOOK_TIMEOUT_RANGE.end(),
+                    webhooks.timeout_secs
+                ),
+            ));
+        }
+        if let Some(host) = webhooks
+            .allowed_hosts
+            .iter()
+            .find(|host| host.is_empty() || host.contains(char::is_whitespace))
+        {
+            errors.push(FieldError::new(
+                "webhooks.allowed_hosts",
+                format!("主机名不能为空或包含空白字符: \"{}\"", host),
+            ));
+        }
+
+        let memory = &self.memory;
+        if !FUZZY_THRESHOLD_RANGE.contains(&memory.fuzzy_threshold) {
+            errors.push(FieldError::new(
+                "memory.fuzzy_threshold",
+                format!(
+                    "必须在 {}-{} 之间，当前为 {}",
+                    FUZZY_THRESHOLD_RANGE.start(),
+                    FUZZY_THRESHOLD_RANGE.end(),
+                    memory.fuzzy_threshold
+                ),
+            ));
+        }
+        if !MEMORY_REFERENCES_RANGE.contains(&memory.max_references) {
+            errors.push(FieldError::new(
+                "memory.max_references",
+                format!(
+                    "必须在 {}-{} 之间，当前为 {}",
+                    MEMORY_REFERENCES_RANGE.start(),
+                    MEMORY_REFERENCES_RANGE.end(),
+                    memory.max_references
+                ),
+            ));
+        }
+
+        let params = &self.model_params;
+        let limits = &self.param_limits;
+        if !TEMPERATURE_RANGE.contains(&params.temperature) {
+            errors.push(FieldError::new(
+                "model_params.temperature",
+                format!("必须在 0-2 之间，当前为 {}", params.temperature),
+            ));
+        }
+        if params.max_tokens == Some(0) {
+            errors.push(FieldError::new("model_params.max_tokens", "必须大于 0"));
+        }
+        if let Some(top_p) = params.top_p {
+            if !(top_p > 0.0 && top_p <= 1.0) {
+                errors.push(FieldError::new(
+                    "model_params.top_p",
+                    format!("必须在 (0, 1] 之间，当前为 {}", top_p),
+                ));
+            }
+        }
+        if !TEMPERATURE_RANGE.contains(&limits.min_temperature)
+            || !TEMPERATURE_RANGE.contains(&limits.max_temperature)
+            || limits.min_temperature > limits.max_temperature
+        {
+            errors.push(FieldError::new(
+                "param_limits.temperature",
+                format!(
+                    "上下限必须在 0-2 之间且下限不大于上限，当前为 {}-{}",
+                    limits.min_temperature, limits.max_temperature
+                ),
+            ));
+        }
+        if limits.max_tokens == 0 {
+            errors.push(FieldError::new("param_limits.max_tokens", "必须大于 0"));
+        }
+
+        if let Err(message) = crate::prompt::validate_template(&self.system_prompt) {
+            errors.push(FieldError::new("system_prompt", message));
+        }
+
+        for (name, template) in &self.prompt_presets {
+            if let Err(message) = crate::presets::validate_preset_name(name) {
+                errors.push(FieldError::new("prompt_presets", message));
+            }
+            if let Err(message) = crate::prompt::validate_template(template) {
+                errors.push(FieldError::new(
+                    "prompt_presets",
+                    format!("预设 \"{}\": {}", name, message),
+                ));
+            }
+        }
+
+        for message in glossary::validate(&self.glossary) {
+            errors.push(FieldError::new("glossary", message));
+        }
+
+        for message in examples::validate(&self.few_shot_examples) {
+            errors.push(FieldError::new("few_shot_examples", message));
+        }
+        if self.few_shot_count > examples::MAX_EXAMPLES_PER_REQUEST {
+            errors.push(FieldError::new(
+                "few_shot_count",
+                format!(
+                    "必须在 0-{} 之间，当前为 {}",
+                    examples::MAX_EXAMPLES_PER_REQUEST,
+                    self.few_shot_count
+                ),
+            ));
+        }
 
-        if Path::new(config_path).exists() {
-            Self::load_from_file(config_path)
+        for (client, preset) in &self.client_presets {
+            if !self.prompt_presets.contains_key(preset) {
+                errors.push(FieldError::new(
+                    "client_presets",
+                    format!("客户端 \"{}\" 引用了不存在的预设 \"{}\"", client, preset),
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
         } else {
-            Ok(Self::default())
+            Err(ConfigError::Invalid(errors))
         }
     }
 
+    /// 对比两份配置，返回变更字段的描述（API Key 脱敏）
+    pub fn diff(&self, other: &Self) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        if self.llm_api_key != other.llm_api_key {
+            changes.push(format!(
+                "llm_api_key: {} -> {}",
+                mask_api_key(&self.llm_api_key),
+                mask_api_key(&other.llm_api_key)
+            ));
+        }
+
+        let mut compare = |field: &str, old: &str, new: &str| {
+            if old != new {
+                changes.push(format!("{}: {:?} -> {:?}", field, old, new));
+            }
+        };
+        compare("llm_api_url", &self.llm_api_url, &other.llm_api_url);
+        compare("llm_model", &self.llm_model, &other.llm_model);
+        compare("llm_provider", &self.llm_provider, &other.llm_provider);
+        compare(
+            "request_timeout_secs",
+            &self.request_timeout_secs.to_string(),
+            &other.request_timeout_secs.to_string(),
+        );
+        compare(
+            "model_params",
+            &format!("{:?}", self.model_params),
+            &format!("{:?}", other.model_params),
+        );
+        compare(
+            "param_limits",
+            &format!("{:?}", self.param_limits),
+            &format!("{:?}", other.param_limits),
+        );
+        compare(
+            "chunking",
+            &format!("{:?}", self.chunking),
+            &format!("{:?}", other.chunking),
+        );
+        compare(
+            "webhooks",
+            &format!("{:?}", self.webhooks),
+            &format!("{:?}", other.webhooks),
+        );
+        compare(
+            "memory",
+            &format!("{:?}", self.memory),
+            &format!("{:?}", other.memory),
+        );
+        compare(
+            "few_shot_count",
+            &self.few_shot_count.to_string(),
+            &other.few_shot_count.to_string(),
+        );
+        compare(
+            "config_history_limit",
+            &self.config_history_limit.to_string(),
+            &other.config_history_limit.to_string(),
+        );
+        compare(
+            "system_prompt_file",
+            self.system_prompt_file.as_deref().unwrap_or(""),
+            other.system_prompt_file.as_deref().unwrap_or(""),
+        );
+
+        for name in changed_keys(&self.prompt_presets, &other.prompt_presets) {
+            changes.push(format!("prompt_presets.{}: 已变更", name));
+        }
+        for client in changed_keys(&self.client_presets, &other.client_presets) {
+            changes.push(format!(
+                "client_presets.{}: {:?} -> {:?}",
+                client,
+                self.client_presets.get(&client),
+                other.client_presets.get(&client)
+            ));
+        }
+
+        if self.glossary != other.glossary {
+            changes.push(format!(
+                "glossary: {} 条 -> {} 条",
+                self.glossary.len(),
+                other.glossary.len()
+            ));
+        }
+
+        if self.few_shot_examples != other.few_shot_examples {
+            changes.push(format!(
+                "few_shot_examples: {} 条 -> {} 条",
+                self.few_shot_examples.len(),
+                other.few_shot_examples.len()
+            ));
+        }
+
+        if self.system_prompt != other.system_prompt {
+            changes.push(format!(
+                "system_prompt: {} 字符 -> {} 字符",
+                self.system_prompt.chars().count(),
+                other.system_prompt.chars().count()
+            ));
+        }
+
+        changes
+    }
+
     /// 检查配置是否完整
     pub fn is_configured(&self) -> bool {
         !self.llm_api_key.is_empty()
@@ -87,3 +776,131 @@ impl Config {
         &self.system_prompt
     }
 }
+
+/// 两个映射之间新增、删除或值不同的键
+fn changed_keys(old: &BTreeMap<String, String>, new: &BTreeMap<String, String>) -> Vec<String> {
+    let keys: std::collections::BTreeSet<&String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .collect();
+    keys.into_iter().cloned().collect()
+}
+
+/// 校验 API URL：必须是带主机名的 http/https 地址
+pub(crate) fn validate_api_url(url: &str) -> Result<(), String> {
+    if url.is_empty() {
+        return Err("不能为空".to_string());
+    }
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("URL 格式无效 ({}): {}", e, url))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!(
+            "仅支持 http/https 协议，当前为 {}",
+            parsed.scheme()
+        ));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err("缺少主机名".to_string());
+    }
+    Ok(())
+}
+
+/// 脱敏 API Key
+///
+/// 按字符截取首尾各 4 个字符，密钥可能包含非 ASCII 字符
+pub fn mask_api_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 8 {
+        return "***".to_string();
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}...{}", head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_masks_api_key() {
+        let old = Config::default();
+        let mut new = old.clone();
+        new.llm_api_key = "sk-1234567890abcdef".to_string();
+        new.llm_model = "gpt-4o-mini".to_string();
+
+        let changes = old.diff(&new);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0], "llm_api_key: *** -> sk-1...cdef");
+        assert_eq!(changes[1], "llm_model: \"\" -> \"gpt-4o-mini\"");
+        assert!(old.diff(&old.clone()).is_empty());
+
+        // 非 ASCII 的回调密钥按字符脱敏
+        let mut hooked = old.clone();
+        hooked.webhooks.secret = "我的回调签名密钥口令".to_string();
+        let changes = old.diff(&hooked);
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].contains("我的回调...密钥口令"));
+    }
+
+    #[test]
+    fn test_param_overrides_are_clamped_to_limits() {
+        let defaults = ModelParams::default();
+        let limits = ParamLimits {
+            min_temperature: 0.1,
+            max_temperature: 0.8,
+            max_tokens: 1000,
+        };
+
+        let overrides = ParamOverrides {
+            temperature: Some(1.5),
+            max_tokens: Some(5000),
+            top_p: Some(0.9),
+            seed: Some(42),
+        };
+        let params = defaults.with_overrides(&overrides, &limits).unwrap();
+        assert_eq!(params.temperature, 0.8);
+        assert_eq!(params.max_tokens, Some(1000));
+        assert_eq!(params.top_p, Some(0.9));
+        assert_eq!(params.seed, Some(42));
+
+        let params = defaults
+            .with_overrides(&ParamOverrides::default(), &limits)
+            .unwrap();
+        assert_eq!(params, defaults);
+
+        let invalid = ParamOverrides {
+            top_p: Some(0.0),
+            ..Default::default()
+        };
+        assert!(defaults.with_overrides(&invalid, &limits).is_err());
+    }
+
+    #[test]
+    fn test_validate_reports_field_errors() {
+        assert!(Config::default().validate().is_ok());
+
+        let mut config = Config::default();
+        config.llm_api_url = "ftp://example.com".to_string();
+        config.llm_provider = "unknown".to_string();
+        config.request_timeout_secs = 0;
+        config.system_prompt = "翻译为 {{to}}：{{txt}}".to_string();
+
+        let Err(ConfigError::Invalid(errors)) = config.validate() else {
+            panic!("expected validation errors");
+        };
+        let fields: Vec<_> = errors.iter().map(|e| e.field).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "llm_api_key",
+                "llm_api_url",
+                "llm_model",
+                "llm_provider",
+                "request_timeout_secs",
+                "system_prompt"
+            ]
+        );
+        assert!(errors[5].message.contains("{{txt}}"));
+    }
+}
@@ -1,6 +1,8 @@
 // 自定义错误类型模块
 
 use actix_web::HttpResponse;
+use serde::Serialize;
+use std::fmt;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -26,6 +28,69 @@ pub enum AppError {
 
 pub type AppResult<T> = Result<T, AppError>;
 
+/// 配置字段级校验错误
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("读取文件 {path} 失败: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("解析配置文件失败: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("配置校验失败: {}", join_field_errors(.0))]
+    Invalid(Vec<FieldError>),
+}
+
+impl ConfigError {
+    pub fn io(path: impl AsRef<std::path::Path>, source: std::io::Error) -> Self {
+        ConfigError::Io {
+            path: path.as_ref().display().to_string(),
+            source,
+        }
+    }
+
+    /// 字段级错误列表（非校验错误时为空）
+    pub fn field_errors(&self) -> &[FieldError] {
+        match self {
+            ConfigError::Invalid(errors) => errors,
+            _ => &[],
+        }
+    }
+}
+
+fn join_field_errors(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 impl actix_web::ResponseError for AppError {
     fn error_response(&self) -> HttpResponse {
         let status = match self {
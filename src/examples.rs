@@ -0,0 +1,263 @@
+// 少样本示例模块
+
+use crate::admin::save_candidate;
+use crate::config::Config;
+use crate::models::Message;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// 每次请求插入的示例数量上限
+pub const MAX_EXAMPLES_PER_REQUEST: usize = 10;
+
+/// 一条少样本示例（原文/译文对）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FewShotExample {
+    pub id: u64,
+    /// 源语种名称
+    pub from: String,
+    /// 目标语种名称
+    pub to: String,
+    /// 领域，为空时适用于所有领域
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    pub source: String,
+    pub target: String,
+}
+
+/// 选择与待翻译文本最相关的示例
+///
+/// 只考虑语种对一致的示例；指定领域时排除其他领域的示例，同领域示例优先。
+/// 相关度按词（CJK 按字）集合的重合程度计算，得分相同时保持录入顺序。
+pub fn select<'a>(
+    examples: &'a [FewShotExample],
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+    domain: Option<&str>,
+    limit: usize,
+) -> Vec<&'a FewShotExample> {
+    if limit == 0 {
+        return Vec::new();
+    }
+
+    let text_tokens = tokens(text);
+    let mut scored: Vec<(f32, &FewShotExample)> = examples
+        .iter()
+        .filter(|e| e.from == from_lang && e.to == to_lang)
+        .filter(|e| match (domain, e.domain.as_deref()) {
+            (Some(wanted), Some(actual)) => wanted == actual,
+            _ => true,
+        })
+        .map(|e| {
+            let domain_bonus = match (domain, e.domain.as_deref()) {
+                (Some(_), Some(_)) => 1.0,
+                _ => 0.0,
+            };
+            (
+                domain_bonus + similarity(&text_tokens, &tokens(&e.source)),
+                e,
+            )
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().take(limit).map(|(_, e)| e).collect()
+}
+
+/// 把示例转换为对话消息（user 原文 / assistant 译文）
+pub fn to_messages(examples: &[&FewShotExample]) -> Vec<Message> {
+    examples
+        .iter()
+        .flat_map(|e| {
+            [
+                Message {
+                    role: "user".to_string(),
+                    content: e.source.clone(),
+                },
+                Message {
+                    role: "assistant".to_string(),
+                    content: e.target.clone(),
+                },
+            ]
+        })
+        .collect()
+}
+
+/// 校验示例列表，返回错误描述
+pub fn validate(examples: &[FewShotExample]) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut ids = HashSet::new();
+    for e in examples {
+        if !ids.insert(e.id) {
+            errors.push(format!("示例 id {} 重复", e.id));
+        }
+        if [&e.from, &e.to, &e.source, &e.target]
+            .iter()
+            .any(|v| v.trim().is_empty())
+        {
+            errors.push(format!("示例 {} 的语种、原文和译文都不能为空", e.id));
+        }
+    }
+    errors
+}
+
+/// 分词：拉丁字母按单词，其余文字按单个字符
+fn tokens(text: &str) -> HashSet<String> {
+    let mut result = HashSet::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            word.push(c.to_ascii_lowercase());
+            continue;
+        }
+        if !word.is_empty() {
+            result.insert(std::mem::take(&mut word));
+        }
+        if c.is_alphanumeric() {
+            result.insert(c.to_string());
+        }
+    }
+    if !word.is_empty() {
+        result.insert(word);
+    }
+    result
+}
+
+/// Jaccard 相似度
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExampleCreate {
+    pub from: String,
+    pub to: String,
+    pub domain: Option<String>,
+    pub source: String,
+    pub target: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExampleQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub domain: Option<String>,
+}
+
+/// 列出示例，可按语种对和领域过滤
+#[get("/admin/examples")]
+pub async fn list_examples(
+    query: web::Query<ExampleQuery>,
+    config: web::Data<Arc<RwLock<Config>>>,
+) -> HttpResponse {
+    let config = config.read();
+    let examples: Vec<&FewShotExample> = config
+        .few_shot_examples
+        .iter()
+        .filter(|e| query.from.as_ref().is_none_or(|from| &e.from == from))
+        .filter(|e| query.to.as_ref().is_none_or(|to| &e.to == to))
+        .filter(|e| {
+            query
+                .domain
+                .as_ref()
+                .is_none_or(|domain| e.domain.as_ref() == Some(domain))
+        })
+        .collect();
+    HttpResponse::Ok().json(examples)
+}
+
+/// 新增示例
+#[post("/admin/examples")]
+pub async fn create_example(
+    req: HttpRequest,
+    create: web::Json<ExampleCreate>,
+    config: web::Data<Arc<RwLock<Config>>>,
+) -> HttpResponse {
+    let create = create.into_inner();
+    let mut config = config.write();
+    let mut candidate = config.clone();
+    let id = candidate
+        .few_shot_examples
+        .iter()
+        .map(|e| e.id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    candidate.few_shot_examples.push(FewShotExample {
+        id,
+        from: create.from,
+        to: create.to,
+        domain: create.domain.filter(|d| !d.is_empty()),
+        source: create.source,
+        target: create.target,
+    });
+    save_candidate(&req, &mut config, candidate, &format!("新增示例 {}", id))
+}
+
+/// 删除示例
+#[delete("/admin/examples/{id}")]
+pub async fn delete_example(
+    req: HttpRequest,
+    path: web::Path<u64>,
+    config: web::Data<Arc<RwLock<Config>>>,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let mut config = config.write();
+    let mut candidate = config.clone();
+    let before = candidate.few_shot_examples.len();
+    candidate.few_shot_examples.retain(|e| e.id != id);
+    if candidate.few_shot_examples.len() == before {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "message": format!("示例 {} 不存在", id)
+        }));
+    }
+    save_candidate(&req, &mut config, candidate, &format!("删除示例 {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example(id: u64, domain: Option<&str>, source: &str) -> FewShotExample {
+        FewShotExample {
+            id,
+            from: "英语".to_string(),
+            to: "中文(简体)".to_string(),
+            domain: domain.map(str::to_string),
+            source: source.to_string(),
+            target: format!("译文{}", id),
+        }
+    }
+
+    #[test]
+    fn test_select_prefers_domain_and_similarity() {
+        let examples = vec![
+            example(1, None, "Click the Save button"),
+            example(2, Some("legal"), "The parties agree to the terms"),
+            example(3, Some("ui"), "Press the Cancel button"),
+            example(4, None, "Weather is nice today"),
+        ];
+
+        let picked = select(
+            &examples,
+            "Click the Cancel button",
+            "英语",
+            "中文(简体)",
+            Some("ui"),
+            2,
+        );
+        let ids: Vec<u64> = picked.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![3, 1]);
+
+        let picked = select(&examples, "Click Save", "英语", "日语", None, 3);
+        assert!(picked.is_empty());
+    }
+}
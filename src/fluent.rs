@@ -0,0 +1,270 @@
+// Fluent（.ftl）资源文件扫描模块
+//
+// 识别消息（`key = value`）、术语（`-brand = value`）和属性（`.label = value`），
+// 值可以跨越多个缩进的续行。占位符（`{ $name }`、`{ -brand }`）、选择表达式的
+// 语法部分（`{ $count ->`、`[one]`、`*[other]`、结尾的 `}`）和续行的缩进都受保护，
+// 只有各分支中的文字会被翻译。
+
+use crate::error::{AppError, AppResult};
+use crate::resources::{Parsed, Style, Unit};
+use std::ops::Range;
+
+lazy_static::lazy_static! {
+    static ref MESSAGE: regex::Regex =
+        regex::Regex::new(r"^(-?[A-Za-z][A-Za-z0-9_-]*)[ \t]*=[ \t]*").unwrap();
+    static ref ATTRIBUTE: regex::Regex =
+        regex::Regex::new(r"^\.([A-Za-z][A-Za-z0-9_-]*)[ \t]*=[ \t]*").unwrap();
+}
+
+/// 正在读取的消息值或属性值
+struct Pattern {
+    path: Vec<String>,
+    /// 值在原文中的区间，值从下一行开始时起点尚未确定
+    range: Option<Range<usize>>,
+    /// 未闭合的花括号数量
+    depth: i32,
+}
+
+impl Pattern {
+    fn extend(&mut self, start: usize, text: &str) {
+        let range = self.range.get_or_insert(start..start);
+        range.end = start + text.len();
+        self.depth += brace_balance(text);
+    }
+}
+
+/// 扫描 Fluent 文件中的消息、术语和属性的值
+pub fn parse(text: &str) -> AppResult<Parsed> {
+    let mut parsed = Parsed::default();
+    let mut message = String::new();
+    let mut current: Option<Pattern> = None;
+
+    let mut finish = |pattern: Option<Pattern>| {
+        if let Some(Pattern {
+            path,
+            range: Some(range),
+            ..
+        }) = pattern
+        {
+            let value = text[range.clone()].to_string();
+            parsed.units.push(Unit {
+                path,
+                protected: protected_ranges(&value),
+                range,
+                value,
+                style: Style::Fluent,
+            });
+        }
+    };
+
+    let mut offset = 0;
+    for (n, raw) in text.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if line.starts_with([' ', '\t']) {
+            let Some(pattern) = current.as_mut() else {
+                return Err(AppError::Validation(format!(
+                    "Fluent 第 {} 行的缩进内容不属于任何消息",
+                    n + 1
+                )));
+            };
+            let content = line.trim_start();
+            let content_start = start + line.len() - content.len();
+            match ATTRIBUTE.captures(content) {
+                Some(caps) if pattern.depth <= 0 => {
+                    finish(current.take());
+                    let mut pattern = Pattern {
+                        path: vec![message.clone(), caps[1].to_string()],
+                        range: None,
+                        depth: 0,
+                    };
+                    let value = &content[caps[0].len()..];
+                    if !value.is_empty() {
+                        pattern.extend(content_start + caps[0].len(), value);
+                    }
+                    current = Some(pattern);
+                }
+                _ => pattern.extend(content_start, content),
+            }
+            continue;
+        }
+
+        finish(current.take());
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some(caps) = MESSAGE.captures(line) else {
+            return Err(AppError::Validation(format!(
+                "Fluent 第 {} 行无法解析: {}",
+                n + 1,
+                line
+            )));
+        };
+        message = caps[1].to_string();
+        let mut pattern = Pattern {
+            path: vec![message.clone()],
+            range: None,
+            depth: 0,
+        };
+        let value = &line[caps[0].len()..];
+        if !value.is_empty() {
+            pattern.extend(start + caps[0].len(), value);
+        }
+        current = Some(pattern);
+    }
+    finish(current);
+    Ok(parsed)
+}
+
+/// 一行中 `{` 与 `}` 数量之差
+fn brace_balance(text: &str) -> i32 {
+    text.chars()
+        .map(|c| match c {
+            '{' => 1,
+            '}' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// 值中需要保护的区间：占位符、选择表达式的语法部分以及续行的换行和缩进
+fn protected_ranges(value: &str) -> Vec<Range<usize>> {
+    let bytes = value.as_bytes();
+    let mut ranges = Vec::new();
+    let mut selects = 0usize;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\n' => {
+                let start = pos;
+                pos += 1;
+                while pos < bytes.len() && matches!(bytes[pos], b' ' | b'\t' | b'\r') {
+                    pos += 1;
+                }
+                // 选择表达式的分支键
+                let rest = &value[pos..];
+                if selects > 0 && (rest.starts_with('[') || rest.starts_with("*[")) {
+                    if let Some(close) = rest.find(']') {
+                        pos += close + 1;
+                        while pos < bytes.len() && bytes[pos] == b' ' {
+                            pos += 1;
+                        }
+                    }
+                }
+                ranges.push(start..pos);
+            }
+            b'{' => {
+                let Some(end) = matching_brace(value, pos) else {
+                    ranges.push(pos..bytes.len());
+                    break;
+                };
+                match select_arrow(&value[pos + 1..end]) {
+                    Some(arrow) => {
+                        let header_end = pos + 1 + arrow + 2;
+                        ranges.push(pos..header_end);
+                        selects += 1;
+                        pos = header_end;
+                    }
+                    None => {
+                        ranges.push(pos..end + 1);
+                        pos = end + 1;
+                    }
+                }
+            }
+            b'}' if selects > 0 => {
+                ranges.push(pos..pos + 1);
+                selects -= 1;
+                pos += 1;
+            }
+            _ => pos += value[pos..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    ranges
+}
+
+fn matching_brace(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, b) in text.as_bytes()[open..].iter().enumerate() {
+        match b {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 占位符内部（不含嵌套）`->` 的位置，即选择表达式
+fn select_arrow(inner: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let bytes = inner.as_bytes();
+    for i in 0..bytes.len() {
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => depth = depth.saturating_sub(1),
+            b'-' if depth == 0 && bytes.get(i + 1) == Some(&b'>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 译文写回：模型新增的换行需要缩进，否则会被解析为新的消息
+pub fn encode(text: &str) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 || line.is_empty() || line.starts_with([' ', '\t']) {
+                line.to_string()
+            } else {
+                format!("    {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::placeholder::Masked;
+
+    #[test]
+    fn test_parse_fluent() {
+        let text = "# Comment\n-brand = Firefox\nwelcome = Welcome to { -brand }, { $user }!\nemails =\n    { $count ->\n        [one] You have one email.\n       *[other] You have { $count } emails.\n    }\nlogin =\n    .placeholder = Your email\n    .title = Log in\n";
+        let parsed = parse(text).unwrap();
+        let units: Vec<(String, String)> = parsed
+            .units
+            .iter()
+            .map(|u| {
+                let masked = Masked::new(&u.value, 0..u.value.len(), &u.protected);
+                (u.path.join("."), masked.text)
+            })
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                ("-brand".into(), "Firefox".into()),
+                ("welcome".into(), "Welcome to ⟦1⟧, ⟦2⟧!".into()),
+                (
+                    "emails".into(),
+                    "⟦1⟧You have one email.⟦2⟧You have ⟦3⟧ emails.⟦4⟧".into()
+                ),
+                ("login.placeholder".into(), "Your email".into()),
+                ("login.title".into(), "Log in".into()),
+            ]
+        );
+        assert!(parse("  orphan\n").is_err());
+        assert_eq!(encode("第一行\n第二行"), "第一行\n    第二行");
+    }
+}
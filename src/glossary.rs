@@ -0,0 +1,64 @@
+// 术语表模块
+
+use serde::{Deserialize, Serialize};
+
+/// 术语表条目
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlossaryEntry {
+    pub source: String,
+    pub target: String,
+    /// 适用的目标语种，为空时对所有目标语种生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+/// 查找文本中命中的术语（不区分大小写）
+pub fn find_hits<'a>(
+    entries: &'a [GlossaryEntry],
+    text: &str,
+    to_lang: &str,
+) -> Vec<&'a GlossaryEntry> {
+    let text = text.to_lowercase();
+    entries
+        .iter()
+        .filter(|entry| entry.to.as_deref().is_none_or(|to| to == to_lang))
+        .filter(|entry| text.contains(&entry.source.to_lowercase()))
+        .collect()
+}
+
+/// 校验术语表，返回错误描述
+pub fn validate(entries: &[GlossaryEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.source.trim().is_empty() || entry.target.trim().is_empty())
+        .map(|(i, _)| format!("第 {} 条术语的原文和译文都不能为空", i + 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_hits() {
+        let entries = vec![
+            GlossaryEntry {
+                source: "Pull Request".to_string(),
+                target: "拉取请求".to_string(),
+                to: None,
+            },
+            GlossaryEntry {
+                source: "commit".to_string(),
+                target: "コミット".to_string(),
+                to: Some("日语".to_string()),
+            },
+        ];
+
+        let hits = find_hits(&entries, "Open a pull request and commit", "中文(简体)");
+        assert_eq!(hits, vec![&entries[0]]);
+
+        let hits = find_hits(&entries, "commit it", "日语");
+        assert_eq!(hits, vec![&entries[1]]);
+    }
+}
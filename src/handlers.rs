@@ -1,18 +1,46 @@
 // HTTP 处理器模块
 
-use crate::config::Config;
-use crate::error::AppError;
+use crate::alignment;
+use crate::book::{self, BookFormat};
+use crate::config::{validate_api_url, Config};
+use crate::error::{AppError, AppResult};
+use crate::glossary;
 use crate::health;
-use crate::language::select_target_language;
-use crate::models::{TranslateRequest, TranslateResponse};
-use crate::translator::{process_translation_result, translate_with_llm};
-use actix_web::{post, web, HttpResponse};
+use crate::html;
+use crate::jobs::{JobStore, JobTask};
+use crate::language::{
+    detect_language, detection_confidence, language_code, select_target_language,
+};
+use crate::markdown;
+use crate::memory::{self, MemoryMatch};
+use crate::mobile::{self, MobileFormat};
+use crate::models::{
+    DocumentQuery, ExplainResponse, JobRequest, MobileTranslateRequest, OutputMode,
+    PoTranslateRequest, ResourceTranslateRequest, SourceExplanation, TextFormat, TranslateRequest,
+    TranslateResponse,
+};
+use crate::office;
+use crate::placeholder::Masked;
+use crate::po;
+use crate::presets;
+use crate::resources::{self, Previous, ResourceFormat};
+use crate::segments;
+use crate::subtitle::{self, SubtitleFormat};
+use crate::translator::{
+    estimate_tokens, memory_exact, plan_chunks, prepare_llm_request, process_translation_result,
+    translate_long_text, TranslateOptions,
+};
+use crate::variables;
+use crate::webhooks;
+use crate::xliff;
+use actix_web::{post, web, HttpMessage, HttpRequest, HttpResponse};
 use std::sync::Arc;
 use validator::Validate;
 
 /// 翻译接口处理器
 #[post("/translate")]
 pub async fn translate(
+    http_req: HttpRequest,
     req: web::Json<TranslateRequest>,
     config: web::Data<Arc<parking_lot::RwLock<Config>>>,
     app_state: web::Data<health::AppState>,
@@ -30,6 +58,9 @@ pub async fn translate(
             phonetic: None,
             dict: None,
             result: Some(vec![format!("输入验证失败: {}", e)]),
+            pairs: None,
+            alignment: None,
+            placeholder_errors: None,
         };
         return Ok(HttpResponse::Ok().json(validation_response));
     }
@@ -55,26 +86,93 @@ pub async fn translate(
             phonetic: None,
             dict: None,
             result: Some(vec!["翻译服务未配置，请访问 /admin 配置 API 后重试".to_string()]),
+            pairs: None,
+            alignment: None,
+            placeholder_errors: None,
         };
         return Ok(HttpResponse::Ok().json(error_response));
     }
 
-    // 克隆必要的配置数据，避免在 await 点持有锁
-    let (api_url, api_key, model, system_prompt) = {
-        let config_guard = config.read();
-        (
-            config_guard.api_url().to_string(),
-            config_guard.api_key().to_string(),
-            config_guard.model().to_string(),
-            config_guard.system_prompt().to_string(),
-        )
+    // 克隆配置快照，避免在 await 点持有锁
+    let config_clone = config.read().clone();
+
+    if let Err(e) = req.check_length(config_clone.chunking.max_input_chars) {
+        app_state.record_error();
+        let response = TranslateResponse::message(&req.text, from_lang, to_lang, e.to_string());
+        return Ok(HttpResponse::Ok().json(response));
+    }
+
+    // 合并请求级模型参数并选择提示词预设
+    let client_key = presets::client_key(&http_req);
+    let confidence = match req.source {
+        Some(_) => None,
+        None => Some(detection_confidence(&req.text, &from_lang)),
     };
+    let options = match build_options(&config_clone, &req, client_key, confidence) {
+        Ok(options) => options,
+        Err(e) => {
+            app_state.record_error();
+            let response = TranslateResponse::message(&req.text, from_lang, to_lang, e.to_string());
+            return Ok(HttpResponse::Ok().json(response));
+        }
+    };
+
+    // 纯文本中的插值变量替换为占位符，翻译后检查并还原
+    let masked = protect_variables(&req);
 
-    // 调用大模型翻译
-    let config_clone = Config::new(api_url, api_key, model, system_prompt);
-    let response = match translate_with_llm(&config_clone, &req.text, &from_lang, &to_lang).await {
+    // 调用大模型翻译（纯文本按长度自动分块，结构化格式只翻译其中的文字）
+    let translation = match req.format {
+        TextFormat::Text => match &masked {
+            Some(masked) => {
+                variables::translate(&config_clone, masked, &from_lang, &to_lang, &options).await
+            }
+            None => {
+                translate_long_text(&config_clone, &req.text, &from_lang, &to_lang, &options).await
+            }
+        },
+        TextFormat::Markdown => {
+            markdown::translate(&config_clone, &req.text, &from_lang, &to_lang, &options).await
+        }
+        TextFormat::Html => {
+            html::translate(&config_clone, &req.text, &from_lang, &to_lang, &options).await
+        }
+    };
+    let response = match translation {
         Ok(translated) => {
-            let result = process_translation_result(&req.text, &translated);
+            // 先按含占位符的译文排版，避免 pangu 在变量两侧插入空格
+            let (translated, formatted, placeholder_errors) = match &masked {
+                Some(masked) => {
+                    let formatted = process_translation_result(&masked.text, &translated)
+                        .iter()
+                        .map(|line| masked.substitute(line))
+                        .collect();
+                    let errors = masked.problems(&translated);
+                    (
+                        masked.substitute(&translated),
+                        Some(formatted),
+                        (!errors.is_empty()).then_some(errors),
+                    )
+                }
+                None => (translated, None, None),
+            };
+            let (result, pairs) = match req.mode {
+                // 结构化格式整体返回，不再按段落拆分和排版
+                _ if req.format != TextFormat::Text => (vec![translated.clone()], None),
+                OutputMode::Translation => (
+                    formatted.unwrap_or_else(|| process_translation_result(&req.text, &translated)),
+                    None,
+                ),
+                OutputMode::Bilingual => {
+                    let pairs = alignment::paragraph_pairs(&req.text, &translated);
+                    let interleaved = pairs
+                        .iter()
+                        .flat_map(|pair| [pair.source.clone(), pair.target.clone()])
+                        .collect();
+                    (interleaved, Some(pairs))
+                }
+            };
+            let alignment = (req.align && req.format == TextFormat::Text)
+                .then(|| alignment::align_text(&req.text, &translated));
 
             TranslateResponse {
                 text: req.text.clone(),
@@ -85,6 +183,9 @@ pub async fn translate(
                 phonetic: None,
                 dict: None,
                 result: Some(result),
+                pairs,
+                alignment,
+                placeholder_errors,
             }
         }
         Err(e) => {
@@ -100,6 +201,9 @@ pub async fn translate(
                 phonetic: None,
                 dict: None,
                 result: Some(vec![error_message]),
+                pairs: None,
+                alignment: None,
+                placeholder_errors: None,
             }
         }
     };
@@ -107,3 +211,647 @@ pub async fn translate(
     app_state.record_success(start_time.elapsed());
     Ok(HttpResponse::Ok().json(response))
 }
+
+/// 字幕翻译接口：请求体与 `/translate` 相同，`text` 为 SRT 或 WebVTT 内容，返回同一格式的字幕文件
+#[post("/translate/subtitle")]
+pub async fn translate_subtitle(
+    http_req: HttpRequest,
+    req: web::Json<TranslateRequest>,
+    config: web::Data<Arc<parking_lot::RwLock<Config>>>,
+    app_state: web::Data<health::AppState>,
+) -> AppResult<HttpResponse> {
+    let start_time = std::time::Instant::now();
+    app_state.record_request();
+    req.validate()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let config = config.read().clone();
+    if !config.is_configured() {
+        app_state.record_error();
+        return Err(AppError::ServiceNotConfigured);
+    }
+    req.check_length(config.chunking.max_input_chars)?;
+
+    let format = SubtitleFormat::detect(&req.text);
+    let plain = subtitle::plain_text(&req.text, format)?;
+    let (from_lang, to_lang) =
+        select_target_language(&plain, &req.destination, req.source.as_deref());
+    let confidence = match req.source {
+        Some(_) => None,
+        None => Some(detection_confidence(&plain, &from_lang)),
+    };
+    let options = build_options(&config, &req, presets::client_key(&http_req), confidence)?;
+
+    match subtitle::translate(&config, &req.text, format, &from_lang, &to_lang, &options).await {
+        Ok(translated) => {
+            app_state.record_success(start_time.elapsed());
+            Ok(HttpResponse::Ok()
+                .content_type(format.content_type())
+                .body(translated))
+        }
+        Err(e) => {
+            app_state.record_error();
+            Err(e)
+        }
+    }
+}
+
+/// PO/POT 翻译接口：`text` 为 PO 或 POT 文件内容，返回翻译后的 PO 文件
+///
+/// 响应头 `X-Translated-Entries`、`X-Failed-Entries` 分别为翻译成功和失败的条目数。
+#[post("/translate/po")]
+pub async fn translate_po(
+    http_req: HttpRequest,
+    body: web::Json<PoTranslateRequest>,
+    config: web::Data<Arc<parking_lot::RwLock<Config>>>,
+    app_state: web::Data<health::AppState>,
+) -> AppResult<HttpResponse> {
+    let start_time = std::time::Instant::now();
+    app_state.record_request();
+    let req = &body.request;
+    req.validate()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let config = config.read().clone();
+    if !config.is_configured() {
+        app_state.record_error();
+        return Err(AppError::ServiceNotConfigured);
+    }
+    req.check_length(config.chunking.max_input_chars)?;
+
+    let source_text = po::source_text(&req.text)?;
+    let (from_lang, to_lang) =
+        select_target_language(&source_text, &req.destination, req.source.as_deref());
+    let confidence = match req.source {
+        Some(_) => None,
+        None => Some(detection_confidence(&source_text, &from_lang)),
+    };
+    let options = build_options(&config, req, presets::client_key(&http_req), confidence)?;
+    let po_options = po::PoOptions {
+        include_fuzzy: body.include_fuzzy,
+        mark_fuzzy: body.mark_fuzzy,
+    };
+
+    match po::translate(
+        &config, &req.text, &from_lang, &to_lang, &options, po_options,
+    )
+    .await
+    {
+        Ok(output) => {
+            app_state.record_success(start_time.elapsed());
+            Ok(HttpResponse::Ok()
+                .content_type("text/x-gettext-translation; charset=utf-8")
+                .insert_header(("X-Translated-Entries", output.translated.to_string()))
+                .insert_header(("X-Failed-Entries", output.failed.to_string()))
+                .body(output.content))
+        }
+        Err(e) => {
+            app_state.record_error();
+            Err(e)
+        }
+    }
+}
+
+/// 国际化资源文件翻译接口：`text` 为 JSON、YAML 或 Fluent 文件内容，返回翻译后的文件
+///
+/// 同时提供 `previous_source` 和 `previous_target` 时，原文未变化的键沿用上一版译文。
+/// 响应头 `X-Translated-Entries`、`X-Reused-Entries`、`X-Failed-Entries` 分别为
+/// 新翻译、沿用和翻译失败的条目数。
+#[post("/translate/resource")]
+pub async fn translate_resource(
+    http_req: HttpRequest,
+    body: web::Json<ResourceTranslateRequest>,
+    config: web::Data<Arc<parking_lot::RwLock<Config>>>,
+    app_state: web::Data<health::AppState>,
+) -> AppResult<HttpResponse> {
+    let start_time = std::time::Instant::now();
+    app_state.record_request();
+    let req = &body.request;
+    req.validate()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let config = config.read().clone();
+    if !config.is_configured() {
+        app_state.record_error();
+        return Err(AppError::ServiceNotConfigured);
+    }
+    req.check_length(config.chunking.max_input_chars)?;
+
+    let format = body
+        .resource_format
+        .unwrap_or_else(|| ResourceFormat::detect(&req.text));
+    let previous = match (&body.previous_source, &body.previous_target) {
+        (Some(source), Some(target)) => Some(Previous { source, target }),
+        (None, None) => None,
+        _ => {
+            return Err(AppError::Validation(
+                "previous_source 和 previous_target 需要同时提供".to_string(),
+            ))
+        }
+    };
+    let source_text = resources::source_text(&req.text, format)?;
+    let (from_lang, to_lang) =
+        select_target_language(&source_text, &req.destination, req.source.as_deref());
+    let confidence = match req.source {
+        Some(_) => None,
+        None => Some(detection_confidence(&source_text, &from_lang)),
+    };
+    let options = build_options(&config, req, presets::client_key(&http_req), confidence)?;
+
+    match resources::translate(
+        &config, &req.text, format, &from_lang, &to_lang, &options, previous,
+    )
+    .await
+    {
+        Ok(output) => {
+            app_state.record_success(start_time.elapsed());
+            Ok(HttpResponse::Ok()
+                .content_type(format.content_type())
+                .insert_header(("X-Translated-Entries", output.translated.to_string()))
+                .insert_header(("X-Reused-Entries", output.reused.to_string()))
+                .insert_header(("X-Failed-Entries", output.failed.to_string()))
+                .body(output.content))
+        }
+        Err(e) => {
+            app_state.record_error();
+            Err(e)
+        }
+    }
+}
+
+/// 移动端字符串资源翻译接口：`text` 为 Android strings.xml 或 Apple .strings、.stringsdict、
+/// .xcstrings 文件内容，返回可直接放入应用的翻译后文件
+///
+/// 响应头 `X-Translated-Entries`、`X-Failed-Entries` 分别为翻译成功和失败的文案数。
+#[post("/translate/mobile")]
+pub async fn translate_mobile(
+    http_req: HttpRequest,
+    body: web::Json<MobileTranslateRequest>,
+    config: web::Data<Arc<parking_lot::RwLock<Config>>>,
+    app_state: web::Data<health::AppState>,
+) -> AppResult<HttpResponse> {
+    let start_time = std::time::Instant::now();
+    app_state.record_request();
+    let req = &body.request;
+    req.validate()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let config = config.read().clone();
+    if !config.is_configured() {
+        app_state.record_error();
+        return Err(AppError::ServiceNotConfigured);
+    }
+    req.check_length(config.chunking.max_input_chars)?;
+
+    let format = body
+        .mobile_format
+        .unwrap_or_else(|| MobileFormat::detect(&req.text));
+    let source_text = mobile::source_text(&req.text, format)?;
+    let (from_lang, to_lang) =
+        select_target_language(&source_text, &req.destination, req.source.as_deref());
+    let confidence = match req.source {
+        Some(_) => None,
+        None => Some(detection_confidence(&source_text, &from_lang)),
+    };
+    let options = build_options(&config, req, presets::client_key(&http_req), confidence)?;
+
+    match mobile::translate(&config, &req.text, format, &from_lang, &to_lang, &options).await {
+        Ok(output) => {
+            app_state.record_success(start_time.elapsed());
+            Ok(HttpResponse::Ok()
+                .content_type(format.content_type())
+                .insert_header(("X-Translated-Entries", output.translated.to_string()))
+                .insert_header(("X-Failed-Entries", output.failed.to_string()))
+                .body(output.content))
+        }
+        Err(e) => {
+            app_state.record_error();
+            Err(e)
+        }
+    }
+}
+
+/// XLIFF 翻译接口：`text` 为 XLIFF 1.2 或 2.0 文件内容，返回写入译文和状态的 XLIFF 文件
+///
+/// 响应头 `X-Translated-Entries`、`X-Failed-Entries` 分别为翻译成功和失败的单元数。
+#[post("/translate/xliff")]
+pub async fn translate_xliff(
+    http_req: HttpRequest,
+    req: web::Json<TranslateRequest>,
+    config: web::Data<Arc<parking_lot::RwLock<Config>>>,
+    app_state: web::Data<health::AppState>,
+) -> AppResult<HttpResponse> {
+    let start_time = std::time::Instant::now();
+    app_state.record_request();
+    req.validate()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let config = config.read().clone();
+    if !config.is_configured() {
+        app_state.record_error();
+        return Err(AppError::ServiceNotConfigured);
+    }
+    req.check_length(config.chunking.max_input_chars)?;
+
+    let source_text = xliff::source_text(&req.text)?;
+    let (from_lang, to_lang) =
+        select_target_language(&source_text, &req.destination, req.source.as_deref());
+    let confidence = match req.source {
+        Some(_) => None,
+        None => Some(detection_confidence(&source_text, &from_lang)),
+    };
+    let options = build_options(&config, &req, presets::client_key(&http_req), confidence)?;
+
+    match xliff::translate(&config, &req.text, &from_lang, &to_lang, &options).await {
+        Ok(output) => {
+            app_state.record_success(start_time.elapsed());
+            Ok(HttpResponse::Ok()
+                .content_type("application/xliff+xml; charset=utf-8")
+                .insert_header(("X-Translated-Entries", output.translated.to_string()))
+                .insert_header(("X-Failed-Entries", output.failed.to_string()))
+                .body(output.content))
+        }
+        Err(e) => {
+            app_state.record_error();
+            Err(e)
+        }
+    }
+}
+
+/// Office 文档翻译接口：请求体为 DOCX、PPTX 或 XLSX 文件，返回保留格式的翻译后文件
+///
+/// 目标语言等参数通过查询字符串传递，如 `/translate/document?to=中文(简体)`。
+/// 响应头 `X-Translated-Entries`、`X-Failed-Entries` 分别为翻译成功和失败的段落数。
+/// 路由在 main.rs 中注册，单独放宽请求体上限
+pub async fn translate_document(
+    http_req: HttpRequest,
+    query: web::Query<DocumentQuery>,
+    body: web::Bytes,
+    config: web::Data<Arc<parking_lot::RwLock<Config>>>,
+    app_state: web::Data<health::AppState>,
+) -> AppResult<HttpResponse> {
+    let start_time = std::time::Instant::now();
+    app_state.record_request();
+    if body.is_empty() {
+        return Err(AppError::Validation("文档内容不能为空".to_string()));
+    }
+    let source_text = office::source_text(&body)?;
+    let req = query.into_inner().into_request(source_text);
+    req.validate()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let config = config.read().clone();
+    if !config.is_configured() {
+        app_state.record_error();
+        return Err(AppError::ServiceNotConfigured);
+    }
+    req.check_length(config.chunking.max_input_chars)?;
+
+    let (from_lang, to_lang) =
+        select_target_language(&req.text, &req.destination, req.source.as_deref());
+    let confidence = match req.source {
+        Some(_) => None,
+        None => Some(detection_confidence(&req.text, &from_lang)),
+    };
+    let options = build_options(&config, &req, presets::client_key(&http_req), confidence)?;
+
+    match office::translate(&config, &body, &from_lang, &to_lang, &options).await {
+        Ok(output) => {
+            app_state.record_success(start_time.elapsed());
+            let filename = format!(
+                "translated.{}.{}",
+                language_code(&to_lang).unwrap_or("out"),
+                output.format.extension()
+            );
+            Ok(HttpResponse::Ok()
+                .content_type(output.format.content_type())
+                .insert_header((
+                    "Content-Disposition",
+                    format!("attachment; filename=\"{}\"", filename),
+                ))
+                .insert_header(("X-Translated-Entries", output.translated.to_string()))
+                .insert_header(("X-Failed-Entries", output.failed.to_string()))
+                .body(output.content))
+        }
+        Err(e) => {
+            app_state.record_error();
+            Err(e)
+        }
+    }
+}
+
+/// 书籍翻译接口：请求体为 EPUB 或 UTF-8 纯文本文件，作为后台任务翻译
+///
+/// 参数同 Office 文档翻译，`bilingual=true` 时生成双语版本。书籍不受 `chunking.max_input_chars`
+/// 限制。返回 202 和任务信息，通过 `GET /jobs/{id}` 查询进度，`GET /jobs/{id}/result` 下载结果。
+/// 路由在 main.rs 中注册，单独放宽请求体上限
+pub async fn translate_book(
+    http_req: HttpRequest,
+    query: web::Query<DocumentQuery>,
+    body: web::Bytes,
+    config: web::Data<Arc<parking_lot::RwLock<Config>>>,
+    app_state: web::Data<health::AppState>,
+    jobs: web::Data<JobStore>,
+) -> AppResult<HttpResponse> {
+    app_state.record_request();
+    if body.is_empty() {
+        return Err(AppError::Validation("书籍内容不能为空".to_string()));
+    }
+    let format = BookFormat::detect(&body)?;
+    let sample = book::sample_text(&body, format)?;
+    let mut query = query.into_inner();
+    let task = JobTask::Book {
+        format,
+        bilingual: query.bilingual,
+    };
+    let callback_url = query.callback_url.take();
+    let req = query.into_request(sample);
+    let (from_lang, to_lang, options) = prepare_job(
+        &http_req,
+        &req,
+        callback_url.as_deref(),
+        &config,
+        &app_state,
+    )
+    .await?;
+    let info = jobs.submit(task, &body, &from_lang, &to_lang, options, callback_url)?;
+    Ok(HttpResponse::Accepted().json(info))
+}
+
+/// 提交后台任务
+///
+/// JSON 请求体为文本任务，字段同翻译接口（`format` 可为 text、markdown、html），不受长度上限限制；
+/// 其他请求体按文件处理：Office 文档按文档翻译，EPUB 和纯文本按书籍翻译，参数通过查询字符串传递。
+/// 指定 `callback_url` 时，任务完成或失败后向该地址发送回调。
+/// 路由在 main.rs 中注册，单独放宽请求体上限
+pub async fn submit_job(
+    http_req: HttpRequest,
+    query: Option<web::Query<DocumentQuery>>,
+    body: web::Bytes,
+    config: web::Data<Arc<parking_lot::RwLock<Config>>>,
+    app_state: web::Data<health::AppState>,
+    jobs: web::Data<JobStore>,
+) -> AppResult<HttpResponse> {
+    app_state.record_request();
+    if body.is_empty() {
+        return Err(AppError::Validation("任务内容不能为空".to_string()));
+    }
+    let (req, task, callback_url) = if http_req.content_type() == "application/json" {
+        let job: JobRequest = serde_json::from_slice(&body)
+            .map_err(|e| AppError::Validation(format!("请求格式错误: {}", e)))?;
+        let task = JobTask::Text {
+            format: job.request.format,
+        };
+        (job.request, task, job.callback_url)
+    } else {
+        let mut query = query
+            .ok_or_else(|| AppError::Validation("缺少查询参数 to".to_string()))?
+            .into_inner();
+        let (task, sample) = match office::detect(&body) {
+            Some(_) => (JobTask::Document, office::source_text(&body)?),
+            None => {
+                let format = BookFormat::detect(&body)?;
+                let task = JobTask::Book {
+                    format,
+                    bilingual: query.bilingual,
+                };
+                (task, book::sample_text(&body, format)?)
+            }
+        };
+        let callback_url = query.callback_url.take();
+        (query.into_request(sample), task, callback_url)
+    };
+
+    let (from_lang, to_lang, options) = prepare_job(
+        &http_req,
+        &req,
+        callback_url.as_deref(),
+        &config,
+        &app_state,
+    )
+    .await?;
+    // 文本任务的输入为请求中的文本，文件任务为原始文件
+    let input = match task {
+        JobTask::Text { .. } => req.text.as_bytes(),
+        _ => &body,
+    };
+    let info = jobs.submit(task, input, &from_lang, &to_lang, options, callback_url)?;
+    Ok(HttpResponse::Accepted().json(info))
+}
+
+/// 校验任务请求和回调地址，确定源语种、目标语种和翻译选项
+async fn prepare_job(
+    http_req: &HttpRequest,
+    req: &TranslateRequest,
+    callback_url: Option<&str>,
+    config: &Arc<parking_lot::RwLock<Config>>,
+    app_state: &health::AppState,
+) -> AppResult<(String, String, TranslateOptions)> {
+    req.validate()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let config = config.read().clone();
+    if let Some(url) = callback_url {
+        validate_api_url(url).map_err(|e| AppError::Validation(format!("callback_url {}", e)))?;
+        webhooks::check_target(url, &config.webhooks.allowed_hosts)
+            .await
+            .map_err(|e| AppError::Validation(format!("callback_url {}", e)))?;
+    }
+    if !config.is_configured() {
+        app_state.record_error();
+        return Err(AppError::ServiceNotConfigured);
+    }
+
+    let (from_lang, to_lang) =
+        select_target_language(&req.text, &req.destination, req.source.as_deref());
+    let confidence = match req.source {
+        Some(_) => None,
+        None => Some(detection_confidence(&req.text, &from_lang)),
+    };
+    let options = build_options(&config, req, presets::client_key(http_req), confidence)?;
+    Ok((from_lang, to_lang, options))
+}
+
+/// 解释翻译请求：返回语种判定、渲染后的消息和 token 估算，不调用大模型
+#[post("/translate/explain")]
+pub async fn explain(
+    http_req: HttpRequest,
+    req: web::Json<TranslateRequest>,
+    config: web::Data<Arc<parking_lot::RwLock<Config>>>,
+) -> AppResult<HttpResponse> {
+    req.validate()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let config = config.read().clone();
+    req.check_length(config.chunking.max_input_chars)?;
+
+    let detected = detect_language(&req.text);
+    let (from_lang, to_lang) =
+        select_target_language(&req.text, &req.destination, req.source.as_deref());
+    let target_reason = if req.destination[0] == from_lang && req.destination.len() > 1 {
+        format!(
+            "源语种与首要目标语种 {} 相同，改用第二个目标语种",
+            req.destination[0]
+        )
+    } else {
+        "使用首要目标语种".to_string()
+    };
+    let confidence = match req.source {
+        Some(_) => None,
+        None => Some(detection_confidence(&req.text, &from_lang)),
+    };
+
+    let options = build_options(&config, &req, presets::client_key(&http_req), confidence)?;
+    let plan = plan_requests(&config, &req, &from_lang, &to_lang, &options);
+    let mut estimated_tokens = 0;
+    let mut first_request = None;
+    for (text, options) in &plan.requests {
+        let request = prepare_llm_request(&config, text, &from_lang, &to_lang, options)?;
+        estimated_tokens += estimate_tokens(&request);
+        first_request.get_or_insert(request);
+    }
+    let cache = if !config.memory.enabled || memory::global().is_none() {
+        "disabled"
+    } else if plan.reused == 0 {
+        "miss"
+    } else if plan.requests.is_empty() {
+        "hit"
+    } else {
+        "partial"
+    };
+    let (memory_status, memory_matches) = match memory::global() {
+        Some(store) if config.memory.enabled => {
+            match store.exact(&from_lang, &to_lang, &req.text) {
+                Some(target) => (
+                    "exact",
+                    vec![MemoryMatch {
+                        source: req.text.trim().to_string(),
+                        target,
+                        score: 1.0,
+                    }],
+                ),
+                None => {
+                    let matches = store.fuzzy(
+                        &from_lang,
+                        &to_lang,
+                        &req.text,
+                        config.memory.fuzzy_threshold,
+                        config.memory.max_references,
+                    );
+                    (if matches.is_empty() { "none" } else { "fuzzy" }, matches)
+                }
+            }
+        }
+        _ => ("disabled", Vec::new()),
+    };
+
+    Ok(HttpResponse::Ok().json(ExplainResponse {
+        source: SourceExplanation {
+            code: language_code(&from_lang),
+            specified: req.source.is_some(),
+            confidence: detection_confidence(&req.text, &detected),
+            detected,
+            language: from_lang,
+        },
+        glossary: glossary::find_hits(&config.glossary, &req.text, &to_lang),
+        target: to_lang,
+        target_reason,
+        preset: options.preset,
+        domain: options.domain,
+        chunks: plan.requests.len(),
+        estimated_tokens,
+        request: first_request,
+        cache,
+        memory: memory_status,
+        memory_matches,
+        configured: config.is_configured(),
+    }))
+}
+
+/// 按翻译接口的实际路径准备、但不发送的大模型请求
+struct RequestPlan {
+    /// 每个请求的文本及选项
+    requests: Vec<(String, TranslateOptions)>,
+    /// 复用翻译记忆、不会发送请求的分块或片段数
+    reused: usize,
+}
+
+/// 与 `/translate` 相同地切分请求：纯文本按长度分块（含变量时先替换为占位符），
+/// Markdown 和 HTML 按片段分批；翻译记忆完全匹配的部分不发送
+fn plan_requests(
+    config: &Config,
+    req: &TranslateRequest,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> RequestPlan {
+    let segments = match req.format {
+        TextFormat::Text => None,
+        TextFormat::Markdown => Some(markdown::masked_segments(&req.text)),
+        TextFormat::Html => Some(html::masked_segments(&req.text)),
+    };
+    if let Some(segments) = segments {
+        let (requests, reused) =
+            segments::plan_requests(config, &segments, from_lang, to_lang, options);
+        return RequestPlan { requests, reused };
+    }
+
+    let masked = protect_variables(req);
+    let (text, options) = match &masked {
+        Some(masked)
+            if variables::memory_translation(config, masked, from_lang, to_lang).is_some() =>
+        {
+            return RequestPlan {
+                requests: Vec::new(),
+                reused: 1,
+            };
+        }
+        Some(masked) => (
+            masked.text.as_str(),
+            variables::placeholder_options(options),
+        ),
+        None => (req.text.as_str(), options.clone()),
+    };
+    let mut plan = RequestPlan {
+        requests: Vec::new(),
+        reused: 0,
+    };
+    for chunk in plan_chunks(config, text, &options) {
+        if chunk.body.is_empty() {
+            continue;
+        }
+        if memory_exact(config, chunk.body, from_lang, to_lang).is_some() {
+            plan.reused += 1;
+        } else {
+            plan.requests.push((chunk.body.to_string(), chunk.options));
+        }
+    }
+    plan
+}
+
+/// 纯文本请求中含插值变量时，返回替换为占位符后的文本
+fn protect_variables(req: &TranslateRequest) -> Option<Masked> {
+    if req.format != TextFormat::Text {
+        return None;
+    }
+    let ranges = variables::find(&req.text);
+    (!ranges.is_empty()).then(|| Masked::new(&req.text, 0..req.text.len(), &ranges))
+}
+
+/// 根据请求构建翻译选项（模型参数覆盖、提示词预设及模板变量）
+fn build_options(
+    config: &Config,
+    req: &TranslateRequest,
+    client_key: Option<String>,
+    confidence: Option<f32>,
+) -> AppResult<TranslateOptions> {
+    let params = config
+        .model_params
+        .with_overrides(&req.param_overrides(), &config.param_limits)?;
+    let (preset, template) =
+        presets::resolve_prompt(config, req.preset.as_deref(), client_key.as_deref())?;
+
+    Ok(TranslateOptions {
+        params,
+        prompt_template: Some(template.to_string()),
+        domain: req.domain.clone().or_else(|| preset.map(str::to_string)),
+        preset: preset.map(str::to_string),
+        client: client_key,
+        confidence,
+        context: req.context.clone(),
+        previous_text: None,
+        extra_instructions: None,
+    })
+}
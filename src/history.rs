@@ -0,0 +1,261 @@
+// 配置历史版本模块
+
+use crate::config::Config;
+use crate::error::ConfigError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 历史版本目录名（位于配置文件所在目录下）
+const HISTORY_DIR: &str = "config_history";
+
+/// 一个已保存的配置版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigVersion {
+    pub id: String,
+    pub timestamp: String,
+    pub actor: String,
+    pub changes: Vec<String>,
+    pub config: Config,
+}
+
+/// 版本摘要（不包含配置内容，避免泄露 API Key）
+#[derive(Debug, Serialize)]
+pub struct VersionSummary {
+    pub id: String,
+    pub timestamp: String,
+    pub actor: String,
+    pub changes: Vec<String>,
+}
+
+impl From<&ConfigVersion> for VersionSummary {
+    fn from(version: &ConfigVersion) -> Self {
+        Self {
+            id: version.id.clone(),
+            timestamp: version.timestamp.clone(),
+            actor: version.actor.clone(),
+            changes: version.changes.clone(),
+        }
+    }
+}
+
+/// 原子保存新配置并记录历史版本，返回变更字段描述
+///
+/// 历史为空时会先把旧配置记录为初始版本，保证第一次修改也能回滚。
+pub fn commit(
+    config_path: &Path,
+    previous: &Config,
+    new: &Config,
+    actor: &str,
+) -> Result<Vec<String>, ConfigError> {
+    new.save_to_file(config_path)?;
+    record(config_path, previous, new, actor)
+}
+
+/// 记录一次已生效的配置变更（不写配置文件本身）
+pub fn record(
+    config_path: &Path,
+    previous: &Config,
+    new: &Config,
+    actor: &str,
+) -> Result<Vec<String>, ConfigError> {
+    let changes = previous.diff(new);
+    let dir = history_dir(config_path);
+    fs::create_dir_all(&dir).map_err(|e| ConfigError::io(&dir, e))?;
+
+    if list(config_path)?.is_empty() {
+        write_version(&dir, previous, "initial", Vec::new())?;
+    }
+    write_version(&dir, new, actor, changes.clone())?;
+    prune(&dir, new.config_history_limit)?;
+
+    Ok(changes)
+}
+
+/// 列出所有历史版本，按时间从新到旧排列
+pub fn list(config_path: &Path) -> Result<Vec<ConfigVersion>, ConfigError> {
+    let dir = history_dir(config_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut versions = Vec::new();
+    for path in version_files(&dir)? {
+        match read_version(&path) {
+            Ok(version) => versions.push(version),
+            Err(e) => log::warn!("跳过无法读取的历史版本: {}", e),
+        }
+    }
+    versions.sort_by(|a, b| version_key(&b.id).cmp(&version_key(&a.id)));
+    Ok(versions)
+}
+
+/// 读取指定版本
+pub fn get(config_path: &Path, id: &str) -> Result<Option<ConfigVersion>, ConfigError> {
+    // 版本号只包含数字和连字符，拒绝路径穿越
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit() || c == '-') {
+        return Ok(None);
+    }
+    let path = history_dir(config_path).join(format!("{}.json", id));
+    if !path.exists() {
+        return Ok(None);
+    }
+    read_version(&path).map(Some)
+}
+
+fn history_dir(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(HISTORY_DIR)
+}
+
+fn write_version(
+    dir: &Path,
+    config: &Config,
+    actor: &str,
+    changes: Vec<String>,
+) -> Result<(), ConfigError> {
+    // 版本号用 UTC 时间生成，本地时间在夏令时回拨时会乱序；本地时间只用于展示
+    let now = chrono::Utc::now();
+    let mut id = now.format("%Y%m%d%H%M%S%3f").to_string();
+    // 同一毫秒内多次写入时追加序号
+    let mut seq = 1;
+    while dir.join(format!("{}.json", id)).exists() {
+        id = format!("{}-{}", now.format("%Y%m%d%H%M%S%3f"), seq);
+        seq += 1;
+    }
+
+    let version = ConfigVersion {
+        id: id.clone(),
+        timestamp: now.with_timezone(&chrono::Local).to_rfc3339(),
+        actor: actor.to_string(),
+        changes,
+        config: config.clone(),
+    };
+    let path = dir.join(format!("{}.json", id));
+    let content = serde_json::to_string_pretty(&version)?;
+
+    // 先写临时文件再重命名，避免留下写到一半的版本文件
+    let tmp_path = path.with_extension("json.tmp");
+    let write_tmp = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    };
+    write_tmp().map_err(|e| ConfigError::io(&tmp_path, e))?;
+    fs::rename(&tmp_path, &path).map_err(|e| ConfigError::io(&path, e))
+}
+
+/// 版本号的排序键：时间戳及同一毫秒内的序号
+fn version_key(id: &str) -> (&str, u32) {
+    match id.split_once('-') {
+        Some((timestamp, seq)) => (timestamp, seq.parse().unwrap_or(0)),
+        None => (id, 0),
+    }
+}
+
+fn read_version(path: &Path) -> Result<ConfigVersion, ConfigError> {
+    let content = fs::read_to_string(path).map_err(|e| ConfigError::io(path, e))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn version_files(dir: &Path) -> Result<Vec<PathBuf>, ConfigError> {
+    let entries = fs::read_dir(dir).map_err(|e| ConfigError::io(dir, e))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect())
+}
+
+/// 删除超出保留数量的最旧版本
+fn prune(dir: &Path, limit: usize) -> Result<(), ConfigError> {
+    let mut files = version_files(dir)?;
+    if files.len() <= limit {
+        return Ok(());
+    }
+    files.sort_by_cached_key(|path| {
+        let id = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("");
+        let (timestamp, seq) = version_key(id);
+        (timestamp.to_string(), seq)
+    });
+    for path in &files[..files.len() - limit] {
+        fs::remove_file(path).map_err(|e| ConfigError::io(path, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_key_orders_same_millisecond_writes() {
+        let mut ids = vec![
+            "20250101120000000-10",
+            "20250101120000000",
+            "20250101120000001",
+            "20250101120000000-2",
+        ];
+        ids.sort_by_key(|id| version_key(id));
+        assert_eq!(
+            ids,
+            vec![
+                "20250101120000000",
+                "20250101120000000-2",
+                "20250101120000000-10",
+                "20250101120000001",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_list_prune_and_rollback() {
+        let dir = std::env::temp_dir().join(format!("rustrans-history-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("config.json");
+
+        let mut config = Config::default();
+        config.config_history_limit = 3;
+        for i in 1..=4 {
+            let mut new = config.clone();
+            new.llm_model = format!("model-{}", i);
+            commit(&config_path, &config, &new, "tester").unwrap();
+            config = new;
+        }
+
+        // 初始版本加 4 次修改，只保留最新的 3 个
+        let versions = list(&config_path).unwrap();
+        let kept: Vec<&str> = versions
+            .iter()
+            .map(|v| v.config.llm_model.as_str())
+            .collect();
+        assert_eq!(kept, vec!["model-4", "model-3", "model-2"]);
+        assert_eq!(
+            versions[0].changes,
+            vec!["llm_model: \"model-3\" -> \"model-4\""]
+        );
+        assert!(fs::read_dir(history_dir(&config_path)).unwrap().all(|e| e
+            .unwrap()
+            .path()
+            .extension()
+            .unwrap()
+            == "json"));
+
+        // 回滚即以历史版本的配置再提交一次
+        let target = get(&config_path, &versions[2].id).unwrap().unwrap();
+        commit(&config_path, &config, &target.config, "rollback").unwrap();
+        assert_eq!(
+            Config::load_from_file(&config_path).unwrap().llm_model,
+            "model-2"
+        );
+        assert_eq!(list(&config_path).unwrap()[0].actor, "rollback");
+        assert!(get(&config_path, "../config").unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,315 @@
+// HTML 翻译模块
+//
+// 适用于 CMS 内容和邮件模板等 HTML 片段：
+// - 块级元素之间的文字各自作为一个片段，片段内的行内标签和字符实体替换为占位符，
+//   因此标签不会被丢弃或调换到片段之外；
+// - `alt`、`title`、`placeholder`、`aria-label` 属性值单独翻译；
+// - `<script>`、`<style>`、`<pre>`、`<code>` 等元素以及 `translate="no"` 的元素不翻译；
+// - 译文中的 `<`、`&` 等字符会被转义，其余标记逐字节保留。
+
+use crate::config::Config;
+use crate::error::AppResult;
+use crate::markup::{self, Token, TokenKind};
+use crate::placeholder::Masked;
+use crate::segments::translate_segments;
+use crate::translator::TranslateOptions;
+use std::ops::Range;
+
+/// 内容按原始文本处理的元素
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea"];
+
+/// 内容不翻译的元素
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "pre", "textarea", "svg", "math"];
+
+/// 没有结束标签的空元素，不带 `/>` 时同样不会开启不翻译区域
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// 行内元素：作为占位符保留在所在片段中
+const INLINE_ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "br", "cite", "data", "del", "dfn", "em", "font", "i", "img",
+    "ins", "label", "mark", "q", "s", "small", "span", "strong", "sub", "sup", "time", "u", "wbr",
+];
+
+/// 整个元素（含内容）作为一个占位符的行内元素
+const OPAQUE_INLINE_ELEMENTS: &[&str] = &["code", "kbd", "samp", "var"];
+
+/// 需要翻译的属性
+const TRANSLATABLE_ATTRS: &[&str] = &["alt", "title", "placeholder", "aria-label"];
+
+/// 翻译 HTML 片段
+pub async fn translate(
+    config: &Config,
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<String> {
+    // 先翻译属性值，再在替换后的文档上翻译文字，避免两类片段的区间重叠
+    let attrs = attribute_segments(text);
+    let text = if attrs.is_empty() {
+        text.to_string()
+    } else {
+        let masked = mask_attributes(text, &attrs);
+        let translated = translate_segments(config, &masked, from_lang, to_lang, options).await?;
+        markup::apply_edits(text, attrs.into_iter().zip(translated).collect())
+    };
+
+    let segments = text_segments(&text);
+    let masked = mask_text(&text, &segments);
+    let translated = translate_segments(config, &masked, from_lang, to_lang, options).await?;
+    Ok(markup::apply_edits(
+        &text,
+        segments
+            .into_iter()
+            .map(|(range, _)| range)
+            .zip(translated)
+            .collect(),
+    ))
+}
+
+/// 文档中所有待译片段（属性值在前，文字在后），不调用大模型时用于解释翻译请求
+pub fn masked_segments(text: &str) -> Vec<Masked> {
+    let mut masked = mask_attributes(text, &attribute_segments(text));
+    masked.extend(mask_text(text, &text_segments(text)));
+    masked
+}
+
+fn mask_attributes(text: &str, attrs: &[Range<usize>]) -> Vec<Masked> {
+    attrs
+        .iter()
+        .map(|range| mask(text, range.clone(), markup::escape_attr))
+        .collect()
+}
+
+fn mask_text(text: &str, segments: &[(Range<usize>, Vec<Range<usize>>)]) -> Vec<Masked> {
+    segments
+        .iter()
+        .map(|(range, protected)| {
+            Masked::new(text, range.clone(), protected).with_escape(markup::escape_text)
+        })
+        .collect()
+}
+
+/// 属性值中的字符实体同样需要保护
+fn mask(text: &str, range: Range<usize>, escape: fn(&str) -> String) -> Masked {
+    let entities = markup::entity_ranges(text, range.clone());
+    Masked::new(text, range, &entities).with_escape(escape)
+}
+
+/// 处于不翻译区域时记录元素名和嵌套深度
+struct Skip {
+    name: String,
+    depth: usize,
+}
+
+impl Skip {
+    /// 判断开始标签是否进入不翻译区域
+    fn enter(token: &Token, text: &str) -> Option<Self> {
+        let TokenKind::Start {
+            name, self_closing, ..
+        } = &token.kind
+        else {
+            return None;
+        };
+        let has_content = !self_closing && !VOID_ELEMENTS.contains(&name.as_str());
+        ((SKIPPED_ELEMENTS.contains(&name.as_str()) || no_translate(token, text)) && has_content)
+            .then(|| Skip {
+                name: name.clone(),
+                depth: 1,
+            })
+    }
+
+    /// 处理不翻译区域内的标签，返回是否已离开该区域
+    fn update(&mut self, token: &Token) -> bool {
+        match &token.kind {
+            TokenKind::Start {
+                name, self_closing, ..
+            } if *name == self.name && !self_closing => self.depth += 1,
+            TokenKind::End { name } if *name == self.name => self.depth -= 1,
+            _ => {}
+        }
+        self.depth == 0
+    }
+}
+
+/// 标签是否带有 `translate="no"`
+fn no_translate(token: &Token, text: &str) -> bool {
+    token
+        .attr("translate")
+        .is_some_and(|v| text[v].eq_ignore_ascii_case("no"))
+}
+
+/// 需要翻译的属性值区间
+fn attribute_segments(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut skip: Option<Skip> = None;
+    for token in markup::tokenize(text, RAW_TEXT_ELEMENTS) {
+        if let Some(s) = &mut skip {
+            if s.update(&token) {
+                skip = None;
+            }
+            continue;
+        }
+        skip = Skip::enter(&token, text);
+        if skip.is_some() || no_translate(&token, text) {
+            continue;
+        }
+        for name in TRANSLATABLE_ATTRS {
+            if let Some(value) = token.attr(name).filter(|v| !v.is_empty()) {
+                ranges.push(value);
+            }
+        }
+    }
+    ranges
+}
+
+/// 需要翻译的文字片段：`(区间, 受保护的区间)`
+fn text_segments(text: &str) -> Vec<(Range<usize>, Vec<Range<usize>>)> {
+    let mut segments = Vec::new();
+    let mut current: Option<(Range<usize>, Vec<Range<usize>>)> = None;
+    let mut skip: Option<Skip> = None;
+    let mut opaque: Option<Skip> = None;
+
+    let finish = |segments: &mut Vec<_>, current: Option<(Range<usize>, Vec<Range<usize>>)>| {
+        if let Some((range, protected)) = current {
+            if let Some(trimmed) = trim_range(text, range) {
+                segments.push((trimmed, protected));
+            }
+        }
+    };
+
+    for token in markup::tokenize(text, RAW_TEXT_ELEMENTS) {
+        let range = token.range.clone();
+
+        // 行内代码等整体保护的元素，直到对应的结束标签
+        if let Some(o) = &mut opaque {
+            let done = o.update(&token);
+            if let Some((segment, protected)) = &mut current {
+                segment.end = range.end;
+                protected.push(range);
+            }
+            if done {
+                opaque = None;
+            }
+            continue;
+        }
+        if let Some(s) = &mut skip {
+            if s.update(&token) {
+                skip = None;
+            }
+            continue;
+        }
+
+        let inline = match &token.kind {
+            TokenKind::Text | TokenKind::Comment => true,
+            TokenKind::Start { name, .. } | TokenKind::End { name } => {
+                INLINE_ELEMENTS.contains(&name.as_str())
+                    || OPAQUE_INLINE_ELEMENTS.contains(&name.as_str())
+            }
+            _ => false,
+        };
+        let entering_skip = Skip::enter(&token, text);
+        if !inline {
+            finish(&mut segments, current.take());
+            skip = entering_skip;
+            continue;
+        }
+
+        let (segment, protected) = current.get_or_insert_with(|| (range.clone(), Vec::new()));
+        segment.end = range.end;
+        if entering_skip.is_some() {
+            // 不翻译的行内元素整体保护，不打断所在片段
+            opaque = entering_skip;
+            protected.push(range);
+            continue;
+        }
+        match &token.kind {
+            TokenKind::Text => protected.extend(markup::entity_ranges(text, range)),
+            TokenKind::Start {
+                name, self_closing, ..
+            } => {
+                if OPAQUE_INLINE_ELEMENTS.contains(&name.as_str()) && !self_closing {
+                    opaque = Some(Skip {
+                        name: name.clone(),
+                        depth: 1,
+                    });
+                }
+                protected.push(range);
+            }
+            _ => protected.push(range),
+        }
+    }
+    finish(&mut segments, current);
+    segments
+}
+
+/// 去掉区间首尾的空白，全为空白时返回 None
+fn trim_range(text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let slice = &text[range.clone()];
+    let trimmed = slice.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let start = range.start + (slice.len() - slice.trim_start().len());
+    Some(start..start + trimmed.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn masked(text: &str) -> Vec<String> {
+        text_segments(text)
+            .iter()
+            .map(|(range, protected)| Masked::new(text, range.clone(), protected).text)
+            .collect()
+    }
+
+    #[test]
+    fn test_text_segments() {
+        let text = "<div>\n  <h1>Hello &amp; <em>welcome</em></h1>\n  <p>Run <code>a <b>b</b></code> now.<br>Next line</p>\n  <script>var s = \"x\";</script>\n  <p translate=\"no\">Brand <b>Name</b></p>\n  <p>By <span translate=\"no\">Acme <b>Inc</b></span>.</p>\n</div>";
+        assert_eq!(
+            masked(text),
+            vec![
+                "Hello ⟦1⟧ ⟦2⟧welcome⟦3⟧",
+                "Run ⟦1⟧ now.⟦2⟧Next line",
+                "By ⟦1⟧."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attribute_segments() {
+        let text = "<img src=\"a.png\" alt=\"A cat\"><a title='Home page' href=\"/\">Home</a><pre title=\"x\"><i title=\"y\">z</i></pre>";
+        let values: Vec<&str> = attribute_segments(text)
+            .into_iter()
+            .map(|r| &text[r])
+            .collect();
+        assert_eq!(values, vec!["A cat", "Home page"]);
+    }
+
+    #[test]
+    fn test_void_element_with_translate_no() {
+        let text = "<p>Logo <img translate=\"no\" alt=\"Acme\"> here</p><input translate=\"no\" placeholder=\"Name\"><p title=\"Tip\">Still translated</p>";
+        assert_eq!(masked(text), vec!["Logo ⟦1⟧ here", "Still translated"]);
+        let values: Vec<&str> = attribute_segments(text)
+            .into_iter()
+            .map(|r| &text[r])
+            .collect();
+        assert_eq!(values, vec!["Tip"]);
+    }
+
+    #[test]
+    fn test_restore_escapes_translation() {
+        let text = "<p>Save <b>now</b></p>";
+        let (range, protected) = text_segments(text).remove(0);
+        let masked = Masked::new(text, range, &protected).with_escape(markup::escape_text);
+        assert_eq!(
+            masked.restore("立即 ⟦1⟧保存 & 退出⟦2⟧").unwrap(),
+            "立即 <b>保存 &amp; 退出</b>"
+        );
+    }
+}
@@ -0,0 +1,855 @@
+// 后台任务模块
+//
+// 长文本、整本书和 Office 文档等耗时较长的翻译在后台执行：提交后立即返回任务 ID，
+// 客户端通过 `GET /jobs/{id}` 查询进度，完成后通过 `GET /jobs/{id}/result` 下载结果。
+//
+// 任务按提交顺序逐个执行。大模型请求与单次翻译共用进程级的并发许可（`chunking.concurrency`），
+// 因此后台任务与同时进行的翻译请求合计不会超过该上限。任务记录、输入和结果保存在
+// jobs 目录中，服务重启后未完成的任务重新排队执行。提交时指定了回调地址的任务，
+// 完成或失败后通过 [`Webhooks`] 通知；回调投递结束前任务记录标记为待回调，
+// 服务重启后重新投递。
+
+use crate::book::{self, Book, BookFormat};
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::health::AppState;
+use crate::html;
+use crate::language::language_code;
+use crate::markdown;
+use crate::models::TextFormat;
+use crate::office;
+use crate::segments;
+use crate::translator::{self, TranslateOptions};
+use crate::webhooks::Webhooks;
+use actix_web::{get, post, web, HttpResponse};
+use futures::future::{AbortHandle, Abortable};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// 任务目录（位于工作目录下）
+pub const JOBS_DIR: &str = "jobs";
+
+/// 保留的已结束任务数量，超出时删除最早的任务及其文件
+const MAX_FINISHED_JOBS: usize = 200;
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+/// 任务内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum JobTask {
+    /// 纯文本、Markdown 或 HTML 文本
+    Text { format: TextFormat },
+    /// EPUB 或纯文本书籍
+    Book { format: BookFormat, bilingual: bool },
+    /// Office 文档
+    Document,
+}
+
+impl JobTask {
+    fn kind(&self) -> &'static str {
+        match self {
+            JobTask::Text { .. } => "text",
+            JobTask::Book { .. } => "book",
+            JobTask::Document => "document",
+        }
+    }
+}
+
+/// 任务信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: String,
+    /// 任务类型：`text`、`book`、`document`
+    pub kind: String,
+    pub status: JobStatus,
+    /// 源语种
+    pub from: String,
+    /// 目标语种
+    pub to: String,
+    /// 已完成的单元数（书籍为章节数，长文本为分节数，Office 文档和 Markdown/HTML 为片段数）
+    pub done: usize,
+    /// 单元总数，开始执行前为 0
+    pub total: usize,
+    /// 已消耗的 token 数（接口未返回用量时为估算值）
+    pub tokens: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
+
+/// 任务结果
+pub struct JobOutput {
+    pub content: Vec<u8>,
+    pub content_type: &'static str,
+    pub filename: String,
+}
+
+/// 结果文件的响应信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OutputMeta {
+    content_type: String,
+    filename: String,
+}
+
+/// 保存到磁盘的任务记录
+#[derive(Debug, Serialize, Deserialize)]
+struct JobRecord {
+    info: JobInfo,
+    task: JobTask,
+    /// 提交时确定的翻译选项，重启后按原选项执行
+    options: TranslateOptions,
+    /// 任务结束后通知的回调地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    callback_url: Option<String>,
+    /// 回调尚未投递结束，重启后重新投递
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    callback_pending: bool,
+    #[serde(default)]
+    output: Option<OutputMeta>,
+}
+
+struct Job {
+    record: JobRecord,
+    tokens: Arc<AtomicU64>,
+    abort: Option<AbortHandle>,
+}
+
+impl Job {
+    fn info(&self) -> JobInfo {
+        let mut info = self.record.info.clone();
+        info.tokens = self.tokens.load(Ordering::Relaxed);
+        info
+    }
+}
+
+/// 任务登记表，所有 worker 共享
+#[derive(Clone)]
+pub struct JobStore {
+    dir: PathBuf,
+    jobs: Arc<Mutex<HashMap<String, Job>>>,
+    sequence: Arc<AtomicU64>,
+    queue: mpsc::UnboundedSender<String>,
+    receiver: Arc<Mutex<Option<mpsc::UnboundedReceiver<String>>>>,
+}
+
+impl JobStore {
+    /// 打开任务目录，加载已有任务；未完成的任务和未投递完的回调重新排队
+    pub fn open(dir: &Path) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let (queue, receiver) = mpsc::unbounded_channel();
+        let store = JobStore {
+            dir: dir.to_path_buf(),
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            sequence: Arc::new(AtomicU64::new(0)),
+            queue,
+            receiver: Arc::new(Mutex::new(Some(receiver))),
+        };
+
+        let mut records = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            // 写入中途退出时残留的临时文件
+            if path.extension().is_some_and(|ext| ext == "tmp") {
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match fs::read_to_string(&path)
+                .map_err(AppError::from)
+                .and_then(|content| Ok(serde_json::from_str::<JobRecord>(&content)?))
+            {
+                Ok(record) => records.push(record),
+                Err(e) => log::warn!("跳过无法读取的任务 {}: {}", path.display(), e),
+            }
+        }
+        records.sort_by(|a, b| a.info.id.cmp(&b.info.id));
+
+        let (mut resumed, mut callbacks) = (0, 0);
+        for mut record in records {
+            let id = record.info.id.clone();
+            if !record.info.status.is_finished() {
+                record.info.status = JobStatus::Queued;
+                record.info.done = 0;
+                record.info.total = 0;
+                resumed += 1;
+                let _ = store.queue.send(id.clone());
+            } else if record.callback_pending {
+                // 已结束的任务不会再执行，worker 取出后直接投递回调
+                callbacks += 1;
+                let _ = store.queue.send(id.clone());
+            }
+            let tokens = Arc::new(AtomicU64::new(record.info.tokens));
+            store.jobs.lock().insert(
+                id,
+                Job {
+                    record,
+                    tokens,
+                    abort: None,
+                },
+            );
+        }
+        if resumed > 0 {
+            log::info!("恢复 {} 个未完成的后台任务", resumed);
+        }
+        if callbacks > 0 {
+            log::info!("重新投递 {} 个未完成的任务回调", callbacks);
+        }
+        store.prune(MAX_FINISHED_JOBS);
+        Ok(store)
+    }
+
+    /// 提交任务，输入写入任务目录后排队
+    pub fn submit(
+        &self,
+        task: JobTask,
+        input: &[u8],
+        from_lang: &str,
+        to_lang: &str,
+        options: TranslateOptions,
+        callback_url: Option<String>,
+    ) -> AppResult<JobInfo> {
+        // 任务号按 UTC 生成以便排序，本地时间只用于展示
+        let now = chrono::Utc::now();
+        let id = format!(
+            "{}-{}",
+            now.format("%Y%m%d%H%M%S%3f"),
+            self.sequence.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        write_atomic(&self.path(&id, "input"), input)?;
+        let record = JobRecord {
+            info: JobInfo {
+                id: id.clone(),
+                kind: task.kind().to_string(),
+                status: JobStatus::Queued,
+                from: from_lang.to_string(),
+                to: to_lang.to_string(),
+                done: 0,
+                total: 0,
+                tokens: 0,
+                error: None,
+                created_at: now.with_timezone(&chrono::Local).to_rfc3339(),
+                finished_at: None,
+            },
+            task,
+            options,
+            callback_url,
+            callback_pending: false,
+            output: None,
+        };
+        self.save(&record)?;
+        let info = record.info.clone();
+        self.jobs.lock().insert(
+            id.clone(),
+            Job {
+                record,
+                tokens: Arc::new(AtomicU64::new(0)),
+                abort: None,
+            },
+        );
+        let _ = self.queue.send(id);
+        Ok(info)
+    }
+
+    pub fn info(&self, id: &str) -> Option<JobInfo> {
+        self.jobs.lock().get(id).map(Job::info)
+    }
+
+    /// 读取已完成任务的结果
+    pub fn output(&self, id: &str) -> AppResult<Option<JobOutputFile>> {
+        let Some(meta) = self
+            .jobs
+            .lock()
+            .get(id)
+            .and_then(|job| job.record.output.clone())
+        else {
+            return Ok(None);
+        };
+        Ok(Some(JobOutputFile {
+            content: fs::read(self.path(id, "output"))?,
+            content_type: meta.content_type,
+            filename: meta.filename,
+        }))
+    }
+
+    /// 取消排队中或执行中的任务，返回取消后的任务信息；任务不存在时返回 None
+    pub fn cancel(&self, id: &str) -> Option<JobInfo> {
+        let mut jobs = self.jobs.lock();
+        let job = jobs.get_mut(id)?;
+        if !job.record.info.status.is_finished() {
+            job.record.info.status = JobStatus::Cancelled;
+            job.record.info.finished_at = Some(chrono::Local::now().to_rfc3339());
+            if let Some(abort) = job.abort.take() {
+                abort.abort();
+            }
+            job.record.info.tokens = job.tokens.load(Ordering::Relaxed);
+            if let Err(e) = self.save(&job.record) {
+                log::warn!("保存任务 {} 失败: {}", id, e);
+            }
+        }
+        Some(job.info())
+    }
+
+    /// 启动执行任务的 worker，只应调用一次
+    pub fn spawn_worker(
+        &self,
+        config: Arc<RwLock<Config>>,
+        app_state: AppState,
+        webhooks: Webhooks,
+    ) {
+        let store = self.clone();
+        let mut receiver = self
+            .receiver
+            .lock()
+            .take()
+            .expect("任务 worker 只能启动一次");
+        actix_web::rt::spawn(async move {
+            while let Some(id) = receiver.recv().await {
+                store.run(&id, &config, &app_state).await;
+                store.notify(&id, &config, &webhooks);
+                store.prune(MAX_FINISHED_JOBS);
+            }
+        });
+    }
+
+    /// 执行一个任务
+    async fn run(&self, id: &str, config: &Arc<RwLock<Config>>, app_state: &AppState) {
+        let (abort, registration) = AbortHandle::new_pair();
+        let (task, options, from_lang, to_lang, tokens) = {
+            let mut jobs = self.jobs.lock();
+            let Some(job) = jobs
+                .get_mut(id)
+                .filter(|job| job.record.info.status == JobStatus::Queued)
+            else {
+                return;
+            };
+            job.record.info.status = JobStatus::Running;
+            job.abort = Some(abort);
+            let record = &job.record;
+            (
+                record.task.clone(),
+                record.options.clone(),
+                record.info.from.clone(),
+                record.info.to.clone(),
+                job.tokens.clone(),
+            )
+        };
+        let progress = Progress {
+            store: self.clone(),
+            id: id.to_string(),
+        };
+        let input = match fs::read(self.path(id, "input")) {
+            Ok(input) => input,
+            Err(e) => return progress.fail(format!("读取任务输入失败: {}", e)),
+        };
+        let config = config.read().clone();
+        let start_time = std::time::Instant::now();
+
+        let execution = execute(
+            &config, &task, input, &from_lang, &to_lang, &options, &progress,
+        );
+        match Abortable::new(translator::with_usage(tokens, execution), registration).await {
+            Ok(Ok(output)) => {
+                app_state.record_success(start_time.elapsed());
+                progress.complete(output);
+            }
+            Ok(Err(e)) => {
+                app_state.record_error();
+                progress.fail(e.to_string());
+            }
+            Err(_) => log::info!("任务 {} 已取消", id),
+        }
+    }
+
+    /// 任务完成或失败后通知回调地址，投递结束前任务标记为待回调
+    fn notify(&self, id: &str, config: &Arc<RwLock<Config>>, webhooks: &Webhooks) {
+        let Some((url, info)) = self.jobs.lock().get_mut(id).and_then(|job| {
+            if !matches!(
+                job.record.info.status,
+                JobStatus::Completed | JobStatus::Failed
+            ) {
+                return None;
+            }
+            let url = job.record.callback_url.clone()?;
+            if !job.record.callback_pending {
+                job.record.callback_pending = true;
+                if let Err(e) = self.save(&job.record) {
+                    log::warn!("保存任务 {} 失败: {}", id, e);
+                }
+            }
+            Some((url, job.info()))
+        }) else {
+            return;
+        };
+        let store = self.clone();
+        let id = id.to_string();
+        webhooks.notify(&url, &info, config, move || store.finish_callback(&id));
+    }
+
+    /// 回调投递结束（成功或放弃），清除待回调标记
+    fn finish_callback(&self, id: &str) {
+        if let Some(job) = self.jobs.lock().get_mut(id) {
+            job.record.callback_pending = false;
+            if let Err(e) = self.save(&job.record) {
+                log::warn!("保存任务 {} 失败: {}", id, e);
+            }
+        }
+    }
+
+    fn path(&self, id: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", id, extension))
+    }
+
+    fn save(&self, record: &JobRecord) -> AppResult<()> {
+        let content = serde_json::to_string_pretty(record)?;
+        write_atomic(&self.path(&record.info.id, "json"), content.as_bytes())?;
+        Ok(())
+    }
+
+    /// 已结束的任务超过 `keep` 个时，删除最早的任务及其文件；回调未投递完的任务保留
+    fn prune(&self, keep: usize) {
+        let mut jobs = self.jobs.lock();
+        let mut finished: Vec<String> = jobs
+            .values()
+            .filter(|job| job.record.info.status.is_finished() && !job.record.callback_pending)
+            .map(|job| job.record.info.id.clone())
+            .collect();
+        if finished.len() <= keep {
+            return;
+        }
+        finished.sort();
+        let expired = finished.len() - keep;
+        for id in &finished[..expired] {
+            jobs.remove(id);
+            for extension in ["json", "input", "output"] {
+                match fs::remove_file(self.path(id, extension)) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        log::warn!("删除任务 {} 的文件失败: {}", id, e)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        log::info!("清理 {} 个过期的后台任务", expired);
+    }
+
+    /// 更新执行中的任务并保存；任务已取消时不做任何修改
+    fn update(&self, id: &str, f: impl FnOnce(&mut JobRecord)) {
+        let mut jobs = self.jobs.lock();
+        let Some(job) = jobs
+            .get_mut(id)
+            .filter(|job| job.record.info.status == JobStatus::Running)
+        else {
+            return;
+        };
+        f(&mut job.record);
+        job.record.info.tokens = job.tokens.load(Ordering::Relaxed);
+        if job.record.info.status.is_finished() {
+            job.abort = None;
+        }
+        if let Err(e) = self.save(&job.record) {
+            log::warn!("保存任务 {} 失败: {}", id, e);
+        }
+    }
+}
+
+/// 从磁盘读取的任务结果
+pub struct JobOutputFile {
+    pub content: Vec<u8>,
+    pub content_type: String,
+    pub filename: String,
+}
+
+/// 执行中的任务用来报告进度和结果
+#[derive(Clone)]
+pub struct Progress {
+    store: JobStore,
+    id: String,
+}
+
+impl Progress {
+    /// 开始执行，`total` 为单元总数
+    pub fn start(&self, total: usize) {
+        self.store
+            .update(&self.id, |record| record.info.total = total);
+    }
+
+    /// 完成一个单元
+    pub fn advance(&self) {
+        self.advance_by(1);
+    }
+
+    /// 完成 `count` 个单元
+    pub fn advance_by(&self, count: usize) {
+        self.store
+            .update(&self.id, |record| record.info.done += count);
+    }
+
+    /// 追加 `count` 个单元，其中 `done` 个已完成；用于执行中才能确定数量的片段
+    pub fn extend(&self, count: usize, done: usize) {
+        self.store.update(&self.id, |record| {
+            record.info.total += count;
+            record.info.done += done;
+        });
+    }
+
+    fn complete(&self, output: JobOutput) {
+        if let Err(e) = write_atomic(&self.store.path(&self.id, "output"), &output.content) {
+            return self.fail(format!("保存任务结果失败: {}", e));
+        }
+        self.store.update(&self.id, |record| {
+            record.info.status = JobStatus::Completed;
+            record.info.done = record.info.total;
+            record.info.finished_at = Some(chrono::Local::now().to_rfc3339());
+            record.output = Some(OutputMeta {
+                content_type: output.content_type.to_string(),
+                filename: output.filename,
+            });
+        });
+    }
+
+    fn fail(&self, error: String) {
+        log::error!("任务 {} 失败: {}", self.id, error);
+        self.store.update(&self.id, |record| {
+            record.info.status = JobStatus::Failed;
+            record.info.error = Some(error);
+            record.info.finished_at = Some(chrono::Local::now().to_rfc3339());
+        });
+    }
+}
+
+/// 按任务类型翻译
+async fn execute(
+    config: &Config,
+    task: &JobTask,
+    input: Vec<u8>,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+    progress: &Progress,
+) -> AppResult<JobOutput> {
+    let code = language_code(to_lang).unwrap_or("out");
+    let output = |content: Vec<u8>, content_type: &'static str, extension: &str| JobOutput {
+        content,
+        content_type,
+        filename: format!("translated.{}.{}", code, extension),
+    };
+    match task {
+        JobTask::Text {
+            format: TextFormat::Text,
+        } => {
+            let book = Book {
+                content: input,
+                format: BookFormat::Text,
+                bilingual: false,
+            };
+            let content =
+                book::translate(config, &book, from_lang, to_lang, options, progress).await?;
+            Ok(output(content, "text/plain; charset=utf-8", "txt"))
+        }
+        JobTask::Text { format } => {
+            let text = String::from_utf8_lossy(&input);
+            let (translated, content_type, extension) = if *format == TextFormat::Markdown {
+                let translation = markdown::translate(config, &text, from_lang, to_lang, options);
+                let translated = segments::with_progress(progress.clone(), translation).await?;
+                (translated, "text/markdown; charset=utf-8", "md")
+            } else {
+                let translation = html::translate(config, &text, from_lang, to_lang, options);
+                let translated = segments::with_progress(progress.clone(), translation).await?;
+                (translated, "text/html; charset=utf-8", "html")
+            };
+            Ok(output(translated.into_bytes(), content_type, extension))
+        }
+        JobTask::Book { format, bilingual } => {
+            let book = Book {
+                content: input,
+                format: *format,
+                bilingual: *bilingual,
+            };
+            let content =
+                book::translate(config, &book, from_lang, to_lang, options, progress).await?;
+            Ok(output(content, format.content_type(), format.extension()))
+        }
+        JobTask::Document => {
+            let translation = office::translate(config, &input, from_lang, to_lang, options);
+            let document = segments::with_progress(progress.clone(), translation).await?;
+            if document.failed > 0 {
+                log::warn!("{} 个段落翻译失败，保留原文", document.failed);
+            }
+            let format = document.format;
+            Ok(output(
+                document.content,
+                format.content_type(),
+                format.extension(),
+            ))
+        }
+    }
+}
+
+/// 先写临时文件再替换，避免中途退出时留下不完整的文件
+fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let mut file = fs::File::create(&temp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    fs::rename(&temp, path)
+}
+
+fn not_found(id: &str) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": format!("任务 {} 不存在", id)
+    }))
+}
+
+/// 查询任务进度
+#[get("/jobs/{id}")]
+pub async fn get_job(path: web::Path<String>, jobs: web::Data<JobStore>) -> HttpResponse {
+    match jobs.info(&path) {
+        Some(info) => HttpResponse::Ok().json(info),
+        None => not_found(&path),
+    }
+}
+
+/// 下载任务结果，任务未完成时返回 409
+#[get("/jobs/{id}/result")]
+pub async fn get_job_result(
+    path: web::Path<String>,
+    jobs: web::Data<JobStore>,
+) -> AppResult<HttpResponse> {
+    let Some(info) = jobs.info(&path) else {
+        return Ok(not_found(&path));
+    };
+    Ok(match jobs.output(&path)? {
+        Some(output) => HttpResponse::Ok()
+            .content_type(output.content_type)
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", output.filename),
+            ))
+            .body(output.content),
+        None => HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!("任务 {} 尚未完成", info.id),
+            "status": info.status,
+        })),
+    })
+}
+
+/// 取消任务，已结束的任务返回 409
+#[post("/jobs/{id}/cancel")]
+pub async fn cancel_job(path: web::Path<String>, jobs: web::Data<JobStore>) -> HttpResponse {
+    let Some(info) = jobs.info(&path) else {
+        return not_found(&path);
+    };
+    if info.status.is_finished() {
+        return HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!("任务 {} 已结束", info.id),
+            "status": info.status,
+        }));
+    }
+    match jobs.cancel(&path) {
+        Some(info) => HttpResponse::Ok().json(info),
+        None => not_found(&path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::placeholder::Masked;
+
+    #[test]
+    fn test_job_lifecycle_and_resume() {
+        let dir = std::env::temp_dir().join(format!("rustrans-jobs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let jobs = JobStore::open(&dir).unwrap();
+        let task = JobTask::Book {
+            format: BookFormat::Text,
+            bilingual: false,
+        };
+        let first = jobs
+            .submit(
+                task.clone(),
+                b"Hello",
+                "英语",
+                "日语",
+                TranslateOptions::default(),
+                None,
+            )
+            .unwrap();
+        let second = jobs
+            .submit(
+                task,
+                b"World",
+                "英语",
+                "日语",
+                TranslateOptions::default(),
+                Some("http://127.0.0.1:9/hook".to_string()),
+            )
+            .unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(first.status, JobStatus::Queued);
+
+        // 模拟 worker 开始执行第一个任务
+        jobs.jobs
+            .lock()
+            .get_mut(&first.id)
+            .unwrap()
+            .record
+            .info
+            .status = JobStatus::Running;
+        let progress = Progress {
+            store: jobs.clone(),
+            id: first.id.clone(),
+        };
+        progress.start(2);
+        progress.advance();
+        let info = jobs.info(&first.id).unwrap();
+        assert_eq!(
+            (info.status, info.done, info.total),
+            (JobStatus::Running, 1, 2)
+        );
+        assert_eq!(
+            jobs.cancel(&second.id).unwrap().status,
+            JobStatus::Cancelled
+        );
+
+        // 重启后执行中的任务重新排队，已取消的任务保持不变
+        let reopened = JobStore::open(&dir).unwrap();
+        let info = reopened.info(&first.id).unwrap();
+        assert_eq!((info.status, info.done), (JobStatus::Queued, 0));
+        assert_eq!(
+            reopened.info(&second.id).unwrap().status,
+            JobStatus::Cancelled
+        );
+        let mut receiver = reopened.receiver.lock().take().unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), first.id);
+        assert!(receiver.try_recv().is_err());
+
+        let progress = Progress {
+            store: reopened.clone(),
+            id: first.id.clone(),
+        };
+        reopened
+            .jobs
+            .lock()
+            .get_mut(&first.id)
+            .unwrap()
+            .record
+            .info
+            .status = JobStatus::Running;
+        progress.start(2);
+        progress.complete(JobOutput {
+            content: b"done".to_vec(),
+            content_type: "text/plain; charset=utf-8",
+            filename: "translated.ja.txt".to_string(),
+        });
+        let output = reopened.output(&first.id).unwrap().unwrap();
+        assert_eq!(output.content, b"done");
+        assert_eq!(reopened.info(&first.id).unwrap().done, 2);
+
+        // 回调未投递完的已结束任务，重启后重新排队投递
+        {
+            let mut jobs = reopened.jobs.lock();
+            let record = &mut jobs.get_mut(&second.id).unwrap().record;
+            record.info.status = JobStatus::Failed;
+            record.callback_pending = true;
+            reopened.save(record).unwrap();
+        }
+        let again = JobStore::open(&dir).unwrap();
+        let mut receiver = again.receiver.lock().take().unwrap();
+        assert_eq!(receiver.try_recv().unwrap(), second.id);
+        assert!(receiver.try_recv().is_err());
+        again.finish_callback(&second.id);
+        let again = JobStore::open(&dir).unwrap();
+        let mut receiver = again.receiver.lock().take().unwrap();
+        assert!(receiver.try_recv().is_err());
+
+        // 只保留最近的已结束任务，连同输入和结果文件一起删除
+        again.prune(1);
+        assert!(again.info(&first.id).is_none());
+        assert!(again.info(&second.id).is_some());
+        assert!(!again.path(&first.id, "output").exists());
+        assert!(!again.path(&first.id, "input").exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_segment_progress() {
+        let dir = std::env::temp_dir().join(format!("rustrans-progress-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let jobs = JobStore::open(&dir).unwrap();
+        let info = jobs
+            .submit(
+                JobTask::Document,
+                b"",
+                "英语",
+                "日语",
+                TranslateOptions::default(),
+                None,
+            )
+            .unwrap();
+        jobs.jobs
+            .lock()
+            .get_mut(&info.id)
+            .unwrap()
+            .record
+            .info
+            .status = JobStatus::Running;
+        let progress = Progress {
+            store: jobs.clone(),
+            id: info.id.clone(),
+        };
+
+        // 不含文字的片段无需请求，直接计为完成
+        let segments: Vec<Masked> = ["42", "3.14"]
+            .iter()
+            .map(|text| Masked::new(text, 0..text.len(), &[]))
+            .collect();
+        let (config, options) = (Config::default(), TranslateOptions::default());
+        let translation = segments::translate_segments_with_notes(
+            &config,
+            &segments,
+            &[],
+            "英语",
+            "日语",
+            &options,
+        );
+        segments::with_progress(progress, translation)
+            .await
+            .unwrap();
+        let info = jobs.info(&info.id).unwrap();
+        assert_eq!((info.done, info.total), (2, 2));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
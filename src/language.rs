@@ -23,6 +23,64 @@ pub fn detect_language(text: &str) -> String {
     }
 }
 
+/// 已知语种名称与 BCP 47 语言代码的对应关系
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("中文(简体)", "zh-Hans"),
+    ("中文(繁体)", "zh-Hant"),
+    ("英语", "en"),
+    ("日语", "ja"),
+    ("韩语", "ko"),
+    ("俄语", "ru"),
+    ("西班牙语", "es"),
+    ("法语", "fr"),
+    ("德语", "de"),
+    ("阿拉伯语", "ar"),
+    ("葡萄牙语", "pt"),
+    ("意大利语", "it"),
+    ("越南语", "vi"),
+];
+
+/// 获取语种名称对应的语言代码，未知语种返回 None
+pub fn language_code(name: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, code)| *code)
+}
+
+/// 估算语种检测的置信度（0-1）
+///
+/// whichlang 不提供概率输出，这里根据文本长度和文字系统一致性做启发式估计：
+/// 文本越短越不可信；对于有独立文字系统的语种，属于该文字的字母占比越高越可信。
+pub fn detection_confidence(text: &str, lang: &str) -> f32 {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.is_empty() {
+        return 0.0;
+    }
+
+    let in_script = |c: &char| match lang {
+        "中文(简体)" | "中文(繁体)" => {
+            matches!(*c as u32, 0x4E00..=0x9FFF | 0x3400..=0x4DBF)
+        }
+        "日语" => matches!(*c as u32, 0x3040..=0x30FF | 0x4E00..=0x9FFF),
+        "韩语" => matches!(*c as u32, 0xAC00..=0xD7AF | 0x1100..=0x11FF),
+        "俄语" => matches!(*c as u32, 0x0400..=0x04FF),
+        "阿拉伯语" => matches!(*c as u32, 0x0600..=0x06FF),
+        _ => c.is_ascii_alphabetic() || matches!(*c as u32, 0x00C0..=0x024F),
+    };
+    let script_ratio =
+        letters.iter().filter(|c| in_script(c)).count() as f32 / letters.len() as f32;
+    // 拉丁字母语种之间无法靠文字系统区分，额外按长度折减
+    let length_factor = match lang {
+        "中文(简体)" | "中文(繁体)" | "日语" | "韩语" => {
+            (letters.len() as f32 / 4.0).min(1.0)
+        }
+        _ => (letters.len() as f32 / 30.0).min(1.0),
+    };
+
+    (script_ratio * (0.5 + 0.5 * length_factor) * 100.0).round() / 100.0
+}
+
 /// 选择目标语种
 ///
 /// 如果检测到的语种与首要目标语种相同，则使用次要目标语种
@@ -68,4 +126,17 @@ mod tests {
         assert_eq!(from, "中文(简体)");
         assert_eq!(to, "英语");
     }
+
+    #[test]
+    fn test_language_code() {
+        assert_eq!(language_code("日语"), Some("ja"));
+        assert_eq!(language_code("克林贡语"), None);
+    }
+
+    #[test]
+    fn test_detection_confidence() {
+        assert_eq!(detection_confidence("你好世界", "中文(简体)"), 1.0);
+        assert!(detection_confidence("Hi", "英语") < 0.6);
+        assert_eq!(detection_confidence("12345", "英语"), 0.0);
+    }
 }
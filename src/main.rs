@@ -1,37 +1,84 @@
 mod admin;
+mod alignment;
+mod android;
+mod apple;
+mod book;
+mod chunker;
+mod cli;
 mod config;
 mod error;
+mod examples;
+mod fluent;
+mod glossary;
 mod handlers;
 mod health;
+mod history;
+mod html;
+mod jobs;
 mod language;
+mod markdown;
+mod markup;
+mod memory;
+mod mobile;
 mod models;
+mod office;
+mod placeholder;
+mod po;
+mod presets;
+mod prompt;
+mod resources;
+mod segments;
+mod subtitle;
+mod tmx;
 mod translator;
+mod variables;
+mod watcher;
+mod webhooks;
+mod xliff;
+mod yaml;
 
 use actix_cors::Cors;
 use actix_web::{middleware, web, App, HttpServer};
-use admin::{admin_index, get_config, update_config};
+use admin::{
+    admin_index, diff_config_version, get_config, list_config_versions, rollback_config,
+    update_config,
+};
 use clap::Parser;
-use config::Config;
-use handlers::translate;
+use cli::Args;
+use config::{Config, CONFIG_PATH};
+use examples::{create_example, delete_example, list_examples};
+use handlers::{
+    explain, submit_job, translate, translate_book, translate_document, translate_mobile,
+    translate_po, translate_resource, translate_subtitle, translate_xliff,
+};
 use health::{health_check, llm_health_check, metrics, AppState};
+use jobs::{cancel_job, get_job, get_job_result, JobStore, JOBS_DIR};
+use memory::{
+    approve_segments, delete_memory, export_memory, import_memory, list_memory, TranslationMemory,
+    MEMORY_PATH,
+};
+use presets::{
+    delete_client_preset, delete_preset, list_presets, upsert_client_preset, upsert_preset,
+};
+use prompt::preview_prompt;
 use std::env;
 use std::sync::Arc;
+use webhooks::{list_webhook_deliveries, Webhooks};
 
-/// 翻译服务
-#[derive(Parser, Debug)]
-#[command(name = "Rustrans")]
-#[command(about = "基于 LLM 的翻译服务", long_about = None)]
-struct Args {
-    /// 服务端口
-    #[arg(short, long)]
-    port: Option<u16>,
-}
-
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
+fn main() -> std::io::Result<()> {
     // 解析命令行参数
     let args = Args::parse();
 
+    // 子命令直接执行后退出，不启动服务
+    if let Some(command) = args.command {
+        std::process::exit(cli::run(command));
+    }
+
+    serve(args)
+}
+
+#[actix_web::main]
+async fn serve(args: Args) -> std::io::Result<()> {
     // 加载 .env 文件（可选）
     dotenv::dotenv().ok();
 
@@ -70,17 +117,34 @@ async fn main() -> std::io::Result<()> {
             cfg
         }
         Err(e) => {
+            // 配置有误时拒绝启动，避免以默认配置运行后覆盖原有配置
             log::error!("加载配置失败: {}", e);
-            Config::default()
+            for error in e.field_errors() {
+                log::error!("  - {}", error);
+            }
+            log::error!("可运行 `Rustrans config check` 查看详细错误");
+            std::process::exit(1);
         }
     };
 
     // 使用 Arc<RwLock> 包装配置，使其可以在运行时修改
     let shared_config = Arc::new(parking_lot::RwLock::new(config));
 
+    // 监听配置文件变更，支持不重启热重载
+    watcher::spawn_config_watcher(shared_config.clone(), CONFIG_PATH.into());
+
     // 创建应用状态用于监控
     let app_state = AppState::new();
 
+    // 加载翻译记忆，翻译流程通过全局句柄查询
+    let translation_memory = TranslationMemory::open(std::path::Path::new(MEMORY_PATH))?;
+    memory::install(translation_memory.clone());
+
+    // 加载后台任务并启动 worker，未完成的任务重新排队
+    let webhooks = Webhooks::default();
+    let job_store = JobStore::open(std::path::Path::new(JOBS_DIR))?;
+    job_store.spawn_worker(shared_config.clone(), app_state.clone(), webhooks.clone());
+
     HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin()
@@ -91,14 +155,66 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(web::Data::new(shared_config.clone()))
             .app_data(web::Data::new(app_state.clone()))
+            .app_data(web::Data::new(job_store.clone()))
+            .app_data(web::Data::new(webhooks.clone()))
+            .app_data(web::Data::new(translation_memory.clone()))
+            // 默认约 2MB 的 JSON 上限会在长度检查之前拒绝较长的文本
+            .app_data(web::JsonConfig::default().limit(config::MAX_JSON_BYTES))
             .wrap(cors)
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
             .wrap(middleware::NormalizePath::trim())
             .service(translate)
+            .service(explain)
+            .service(translate_subtitle)
+            .service(translate_po)
+            .service(translate_resource)
+            .service(translate_mobile)
+            .service(translate_xliff)
+            // 上传的 Office 文档和书籍可能超过默认的 256KB 请求体上限，只对这几个接口放宽
+            .service(
+                web::resource("/translate/document")
+                    .app_data(web::PayloadConfig::new(office::MAX_DOCUMENT_BYTES))
+                    .route(web::post().to(translate_document)),
+            )
+            .service(
+                web::resource("/translate/book")
+                    .app_data(web::PayloadConfig::new(office::MAX_DOCUMENT_BYTES))
+                    .route(web::post().to(translate_book)),
+            )
+            .service(
+                web::resource("/jobs")
+                    .app_data(web::PayloadConfig::new(office::MAX_DOCUMENT_BYTES))
+                    .route(web::post().to(submit_job)),
+            )
+            .service(get_job)
+            .service(get_job_result)
+            .service(cancel_job)
+            .service(approve_segments)
             .service(admin_index)
             .service(get_config)
             .service(update_config)
+            .service(list_config_versions)
+            .service(diff_config_version)
+            .service(rollback_config)
+            .service(list_webhook_deliveries)
+            .service(list_presets)
+            .service(upsert_client_preset)
+            .service(delete_client_preset)
+            .service(upsert_preset)
+            .service(delete_preset)
+            .service(preview_prompt)
+            .service(list_examples)
+            .service(create_example)
+            .service(delete_example)
+            .service(list_memory)
+            .service(
+                web::resource("/admin/memory/import")
+                    .app_data(web::PayloadConfig::new(memory::MAX_TMX_BYTES))
+                    .route(web::post().to(import_memory)),
+            )
+            .service(export_memory)
+            .service(delete_memory)
             .service(health_check)
             .service(metrics)
             .service(llm_health_check)
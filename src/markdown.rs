@@ -0,0 +1,235 @@
+// Markdown 翻译模块
+//
+// 用 pulldown-cmark 解析文档，只翻译段落、标题、列表项和表格单元格中的文字。
+// 代码块、front matter、HTML 块保持不动；行内代码、行内 HTML、链接地址、图片路径、
+// 自动链接以及多行段落中的续行前缀（如引用的 `> `）替换为占位符后再发送给模型。
+// 译文按原文偏移写回，文档其余部分逐字节保留。
+
+use crate::config::Config;
+use crate::error::AppResult;
+use crate::placeholder::Masked;
+use crate::segments::translate_segments;
+use crate::translator::TranslateOptions;
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+/// 一段可翻译的行内内容及其中需要保护的区间
+#[derive(Debug, Default, PartialEq)]
+struct Segment {
+    range: Range<usize>,
+    protected: Vec<Range<usize>>,
+}
+
+/// 正在解析的链接或图片
+struct OpenLink {
+    range: Range<usize>,
+    /// 链接文字开始的位置，其前的 `[` 或 `![` 需要保护
+    text_start: usize,
+    /// 链接文字结束的位置，其后的 `](url)` 部分需要保护
+    text_end: usize,
+    autolink: bool,
+}
+
+/// 翻译 Markdown 文档，保留文档结构
+pub async fn translate(
+    config: &Config,
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<String> {
+    let segments = segments(text);
+    let masked = mask(text, &segments);
+    let translated = translate_segments(config, &masked, from_lang, to_lang, options).await?;
+
+    let mut result = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for (segment, translated) in segments.iter().zip(translated) {
+        result.push_str(&text[cursor..segment.range.start]);
+        result.push_str(&translated);
+        cursor = segment.range.end;
+    }
+    result.push_str(&text[cursor..]);
+    Ok(result)
+}
+
+/// 文档中所有待译片段，不调用大模型时用于解释翻译请求
+pub fn masked_segments(text: &str) -> Vec<Masked> {
+    mask(text, &segments(text))
+}
+
+fn mask(text: &str, segments: &[Segment]) -> Vec<Masked> {
+    segments
+        .iter()
+        .map(|s| Masked::new(text, s.range.clone(), &s.protected))
+        .collect()
+}
+
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS
+}
+
+/// 找出文档中所有可翻译的行内内容
+fn segments(text: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut current: Option<Segment> = None;
+    let mut links: Vec<OpenLink> = Vec::new();
+    let mut verbatim = 0usize;
+    let mut after_break: Option<usize> = None;
+
+    for (event, range) in Parser::new_ext(text, parser_options()).into_offset_iter() {
+        let inline = match &event {
+            Event::Start(tag) => is_inline_tag(tag),
+            Event::End(tag) => is_inline_tag_end(tag),
+            Event::Text(_)
+            | Event::Code(_)
+            | Event::InlineHtml(_)
+            | Event::InlineMath(_)
+            | Event::FootnoteReference(_)
+            | Event::SoftBreak
+            | Event::HardBreak => verbatim == 0,
+            _ => false,
+        };
+
+        if !inline {
+            match event {
+                Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => verbatim += 1,
+                Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => {
+                    verbatim = verbatim.saturating_sub(1)
+                }
+                _ => {}
+            }
+            // 块级事件结束当前片段
+            segments.extend(current.take());
+            links.clear();
+            after_break = None;
+            continue;
+        }
+
+        let segment = current.get_or_insert_with(|| Segment {
+            range: range.clone(),
+            protected: Vec::new(),
+        });
+        segment.range.end = segment.range.end.max(range.end);
+
+        // 续行前缀（引用标记、列表缩进等）
+        if let Some(line_start) = after_break.take() {
+            if range.start > line_start {
+                segment.protected.push(line_start..range.start);
+            }
+        }
+
+        match event {
+            Event::Code(_) | Event::InlineHtml(_) | Event::InlineMath(_) => {
+                segment.protected.push(range.clone())
+            }
+            Event::FootnoteReference(_) => segment.protected.push(range.clone()),
+            Event::SoftBreak | Event::HardBreak => after_break = Some(range.end),
+            Event::Start(Tag::Link { link_type, .. }) => links.push(OpenLink {
+                text_start: range.start + 1,
+                text_end: range.start + 1,
+                autolink: matches!(link_type, LinkType::Autolink | LinkType::Email),
+                range: range.clone(),
+            }),
+            Event::Start(Tag::Image { .. }) => links.push(OpenLink {
+                text_start: range.start + 2,
+                text_end: range.start + 2,
+                autolink: false,
+                range: range.clone(),
+            }),
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                if let Some(link) = links.pop() {
+                    if link.autolink {
+                        segment.protected.push(link.range.clone());
+                    } else {
+                        // 链接文字两侧的语法各自替换为占位符，模型漏掉时可以发现
+                        segment.protected.push(link.range.start..link.text_start);
+                        if link.text_end < link.range.end {
+                            segment.protected.push(link.text_end..link.range.end);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // 链接内部的事件推进链接文字的结束位置
+        if let Some(link) = links.last_mut() {
+            if range != link.range && range.end <= link.range.end {
+                link.text_end = link.text_end.max(range.end);
+            }
+        }
+    }
+    segments.extend(current);
+    segments
+}
+
+fn is_inline_tag(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::Emphasis
+            | Tag::Strong
+            | Tag::Strikethrough
+            | Tag::Superscript
+            | Tag::Subscript
+            | Tag::Link { .. }
+            | Tag::Image { .. }
+    )
+}
+
+fn is_inline_tag_end(tag: &TagEnd) -> bool {
+    matches!(
+        tag,
+        TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Superscript
+            | TagEnd::Subscript
+            | TagEnd::Link
+            | TagEnd::Image
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn masked(text: &str) -> Vec<String> {
+        segments(text)
+            .iter()
+            .map(|s| Masked::new(text, s.range.clone(), &s.protected).text)
+            .collect()
+    }
+
+    #[test]
+    fn test_segments_skip_code_and_front_matter() {
+        let text = "---\ntitle: Guide\n---\n\n# Install `rustrans`\n\n```bash\ncargo install rustrans\n```\n\n- Run **now**\n- See [the docs](https://example.com/docs \"Docs\")\n\n| Name | Value |\n| --- | --- |\n| Port | `9999` |\n";
+        assert_eq!(
+            masked(text),
+            vec![
+                "Install ⟦1⟧",
+                "Run **now**",
+                "See ⟦1⟧the docs⟦2⟧",
+                "Name",
+                "Value",
+                "Port",
+                "⟦1⟧",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_segments_protect_images_autolinks_and_prefixes() {
+        let text =
+            "> Quoted line one\n> and line two with <https://a.b> and ![logo](img/logo.png).\n";
+        assert_eq!(
+            masked(text),
+            vec!["Quoted line one\n⟦1⟧and line two with ⟦2⟧ and ⟦3⟧logo⟦4⟧."]
+        );
+    }
+}
@@ -0,0 +1,359 @@
+// HTML/XML 标记解析模块
+//
+// 宽松的分词器：只识别标签、注释、声明和文本，记录每个片段在原文中的字节区间，
+// 不构建 DOM、不做纠错，便于在保留原文的前提下只替换其中的文字和属性值。
+
+use std::ops::Range;
+
+/// 标签属性
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attr {
+    /// 属性名（小写）
+    pub name: String,
+    /// 属性值在原文中的区间（不含引号），无值属性为 None
+    pub value: Option<Range<usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    /// 文本
+    Text,
+    /// 不解析内部标记的元素内容（如 `<script>`、`<style>`）
+    RawText,
+    /// 开始标签，名称为小写
+    Start {
+        name: String,
+        attrs: Vec<Attr>,
+        self_closing: bool,
+    },
+    /// 结束标签，名称为小写
+    End { name: String },
+    /// 注释
+    Comment,
+    /// `<!DOCTYPE>`、`<?xml?>`、`<![CDATA[]]>` 等声明
+    Declaration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub range: Range<usize>,
+}
+
+impl Token {
+    /// 开始标签中指定属性的值区间
+    pub fn attr(&self, name: &str) -> Option<Range<usize>> {
+        match &self.kind {
+            TokenKind::Start { attrs, .. } => attrs
+                .iter()
+                .find(|a| a.name == name)
+                .and_then(|a| a.value.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// 对文档分词，`raw_text_elements` 中的元素内容作为一个 [`TokenKind::RawText`]
+pub fn tokenize(text: &str, raw_text_elements: &[&str]) -> Vec<Token> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let mut text_start = 0;
+
+    let flush_text = |tokens: &mut Vec<Token>, start: usize, end: usize| {
+        if start < end {
+            tokens.push(Token {
+                kind: TokenKind::Text,
+                range: start..end,
+            });
+        }
+    };
+
+    while pos < bytes.len() {
+        if bytes[pos] != b'<' {
+            pos += 1;
+            continue;
+        }
+        let Some((kind, end)) = parse_markup(text, pos) else {
+            pos += 1;
+            continue;
+        };
+        flush_text(&mut tokens, text_start, pos);
+
+        let raw = match &kind {
+            TokenKind::Start {
+                name, self_closing, ..
+            } if !self_closing && raw_text_elements.contains(&name.as_str()) => Some(name.clone()),
+            _ => None,
+        };
+        tokens.push(Token {
+            kind,
+            range: pos..end,
+        });
+        pos = end;
+        text_start = end;
+
+        if let Some(name) = raw {
+            let close = find_ascii_ci(text, pos, &format!("</{}", name)).unwrap_or(text.len());
+            if close > pos {
+                tokens.push(Token {
+                    kind: TokenKind::RawText,
+                    range: pos..close,
+                });
+            }
+            pos = close;
+            text_start = close;
+        }
+    }
+    flush_text(&mut tokens, text_start, text.len());
+    tokens
+}
+
+/// 解析从 `start`（`<`）开始的标记，返回类型和结束位置；不是合法标记时返回 None
+fn parse_markup(text: &str, start: usize) -> Option<(TokenKind, usize)> {
+    let rest = &text[start..];
+    if let Some(body) = rest.strip_prefix("<!--") {
+        let end = body.find("-->").map_or(text.len(), |i| start + 4 + i + 3);
+        return Some((TokenKind::Comment, end));
+    }
+    if rest.starts_with("<![CDATA[") {
+        let end = rest.find("]]>").map_or(text.len(), |i| start + i + 3);
+        return Some((TokenKind::Declaration, end));
+    }
+    if rest.starts_with("<!") || rest.starts_with("<?") {
+        let end = rest.find('>').map_or(text.len(), |i| start + i + 1);
+        return Some((TokenKind::Declaration, end));
+    }
+
+    let bytes = text.as_bytes();
+    let closing = rest.starts_with("</");
+    let mut pos = start + if closing { 2 } else { 1 };
+    if !bytes.get(pos).is_some_and(u8::is_ascii_alphabetic) {
+        return None;
+    }
+    let name_start = pos;
+    while pos < bytes.len() && is_name_byte(bytes[pos]) {
+        pos += 1;
+    }
+    let name = text[name_start..pos].to_ascii_lowercase();
+
+    if closing {
+        let end = text[pos..].find('>')? + pos + 1;
+        return Some((TokenKind::End { name }, end));
+    }
+
+    let mut attrs = Vec::new();
+    loop {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        match bytes.get(pos)? {
+            b'>' => {
+                return Some((
+                    TokenKind::Start {
+                        name,
+                        attrs,
+                        self_closing: false,
+                    },
+                    pos + 1,
+                ))
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'>') => {
+                return Some((
+                    TokenKind::Start {
+                        name,
+                        attrs,
+                        self_closing: true,
+                    },
+                    pos + 2,
+                ))
+            }
+            _ => {}
+        }
+
+        let attr_start = pos;
+        while pos < bytes.len()
+            && !matches!(bytes[pos], b'=' | b'>' | b'/')
+            && !bytes[pos].is_ascii_whitespace()
+        {
+            pos += 1;
+        }
+        if pos == attr_start {
+            // 孤立的 `/` 等字符
+            pos += 1;
+            continue;
+        }
+        let attr_name = text[attr_start..pos].to_ascii_lowercase();
+
+        let mut look = pos;
+        while look < bytes.len() && bytes[look].is_ascii_whitespace() {
+            look += 1;
+        }
+        if bytes.get(look) != Some(&b'=') {
+            attrs.push(Attr {
+                name: attr_name,
+                value: None,
+            });
+            continue;
+        }
+        pos = look + 1;
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        let value = match bytes.get(pos)? {
+            quote @ (b'"' | b'\'') => {
+                let value_start = pos + 1;
+                let value_end = text[value_start..].find(*quote as char)? + value_start;
+                pos = value_end + 1;
+                value_start..value_end
+            }
+            _ => {
+                let value_start = pos;
+                while pos < bytes.len() && bytes[pos] != b'>' && !bytes[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                value_start..pos
+            }
+        };
+        attrs.push(Attr {
+            name: attr_name,
+            value: Some(value),
+        });
+    }
+}
+
+fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b':' | b'.')
+}
+
+/// 从 `from` 开始查找 `needle`（ASCII 大小写不敏感）
+fn find_ascii_ci(text: &str, from: usize, needle: &str) -> Option<usize> {
+    let haystack = text.as_bytes();
+    let needle = needle.as_bytes();
+    (from..=haystack.len().checked_sub(needle.len())?)
+        .find(|&i| haystack[i..i + needle.len()].eq_ignore_ascii_case(needle))
+}
+
+/// 文本中字符实体（`&amp;`、`&#39;`、`&#x4e2d;`）的区间
+pub fn entity_ranges(text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    lazy_static::lazy_static! {
+        static ref ENTITY: regex::Regex =
+            regex::Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[A-Za-z][A-Za-z0-9]*);").unwrap();
+    }
+    ENTITY
+        .find_iter(&text[range.clone()])
+        .map(|m| range.start + m.start()..range.start + m.end())
+        .collect()
+}
+
+/// 转义文本内容中的 `&`、`<`、`>`
+pub fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// 还原 XML 预定义实体和数字字符引用，无法识别的实体原样保留
+pub fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for range in entity_ranges(text, 0..text.len()) {
+        result.push_str(&text[cursor..range.start]);
+        let name = &text[range.start + 1..range.end - 1];
+        let decoded = match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => name
+                .strip_prefix("#x")
+                .or_else(|| name.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| name.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => result.push(c),
+            None => result.push_str(&text[range.clone()]),
+        }
+        cursor = range.end;
+    }
+    result.push_str(&text[cursor..]);
+    result
+}
+
+/// 转义属性值，额外转义引号
+pub fn escape_attr(text: &str) -> String {
+    escape_text(text)
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// 区间独占一行时扩展为整行（含行尾换行符），用于删除或整体替换元素
+pub fn line_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let rest = &text[range.end..];
+    let line_end = rest.find('\n').map_or(text.len(), |i| range.end + i + 1);
+    if text[line_start..range.start].trim().is_empty()
+        && text[range.end..line_end].trim().is_empty()
+    {
+        line_start..line_end
+    } else {
+        range
+    }
+}
+
+/// 按区间替换文本，`edits` 的区间互不重叠
+pub fn apply_edits(text: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    edits.sort_by_key(|(range, _)| range.start);
+    let mut result = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for (range, replacement) in edits {
+        result.push_str(&text[cursor..range.start]);
+        result.push_str(&replacement);
+        cursor = range.end;
+    }
+    result.push_str(&text[cursor..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let text = "<!DOCTYPE html><p class=\"a\" hidden>Hi <b>there</b> &amp; 1 < 2<br/></p><script>if (a<b) {}</script><!-- c -->";
+        let tokens = tokenize(text, &["script", "style"]);
+        let kinds: Vec<String> = tokens
+            .iter()
+            .map(|t| match &t.kind {
+                TokenKind::Start { name, attrs, .. } => format!("<{}:{}>", name, attrs.len()),
+                TokenKind::End { name } => format!("</{}>", name),
+                _ => text[t.range.clone()].to_string(),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "<!DOCTYPE html>",
+                "<p:2>",
+                "Hi ",
+                "<b:0>",
+                "there",
+                "</b>",
+                " &amp; 1 < 2",
+                "<br:0>",
+                "</p>",
+                "<script:0>",
+                "if (a<b) {}",
+                "</script>",
+                "<!-- c -->",
+            ]
+        );
+        assert_eq!(&text[tokens[1].attr("class").unwrap()], "a");
+        assert_eq!(entity_ranges(text, tokens[6].range.clone()).len(), 1);
+    }
+}
@@ -0,0 +1,792 @@
+// 翻译记忆模块
+//
+// 保存经人工审定的原文/译文句段，按语种对索引：
+// - 新请求的原文与记忆完全匹配（忽略首尾和连续空白的差异）时直接复用译文，不调用大模型；
+// - 相似度（基于编辑距离）达到阈值的句段作为参考随请求发送，使反复出现的内容用语一致。
+//   句段按原文长度分桶，模糊匹配只比较长度可能达到阈值的句段，且每次比较的数量有上限。
+//
+// 记忆保存在工作目录下的 memory.jsonl 中，每行一个句段，新增时追加写入，
+// 同一语种对的相同原文以最后审定的译文为准。
+
+use crate::error::{AppError, AppResult};
+use crate::language::LANGUAGES;
+use crate::tmx;
+use actix_web::{delete, get, post, web, HttpResponse};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// 翻译记忆文件（位于工作目录下）
+pub const MEMORY_PATH: &str = "memory.jsonl";
+
+/// 列表接口默认返回的句段数量
+const DEFAULT_LIST_LIMIT: usize = 100;
+
+/// 导入的 TMX 文件大小上限
+pub const MAX_TMX_BYTES: usize = 50 * 1024 * 1024;
+
+/// 模糊匹配时参与比较的文本行数上限
+const MAX_FUZZY_QUERIES: usize = 20;
+
+/// 每行文本参与编辑距离比较的句段数上限，优先比较长度最接近的句段
+const MAX_FUZZY_CANDIDATES: usize = 2000;
+
+/// 一条审定的句段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    /// 源语言代码
+    pub from: String,
+    /// 目标语言代码
+    pub to: String,
+    pub source: String,
+    pub target: String,
+    pub created_at: String,
+}
+
+/// 模糊匹配结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemoryMatch {
+    pub source: String,
+    pub target: String,
+    /// 相似度（0-1）
+    pub score: f64,
+}
+
+/// 一个语种对的句段
+#[derive(Default)]
+struct Pair {
+    /// 规范化原文 -> 句段
+    entries: HashMap<String, MemoryEntry>,
+    /// 原文字符数 -> 规范化原文
+    lengths: BTreeMap<usize, BTreeSet<String>>,
+}
+
+impl Pair {
+    fn insert(&mut self, entry: MemoryEntry) {
+        let key = normalize_text(&entry.source);
+        self.lengths
+            .entry(key.chars().count())
+            .or_default()
+            .insert(key.clone());
+        self.entries.insert(key, entry);
+    }
+
+    fn remove(&mut self, key: &str) -> Option<MemoryEntry> {
+        let entry = self.entries.remove(key)?;
+        let length = key.chars().count();
+        if let Some(bucket) = self.lengths.get_mut(&length) {
+            bucket.remove(key);
+            if bucket.is_empty() {
+                self.lengths.remove(&length);
+            }
+        }
+        Some(entry)
+    }
+
+    /// 长度可能使相似度达到 `threshold` 的原文，长度最接近的在前，至多 `limit` 条
+    fn candidates(&self, length: usize, threshold: f64, limit: usize) -> Vec<&str> {
+        // 相似度不超过 短长度/长长度，超出此范围的句段不可能达到阈值
+        let (min, max) = if threshold > 0.0 {
+            (
+                (length as f64 * threshold).ceil() as usize,
+                (length as f64 / threshold).floor() as usize,
+            )
+        } else {
+            (0, usize::MAX)
+        };
+        let mut buckets: Vec<(&usize, &BTreeSet<String>)> = self.lengths.range(min..=max).collect();
+        buckets.sort_by_key(|(bucket, _)| bucket.abs_diff(length));
+        buckets
+            .into_iter()
+            .flat_map(|(_, sources)| sources.iter().map(String::as_str))
+            .take(limit)
+            .collect()
+    }
+}
+
+/// 语种对 -> 句段
+type Segments = HashMap<(String, String), Pair>;
+
+/// 翻译记忆库
+#[derive(Clone)]
+pub struct TranslationMemory {
+    path: PathBuf,
+    segments: Arc<RwLock<Segments>>,
+}
+
+/// 翻译流程共用的记忆库，在启动时安装
+static GLOBAL: OnceLock<TranslationMemory> = OnceLock::new();
+
+/// 安装全局记忆库；翻译函数只接收配置，通过 [`global`] 访问记忆库
+pub fn install(memory: TranslationMemory) {
+    let _ = GLOBAL.set(memory);
+}
+
+/// 全局记忆库，未安装时返回 None
+pub fn global() -> Option<&'static TranslationMemory> {
+    GLOBAL.get()
+}
+
+impl TranslationMemory {
+    /// 加载记忆文件，文件不存在时为空；重复的句段在加载后合并写回
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let memory = TranslationMemory {
+            path: path.to_path_buf(),
+            segments: Arc::new(RwLock::new(HashMap::new())),
+        };
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(memory),
+            Err(e) => return Err(e),
+        };
+
+        let mut lines = 0;
+        {
+            let mut segments = memory.segments.write();
+            for (number, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                lines += 1;
+                match serde_json::from_str::<MemoryEntry>(line) {
+                    Ok(mut entry) => {
+                        entry.created_at = utc_timestamp(&entry.created_at);
+                        insert(&mut segments, entry)
+                    }
+                    Err(e) => log::warn!("跳过翻译记忆第 {} 行: {}", number + 1, e),
+                }
+            }
+        }
+        let count = memory.len();
+        if count < lines {
+            memory.rewrite()?;
+        }
+        log::info!("加载翻译记忆 {} 条", count);
+        Ok(memory)
+    }
+
+    /// 句段总数
+    pub fn len(&self) -> usize {
+        self.segments
+            .read()
+            .values()
+            .map(|pair| pair.entries.len())
+            .sum()
+    }
+
+    /// 保存审定的句段，语言名称或代码统一为规范的语言代码；已有句段以新译文为准
+    pub fn approve(&self, from: &str, to: &str, pairs: &[SegmentPair]) -> AppResult<ImportStats> {
+        if from.trim().is_empty() || to.trim().is_empty() {
+            return Err(AppError::Validation("源语言和目标语言不能为空".to_string()));
+        }
+        if pairs
+            .iter()
+            .any(|p| p.source.trim().is_empty() || p.target.trim().is_empty())
+        {
+            return Err(AppError::Validation("原文和译文不能为空".to_string()));
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let entries = pairs
+            .iter()
+            .map(|p| MemoryEntry {
+                from: from.to_string(),
+                to: to.to_string(),
+                source: p.source.clone(),
+                target: p.target.clone(),
+                created_at: now.clone(),
+            })
+            .collect();
+        self.import(entries, DuplicatePolicy::Overwrite)
+    }
+
+    /// 批量存入句段，按 `duplicates` 处理已有的相同原文；译文完全相同的句段直接跳过
+    ///
+    /// 语言代码统一规范化，原文或译文为空的句段跳过。新增和更新的句段一次性追加到文件。
+    pub fn import(
+        &self,
+        entries: Vec<MemoryEntry>,
+        duplicates: DuplicatePolicy,
+    ) -> AppResult<ImportStats> {
+        let mut stats = ImportStats::default();
+        let mut segments = self.segments.write();
+        // 本批次内的句段，后出现的同一原文与已有句段同样按策略处理
+        let mut staged: HashMap<(String, String, String), MemoryEntry> = HashMap::new();
+        let mut order = Vec::new();
+        for entry in entries {
+            let entry = MemoryEntry {
+                from: normalize_language(&entry.from),
+                to: normalize_language(&entry.to),
+                source: entry.source.trim().to_string(),
+                target: entry.target.trim().to_string(),
+                created_at: utc_timestamp(&entry.created_at),
+            };
+            if [&entry.from, &entry.to, &entry.source, &entry.target]
+                .iter()
+                .any(|v| v.is_empty())
+            {
+                stats.skipped += 1;
+                continue;
+            }
+            let key = (
+                entry.from.clone(),
+                entry.to.clone(),
+                normalize_text(&entry.source),
+            );
+            let existing = staged.get(&key).or_else(|| {
+                segments
+                    .get(&(key.0.clone(), key.1.clone()))
+                    .and_then(|pair| pair.entries.get(&key.2))
+            });
+            match existing {
+                None => stats.added += 1,
+                Some(existing) if existing.target == entry.target => {
+                    stats.skipped += 1;
+                    continue;
+                }
+                Some(_) if duplicates == DuplicatePolicy::Skip => {
+                    stats.skipped += 1;
+                    continue;
+                }
+                Some(_) => stats.updated += 1,
+            }
+            if !staged.contains_key(&key) {
+                order.push(key.clone());
+            }
+            staged.insert(key, entry);
+        }
+
+        let entries: Vec<MemoryEntry> = order.iter().filter_map(|key| staged.remove(key)).collect();
+        let mut lines = String::new();
+        for entry in &entries {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+        if !lines.is_empty() {
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?
+                .write_all(lines.as_bytes())?;
+        }
+        for entry in entries {
+            insert(&mut segments, entry);
+        }
+        Ok(stats)
+    }
+
+    /// 删除句段，返回是否存在
+    pub fn remove(&self, from: &str, to: &str, source: &str) -> AppResult<bool> {
+        let key = (normalize_language(from), normalize_language(to));
+        let removed = self
+            .segments
+            .write()
+            .get_mut(&key)
+            .and_then(|pair| pair.remove(&normalize_text(source)))
+            .is_some();
+        if removed {
+            self.rewrite()?;
+        }
+        Ok(removed)
+    }
+
+    /// 完全匹配的译文
+    pub fn exact(&self, from: &str, to: &str, source: &str) -> Option<String> {
+        let key = (normalize_language(from), normalize_language(to));
+        self.segments
+            .read()
+            .get(&key)?
+            .entries
+            .get(&normalize_text(source))
+            .map(|entry| entry.target.clone())
+    }
+
+    /// 相似度不低于 `threshold` 的句段，按相似度降序返回至多 `limit` 条
+    ///
+    /// `text` 整体及其中的每一行分别与记忆比较，同一句段只保留最高得分。
+    pub fn fuzzy(
+        &self,
+        from: &str,
+        to: &str,
+        text: &str,
+        threshold: f64,
+        limit: usize,
+    ) -> Vec<MemoryMatch> {
+        if limit == 0 {
+            return Vec::new();
+        }
+        let key = (normalize_language(from), normalize_language(to));
+        let segments = self.segments.read();
+        let Some(pair) = segments.get(&key) else {
+            return Vec::new();
+        };
+
+        let mut queries: Vec<String> = vec![normalize_text(text)];
+        for line in text.lines() {
+            if queries.len() >= MAX_FUZZY_QUERIES {
+                break;
+            }
+            let line = normalize_text(line);
+            if line.chars().count() >= 3 && !queries.contains(&line) {
+                queries.push(line);
+            }
+        }
+
+        let mut best: HashMap<&str, f64> = HashMap::new();
+        for query in &queries {
+            let candidates =
+                pair.candidates(query.chars().count(), threshold, MAX_FUZZY_CANDIDATES);
+            for source in candidates {
+                let score = strsim::normalized_levenshtein(query, source);
+                if score >= threshold {
+                    let slot = best.entry(source).or_insert(0.0);
+                    *slot = slot.max(score);
+                }
+            }
+        }
+
+        let mut matches: Vec<MemoryMatch> = best
+            .into_iter()
+            .map(|(source, score)| MemoryMatch {
+                source: pair.entries[source].source.clone(),
+                target: pair.entries[source].target.clone(),
+                score,
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.source.cmp(&b.source)));
+        matches.truncate(limit);
+        matches
+    }
+
+    /// 按语种对和关键字列出句段，按审定时间倒序
+    pub fn list(
+        &self,
+        from: Option<&str>,
+        to: Option<&str>,
+        query: Option<&str>,
+        limit: usize,
+    ) -> Vec<MemoryEntry> {
+        let (from, to) = (from.map(normalize_language), to.map(normalize_language));
+        let query = query.map(str::to_lowercase);
+        let segments = self.segments.read();
+        let mut entries: Vec<MemoryEntry> = segments
+            .iter()
+            .filter(|((f, t), _)| {
+                from.as_ref().is_none_or(|from| from == f) && to.as_ref().is_none_or(|to| to == t)
+            })
+            .flat_map(|(_, pair)| pair.entries.values())
+            .filter(|entry| {
+                query.as_ref().is_none_or(|q| {
+                    entry.source.to_lowercase().contains(q)
+                        || entry.target.to_lowercase().contains(q)
+                })
+            })
+            .cloned()
+            .collect();
+        entries.sort_by(|a, b| {
+            b.created_at
+                .cmp(&a.created_at)
+                .then(a.source.cmp(&b.source))
+        });
+        entries.truncate(limit);
+        entries
+    }
+
+    /// 各语种对的句段数量
+    pub fn pairs(&self) -> Vec<PairCount> {
+        let mut pairs: Vec<PairCount> = self
+            .segments
+            .read()
+            .iter()
+            .filter(|(_, pair)| !pair.entries.is_empty())
+            .map(|((from, to), pair)| PairCount {
+                from: from.clone(),
+                to: to.clone(),
+                count: pair.entries.len(),
+            })
+            .collect();
+        pairs.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+        pairs
+    }
+
+    /// 把当前记忆完整写回文件（先写临时文件再替换）
+    fn rewrite(&self) -> std::io::Result<()> {
+        let segments = self.segments.read();
+        let mut entries: Vec<&MemoryEntry> = segments
+            .values()
+            .flat_map(|pair| pair.entries.values())
+            .collect();
+        entries.sort_by(|a, b| (&a.created_at, &a.source).cmp(&(&b.created_at, &b.source)));
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        let temp = self.path.with_extension("jsonl.tmp");
+        fs::write(&temp, content)?;
+        fs::rename(&temp, &self.path)
+    }
+}
+
+/// 把 RFC 3339 时间统一为 UTC，使按字符串排序与时间先后一致；无法解析时原样保留
+fn utc_timestamp(value: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&chrono::Utc).to_rfc3339())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+fn insert(segments: &mut Segments, entry: MemoryEntry) {
+    segments
+        .entry((entry.from.clone(), entry.to.clone()))
+        .or_default()
+        .insert(entry);
+}
+
+/// 合并连续空白并去掉首尾空白
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 把语种名称或语言代码统一为规范的语言代码
+///
+/// 已知语种名称（如 `英语`）转换为对应代码；中文按文字或地区区分简繁（`zh-CN` → `zh-Hans`，
+/// `zh-TW` → `zh-Hant`）；其余代码只保留语言和文字（`en-US` → `en`，`sr-latn-RS` → `sr-Latn`）。
+pub fn normalize_language(language: &str) -> String {
+    let language = language.trim();
+    if let Some((_, code)) = LANGUAGES.iter().find(|(name, _)| *name == language) {
+        return code.to_string();
+    }
+    let tag = language.replace('_', "-").to_lowercase();
+    let mut subtags = tag.split('-').filter(|s| !s.is_empty());
+    let Some(primary) = subtags.next() else {
+        return String::new();
+    };
+    let rest: Vec<&str> = subtags.collect();
+    if primary == "zh" {
+        let traditional = rest
+            .iter()
+            .any(|s| matches!(*s, "hant" | "tw" | "hk" | "mo"));
+        return if traditional { "zh-Hant" } else { "zh-Hans" }.to_string();
+    }
+    // 四个字母的子标签为文字，首字母大写
+    match rest
+        .iter()
+        .find(|s| s.len() == 4 && s.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        Some(script) => format!("{}-{}{}", primary, script[..1].to_uppercase(), &script[1..]),
+        None => primary.to_string(),
+    }
+}
+
+/// 句段重复时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// 以新译文为准
+    #[default]
+    Overwrite,
+    /// 保留已有译文
+    Skip,
+}
+
+/// 批量存入的结果
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct ImportStats {
+    /// 新增的句段数
+    pub added: usize,
+    /// 译文被更新的句段数
+    pub updated: usize,
+    /// 跳过的句段数（译文相同、保留已有译文或内容为空）
+    pub skipped: usize,
+}
+
+/// 原文/译文对
+#[derive(Debug, Clone, Deserialize)]
+pub struct SegmentPair {
+    pub source: String,
+    pub target: String,
+}
+
+/// 各语种对的句段数量
+#[derive(Debug, Serialize)]
+pub struct PairCount {
+    pub from: String,
+    pub to: String,
+    pub count: usize,
+}
+
+/// 审定句段请求
+#[derive(Debug, Deserialize)]
+pub struct ApproveRequest {
+    /// 源语言，语种名称或语言代码
+    pub from: String,
+    /// 目标语言，语种名称或语言代码
+    pub to: String,
+    pub segments: Vec<SegmentPair>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MemoryQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// 原文或译文中包含的关键字
+    pub q: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    /// 只导入该源语言的句段
+    pub from: Option<String>,
+    /// 只导入该目标语言的句段
+    pub to: Option<String>,
+    /// 原文已存在且译文不同时的处理方式：`overwrite`（默认）或 `skip`
+    #[serde(default)]
+    pub duplicates: DuplicatePolicy,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MemoryDelete {
+    pub from: String,
+    pub to: String,
+    pub source: String,
+}
+
+/// 把审定的句段存入翻译记忆
+#[post("/admin/memory/approve")]
+pub async fn approve_segments(
+    req: web::Json<ApproveRequest>,
+    memory: web::Data<TranslationMemory>,
+) -> AppResult<HttpResponse> {
+    if req.segments.is_empty() {
+        return Err(AppError::Validation("segments 不能为空".to_string()));
+    }
+    let stats = memory.approve(&req.from, &req.to, &req.segments)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "added": stats.added,
+        "updated": stats.updated,
+        "skipped": stats.skipped,
+        "total": memory.len(),
+    })))
+}
+
+/// 查询翻译记忆：各语种对的数量和匹配的句段
+#[get("/admin/memory")]
+pub async fn list_memory(
+    query: web::Query<MemoryQuery>,
+    memory: web::Data<TranslationMemory>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({
+        "pairs": memory.pairs(),
+        "entries": memory.list(
+            query.from.as_deref(),
+            query.to.as_deref(),
+            query.q.as_deref().filter(|q| !q.is_empty()),
+            query.limit.unwrap_or(DEFAULT_LIST_LIMIT),
+        ),
+    }))
+}
+
+/// 导入 TMX 文件，请求体为 TMX 原文
+///
+/// 路由在 main.rs 中注册，单独放宽请求体上限
+pub async fn import_memory(
+    query: web::Query<ImportQuery>,
+    body: web::Bytes,
+    memory: web::Data<TranslationMemory>,
+) -> AppResult<HttpResponse> {
+    if body.is_empty() {
+        return Err(AppError::Validation("TMX 内容不能为空".to_string()));
+    }
+    let result = tmx::import(
+        &memory,
+        &body,
+        query.from.as_deref(),
+        query.to.as_deref(),
+        query.duplicates,
+    )?;
+    log::info!(
+        "导入 TMX：新增 {}，更新 {}，跳过 {}",
+        result.stats.added,
+        result.stats.updated,
+        result.stats.skipped
+    );
+    let mut response = serde_json::to_value(&result)?;
+    response["success"] = true.into();
+    response["total"] = memory.len().into();
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// 导出为 TMX，可按语种对过滤
+#[get("/admin/memory/export")]
+pub async fn export_memory(
+    query: web::Query<MemoryQuery>,
+    memory: web::Data<TranslationMemory>,
+) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(tmx::CONTENT_TYPE)
+        .insert_header(("Content-Disposition", "attachment; filename=\"memory.tmx\""))
+        .body(tmx::export(
+            &memory,
+            query.from.as_deref(),
+            query.to.as_deref(),
+        ))
+}
+
+/// 删除句段
+#[delete("/admin/memory")]
+pub async fn delete_memory(
+    req: web::Json<MemoryDelete>,
+    memory: web::Data<TranslationMemory>,
+) -> AppResult<HttpResponse> {
+    if !memory.remove(&req.from, &req.to, &req.source)? {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "message": "句段不存在"
+        })));
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "message": "句段已删除"
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_language() {
+        assert_eq!(normalize_language("英语"), "en");
+        assert_eq!(normalize_language("en-US"), "en");
+        assert_eq!(normalize_language("zh_CN"), "zh-Hans");
+        assert_eq!(normalize_language("zh-Hant-TW"), "zh-Hant");
+        assert_eq!(normalize_language("中文(繁体)"), "zh-Hant");
+        assert_eq!(normalize_language("nl-NL"), "nl");
+        assert_eq!(normalize_language("sr_Latn_RS"), "sr-Latn");
+    }
+
+    #[test]
+    fn test_exact_fuzzy_and_reload() {
+        let path =
+            std::env::temp_dir().join(format!("rustrans-memory-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let memory = TranslationMemory::open(&path).unwrap();
+        let pair = |source: &str, target: &str| SegmentPair {
+            source: source.to_string(),
+            target: target.to_string(),
+        };
+        memory
+            .approve(
+                "英语",
+                "zh-CN",
+                &[
+                    pair(
+                        "Fixed a crash when opening large files.",
+                        "修复了打开大文件时崩溃的问题。",
+                    ),
+                    pair("Improved startup time.", "缩短了启动时间。"),
+                ],
+            )
+            .unwrap();
+        memory
+            .approve(
+                "en",
+                "zh-Hans",
+                &[pair("Improved  startup time. ", "启动更快了。")],
+            )
+            .unwrap();
+
+        assert_eq!(
+            memory
+                .exact("英语", "中文(简体)", "Improved startup time.")
+                .as_deref(),
+            Some("启动更快了。")
+        );
+        assert_eq!(memory.exact("英语", "日语", "Improved startup time."), None);
+
+        let matches = memory.fuzzy(
+            "英语",
+            "中文(简体)",
+            "New features\nFixed a crash when opening large images.",
+            0.75,
+            3,
+        );
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].target, "修复了打开大文件时崩溃的问题。");
+        assert!(matches[0].score > 0.8 && matches[0].score < 1.0);
+
+        // 重新加载时合并重复句段，以最后审定的译文为准
+        let reopened = TranslationMemory::open(&path).unwrap();
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        assert!(reopened
+            .remove("en", "zh-Hans", "Improved startup time.")
+            .unwrap());
+        assert_eq!(TranslationMemory::open(&path).unwrap().len(), 1);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_import_normalizes_created_at() {
+        let path =
+            std::env::temp_dir().join(format!("rustrans-memory-utc-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let memory = TranslationMemory::open(&path).unwrap();
+        let entry = |source: &str, created_at: &str| MemoryEntry {
+            from: "en".to_string(),
+            to: "ja".to_string(),
+            source: source.to_string(),
+            target: "訳文".to_string(),
+            created_at: created_at.to_string(),
+        };
+        // 本地时间 20:00+08:00 早于 UTC 13:00，按字符串比较则顺序相反
+        memory
+            .import(
+                vec![
+                    entry("Local", "2024-01-31T20:00:00+08:00"),
+                    entry("Utc", "2024-01-31T13:00:00+00:00"),
+                ],
+                DuplicatePolicy::Overwrite,
+            )
+            .unwrap();
+        let listed = memory.list(None, None, None, 10);
+        assert_eq!(listed[0].source, "Utc");
+        assert_eq!(listed[1].created_at, "2024-01-31T12:00:00+00:00");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_fuzzy_candidates() {
+        let mut pair = Pair::default();
+        for length in 1..=200 {
+            for variant in 0..50 {
+                pair.insert(MemoryEntry {
+                    from: "en".to_string(),
+                    to: "zh-Hans".to_string(),
+                    source: format!("{}{}", variant, "x".repeat(length)),
+                    target: "译文".to_string(),
+                    created_at: String::new(),
+                });
+            }
+        }
+        let candidates = pair.candidates(100, 0.8, usize::MAX);
+        assert!(candidates
+            .iter()
+            .all(|source| (80..=125).contains(&source.chars().count())));
+        let nearest = pair.candidates(100, 0.8, 60);
+        assert_eq!(nearest.len(), 60);
+        assert!(nearest[..50]
+            .iter()
+            .all(|source| source.chars().count() == 100));
+
+        let removed = pair.remove(&normalize_text(&format!("0{}", "x".repeat(99))));
+        assert!(removed.is_some());
+        assert_eq!(pair.candidates(100, 1.0, usize::MAX).len(), 49);
+    }
+}
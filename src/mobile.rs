@@ -0,0 +1,217 @@
+// 移动端字符串资源翻译模块
+//
+// 支持 Android 的 strings.xml 以及 Apple 的 .strings、.stringsdict 和 .xcstrings。
+// 各格式的解析和写回分别在 android.rs、apple.rs 中实现，这里负责格式识别和批量翻译：
+// 每条文案作为一个片段，键名和注释作为补充说明；复数文案按目标语言的 CLDR 复数类别
+// 重新生成，缺少的类别以原文的 other 形式为基础翻译。
+
+use crate::android;
+use crate::apple;
+use crate::config::Config;
+use crate::error::AppResult;
+use crate::placeholder::Masked;
+use crate::po::plural_rule;
+use crate::segments::translate_segments_with_notes;
+use crate::translator::TranslateOptions;
+use serde::Deserialize;
+
+/// 移动端字符串资源格式
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MobileFormat {
+    /// Android `res/values/strings.xml`
+    Android,
+    /// Apple `Localizable.strings`
+    Strings,
+    /// Apple `Localizable.stringsdict`
+    Stringsdict,
+    /// Apple 字符串目录 `Localizable.xcstrings`
+    Xcstrings,
+}
+
+impl MobileFormat {
+    /// 根据内容判断格式
+    pub fn detect(text: &str) -> Self {
+        let head = text.trim_start();
+        if head.starts_with('{') {
+            MobileFormat::Xcstrings
+        } else if text.contains("<resources") {
+            MobileFormat::Android
+        } else if text.contains("<plist") {
+            MobileFormat::Stringsdict
+        } else {
+            MobileFormat::Strings
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            MobileFormat::Android | MobileFormat::Stringsdict => "application/xml; charset=utf-8",
+            MobileFormat::Strings => "text/plain; charset=utf-8",
+            MobileFormat::Xcstrings => "application/json; charset=utf-8",
+        }
+    }
+}
+
+/// 一条待翻译的文案
+pub struct Message {
+    /// 键名，作为补充说明提供给模型
+    pub key: String,
+    /// 开发者留给译者的注释
+    pub comment: Option<String>,
+    /// 复数文案的各类别及原文；普通文案只有一个类别为空的形式
+    pub forms: Vec<(String, Masked)>,
+}
+
+impl Message {
+    pub fn single(key: String, comment: Option<String>, source: Masked) -> Self {
+        Message {
+            key,
+            comment,
+            forms: vec![(String::new(), source)],
+        }
+    }
+
+    pub fn is_plural(&self) -> bool {
+        !matches!(&self.forms[..], [(category, _)] if category.is_empty())
+    }
+
+    /// 目标语言某个复数类别对应的原文：同名类别，其次为 other
+    fn source_for(&self, category: &str) -> &Masked {
+        self.forms
+            .iter()
+            .find(|(c, _)| c == category)
+            .or_else(|| self.forms.iter().find(|(c, _)| c == "other"))
+            .or(self.forms.last())
+            .map(|(_, masked)| masked)
+            .expect("文案至少有一个形式")
+    }
+}
+
+/// 一条文案的译文：`(复数类别, 译文)`，普通文案的类别为空
+pub type Translation = Vec<(String, String)>;
+
+/// 翻译后的文件及统计
+#[derive(Debug)]
+pub struct MobileOutput {
+    pub content: String,
+    /// 翻译成功的文案数
+    pub translated: usize,
+    /// 翻译失败、保留原文的文案数
+    pub failed: usize,
+}
+
+/// 目标语言的 CLDR 复数类别及各类别适用的数量，未知语言按英语处理
+pub fn plural_categories(to_lang: &str) -> &'static [(&'static str, &'static str)] {
+    plural_rule(to_lang).categories
+}
+
+/// 所有文案的原文，用于语种检测
+pub fn source_text(text: &str, format: MobileFormat) -> AppResult<String> {
+    let messages = match format {
+        MobileFormat::Android => android::parse(text)?.messages,
+        MobileFormat::Strings => apple::parse_strings(text)?.messages,
+        MobileFormat::Stringsdict => apple::parse_stringsdict(text)?.messages,
+        MobileFormat::Xcstrings => apple::parse_xcstrings(text, None)?.messages,
+    };
+    Ok(messages
+        .iter()
+        .flat_map(|m| m.forms.iter().map(|(_, masked)| masked.source.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// 翻译移动端字符串资源文件
+pub async fn translate(
+    config: &Config,
+    text: &str,
+    format: MobileFormat,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<MobileOutput> {
+    let (content, results) = match format {
+        MobileFormat::Android => {
+            let doc = android::parse(text)?;
+            let results =
+                translate_messages(config, &doc.messages, from_lang, to_lang, options).await?;
+            (doc.render(text, &results), results)
+        }
+        MobileFormat::Strings => {
+            let doc = apple::parse_strings(text)?;
+            let results =
+                translate_messages(config, &doc.messages, from_lang, to_lang, options).await?;
+            (doc.render(text, &results), results)
+        }
+        MobileFormat::Stringsdict => {
+            let doc = apple::parse_stringsdict(text)?;
+            let results =
+                translate_messages(config, &doc.messages, from_lang, to_lang, options).await?;
+            (doc.render(text, &results), results)
+        }
+        MobileFormat::Xcstrings => {
+            let doc = apple::parse_xcstrings(text, Some(to_lang))?;
+            let results =
+                translate_messages(config, &doc.messages, from_lang, to_lang, options).await?;
+            (doc.render(to_lang, &results)?, results)
+        }
+    };
+    let failed = results.iter().filter(|r| r.is_none()).count();
+    Ok(MobileOutput {
+        content,
+        translated: results.len() - failed,
+        failed,
+    })
+}
+
+/// 翻译一组文案，返回与输入一一对应的译文；复数文案按目标语言的复数类别展开，
+/// 任一形式的占位符无法还原时整条文案返回 None
+async fn translate_messages(
+    config: &Config,
+    messages: &[Message],
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<Vec<Option<Translation>>> {
+    let categories = plural_categories(to_lang);
+
+    let mut units: Vec<(usize, String)> = Vec::new();
+    let mut segments: Vec<Masked> = Vec::new();
+    let mut notes: Vec<Option<String>> = Vec::new();
+    for (index, message) in messages.iter().enumerate() {
+        let mut note = vec![format!("键：{}", message.key)];
+        if let Some(comment) = &message.comment {
+            note.push(format!("注释：{}", comment));
+        }
+        if !message.is_plural() {
+            units.push((index, String::new()));
+            segments.push(message.forms[0].1.clone());
+            notes.push(Some(note.join("；")));
+            continue;
+        }
+        let singular = message.source_for("one").source.clone();
+        for &(category, counts) in categories {
+            let mut note = note.clone();
+            note.push(format!(
+                "这是复数文案的 {} 形式，用于数量为 {} 的情况；原文单数形式为「{}」",
+                category, counts, singular
+            ));
+            units.push((index, category.to_string()));
+            segments.push(message.source_for(category).clone());
+            notes.push(Some(note.join("；")));
+        }
+    }
+
+    let results =
+        translate_segments_with_notes(config, &segments, &notes, from_lang, to_lang, options)
+            .await?;
+
+    let mut translations: Vec<Option<Translation>> = vec![Some(Vec::new()); messages.len()];
+    for ((index, category), result) in units.into_iter().zip(results) {
+        match (result, &mut translations[index]) {
+            (Some(text), Some(forms)) => forms.push((category, text)),
+            _ => translations[index] = None,
+        }
+    }
+    Ok(translations)
+}
@@ -1,5 +1,12 @@
 // 数据模型模块
 
+use crate::alignment::{AlignedPair, ParagraphAlignment};
+use crate::config::ParamOverrides;
+use crate::error::{AppError, AppResult};
+use crate::glossary::GlossaryEntry;
+use crate::memory::MemoryMatch;
+use crate::mobile::MobileFormat;
+use crate::resources::ResourceFormat;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
@@ -10,13 +17,187 @@ pub struct TranslateRequest {
     #[allow(dead_code)]
     pub name: String,
 
-    #[validate(length(min = 1, max = 10000, message = "文本长度必须在1-10000字符之间"))]
+    /// 待翻译文本，最大长度由配置项 `chunking.max_input_chars` 决定
+    #[validate(length(min = 1, message = "文本不能为空"))]
     pub text: String,
 
     #[validate(length(min = 1, max = 10, message = "目标语言列表不能为空且不能超过10个"))]
     pub destination: Vec<String>,
 
     pub source: Option<String>,
+
+    /// 使用的提示词预设名称
+    pub preset: Option<String>,
+
+    /// 领域，用于选择少样本示例；缺省时使用预设名称
+    pub domain: Option<String>,
+
+    /// 补充上下文（如前后文、使用场景），通过 `context` 变量提供给提示词模板
+    pub context: Option<String>,
+
+    /// 文本格式
+    #[serde(default)]
+    pub format: TextFormat,
+
+    /// 输出模式
+    #[serde(default)]
+    pub mode: OutputMode,
+
+    /// 是否返回段落/句子级的原文译文对齐结果
+    #[serde(default)]
+    pub align: bool,
+
+    /// 请求级模型参数覆盖（受管理员设置的上下限约束）
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub top_p: Option<f32>,
+    pub seed: Option<u64>,
+}
+
+/// PO/POT 文件翻译请求：在翻译请求的基础上增加 fuzzy 条目的处理方式
+#[derive(Debug, Deserialize)]
+pub struct PoTranslateRequest {
+    #[serde(flatten)]
+    pub request: TranslateRequest,
+
+    /// 同时翻译标记为 fuzzy 的条目
+    #[serde(default)]
+    pub include_fuzzy: bool,
+
+    /// 为机器翻译的条目加上 fuzzy 标记
+    #[serde(default)]
+    pub mark_fuzzy: bool,
+}
+
+/// 后台文本任务请求
+#[derive(Debug, Deserialize)]
+pub struct JobRequest {
+    #[serde(flatten)]
+    pub request: TranslateRequest,
+
+    /// 任务完成或失败后通知的回调地址
+    pub callback_url: Option<String>,
+}
+
+/// 国际化资源文件（JSON、YAML、Fluent）翻译请求
+#[derive(Debug, Deserialize)]
+pub struct ResourceTranslateRequest {
+    #[serde(flatten)]
+    pub request: TranslateRequest,
+
+    /// 文件格式，未指定时根据内容判断
+    #[serde(default)]
+    pub resource_format: Option<ResourceFormat>,
+
+    /// 上一版原文，与 `previous_target` 同时提供时只翻译新增或变化的键
+    #[serde(default)]
+    pub previous_source: Option<String>,
+
+    /// 上一版译文
+    #[serde(default)]
+    pub previous_target: Option<String>,
+}
+
+/// 移动端字符串资源（Android strings.xml、Apple .strings/.stringsdict/.xcstrings）翻译请求
+#[derive(Debug, Deserialize)]
+pub struct MobileTranslateRequest {
+    #[serde(flatten)]
+    pub request: TranslateRequest,
+
+    /// 文件格式，未指定时根据内容判断
+    #[serde(default)]
+    pub mobile_format: Option<MobileFormat>,
+}
+
+/// Office 文档和书籍翻译的查询参数，文件内容作为请求体上传
+#[derive(Debug, Deserialize)]
+pub struct DocumentQuery {
+    /// 目标语言，多个以逗号分隔，含义同 `destination`
+    pub to: String,
+    pub from: Option<String>,
+    pub preset: Option<String>,
+    pub domain: Option<String>,
+    pub context: Option<String>,
+    /// 书籍翻译时生成原文与译文对照的双语版本
+    #[serde(default)]
+    pub bilingual: bool,
+    /// 后台任务结束后通知的回调地址
+    pub callback_url: Option<String>,
+}
+
+impl DocumentQuery {
+    /// 以文档中提取的文字构造翻译请求，复用请求校验和选项构建
+    pub fn into_request(self, text: String) -> TranslateRequest {
+        TranslateRequest {
+            name: "document".to_string(),
+            text,
+            destination: self
+                .to
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            source: self.from,
+            preset: self.preset,
+            domain: self.domain,
+            context: self.context,
+            format: TextFormat::Text,
+            mode: OutputMode::Translation,
+            align: false,
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            seed: None,
+        }
+    }
+}
+
+/// 待翻译文本的格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextFormat {
+    /// 纯文本
+    #[default]
+    Text,
+    /// Markdown：只翻译文字，保留代码、链接地址等结构
+    Markdown,
+    /// HTML 片段：只翻译文字和 alt/title 等属性，保留标签
+    Html,
+}
+
+/// 翻译结果的输出模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMode {
+    /// `result` 只包含译文段落
+    #[default]
+    Translation,
+    /// `result` 中原文段落与译文段落交替排列，并通过 `pairs` 返回对应关系
+    Bilingual,
+}
+
+impl TranslateRequest {
+    /// 检查文本长度是否超过配置的上限
+    pub fn check_length(&self, max_chars: usize) -> AppResult<()> {
+        let chars = self.text.chars().count();
+        if chars > max_chars {
+            return Err(AppError::Validation(format!(
+                "文本长度 {} 超过上限 {} 字符",
+                chars, max_chars
+            )));
+        }
+        Ok(())
+    }
+
+    /// 提取请求中的模型参数覆盖
+    pub fn param_overrides(&self) -> ParamOverrides {
+        ParamOverrides {
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            top_p: self.top_p,
+            seed: self.seed,
+        }
+    }
 }
 
 // 划词翻译响应结构
@@ -36,6 +217,84 @@ pub struct TranslateResponse {
     pub dict: Option<Vec<DictEntry>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<Vec<String>>,
+    /// 原文/译文段落对（双语模式下返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pairs: Option<Vec<AlignedPair>>,
+    /// 原文与译文的对齐结果（请求 `align` 为 true 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alignment: Option<Vec<ParagraphAlignment>>,
+    /// 译文中缺失或重复的变量占位符（没有问题时不返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder_errors: Option<Vec<String>>,
+}
+
+impl TranslateResponse {
+    /// 构建只包含一条提示信息的响应（错误、校验失败等）
+    pub fn message(text: &str, from: String, to: String, message: String) -> Self {
+        Self {
+            text: text.to_string(),
+            from,
+            to,
+            tts_uri: None,
+            link: None,
+            phonetic: None,
+            dict: None,
+            result: Some(vec![message]),
+            pairs: None,
+            alignment: None,
+            placeholder_errors: None,
+        }
+    }
+}
+
+/// 翻译请求的解释（不调用大模型）
+#[derive(Debug, Serialize)]
+pub struct ExplainResponse<'a> {
+    /// 源语种判定
+    pub source: SourceExplanation,
+    /// 选定的目标语种
+    pub target: String,
+    /// 目标语种的选择依据
+    pub target_reason: String,
+    /// 使用的提示词预设
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    /// 挑选少样本示例使用的领域
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    /// 将要发送给大模型的第一个完整请求体，全部复用翻译记忆时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<LLMRequest>,
+    /// 命中的术语
+    pub glossary: Vec<&'a GlossaryEntry>,
+    /// 将要发送的请求数量（长文本的分块或结构化格式的片段批次）
+    pub chunks: usize,
+    /// 估算的输入 token 数（所有请求合计）
+    pub estimated_tokens: usize,
+    /// 按实际翻译路径逐个分块或片段查询翻译记忆的结果：`hit`（全部复用，不调用大模型）、
+    /// `partial`、`miss` 或 `disabled`
+    pub cache: &'static str,
+    /// 翻译记忆：`exact`（完全匹配，不调用大模型）、`fuzzy`（附带相似句段作参考）、`none` 或 `disabled`
+    pub memory: &'static str,
+    /// 命中的翻译记忆句段
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub memory_matches: Vec<MemoryMatch>,
+    /// 翻译服务是否已配置
+    pub configured: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourceExplanation {
+    /// 最终使用的源语种
+    pub language: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+    /// 是否由请求的 `source` 字段指定
+    pub specified: bool,
+    /// 自动检测的结果（即使请求指定了源语种也会给出）
+    pub detected: String,
+    /// 检测结果的置信度
+    pub confidence: f32,
 }
 
 #[derive(Debug, Serialize)]
@@ -62,6 +321,12 @@ pub struct LLMRequest {
     pub model: String,
     pub messages: Vec<Message>,
     pub temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -73,6 +338,14 @@ pub struct Message {
 #[derive(Debug, Deserialize)]
 pub struct LLMResponse {
     pub choices: Vec<Choice>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+/// 大模型接口返回的 token 用量
+#[derive(Debug, Deserialize)]
+pub struct Usage {
+    pub total_tokens: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -0,0 +1,650 @@
+// Office 文档翻译模块
+//
+// DOCX、PPTX、XLSX 都是 ZIP 包中的 XML 部件。这里找出文字所在的部件（Word 的正文、
+// 页眉页脚和脚注，PowerPoint 的幻灯片和备注，Excel 的共享字符串和内联字符串），按段落翻译：
+// 段落中格式相同且相邻的文字段合并，格式不同的部分之间以占位符分隔，使模型看到完整的句子；
+// 译文按占位符拆回各格式段，写入该段的第一个文字元素，其余文字元素清空。
+// 其余部件和 XML 结构原样保留。
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::markup::{self, Token, TokenKind};
+use crate::placeholder::{self, Masked};
+use crate::segments::translate_segments_with_notes;
+use crate::translator::TranslateOptions;
+use serde::Deserialize;
+use std::io::{Cursor, Read, Write};
+use std::ops::Range;
+
+lazy_static::lazy_static! {
+    /// 不影响显示效果、比较格式时忽略的属性和元素（语言、拼写检查、修订标识）
+    static ref IGNORED_PROPERTIES: regex::Regex = regex::Regex::new(
+        r#"(?i)\s(lang|altlang|dirty|err|noproof|smtclean|w:rsidrpr|w:rsidr)="[^"]*"|<w:(lang|noproof)\b[^>]*/>|\s+"#,
+    )
+    .unwrap();
+    static ref WORD_PART: regex::Regex =
+        regex::Regex::new(r"^word/(document|header\d*|footer\d*|footnotes|endnotes)\.xml$").unwrap();
+    static ref SLIDE_PART: regex::Regex =
+        regex::Regex::new(r"^ppt/(slides/slide|notesSlides/notesSlide)\d+\.xml$").unwrap();
+    static ref SHEET_PART: regex::Regex =
+        regex::Regex::new(r"^xl/(sharedStrings|worksheets/sheet\d+)\.xml$").unwrap();
+}
+
+/// 上传文档的大小上限
+pub const MAX_DOCUMENT_BYTES: usize = 50 * 1024 * 1024;
+
+/// 包中单个条目解压后的大小上限
+const MAX_ENTRY_BYTES: u64 = 100 * 1024 * 1024;
+
+/// 包中全部条目解压后的总大小上限
+const MAX_PACKAGE_BYTES: u64 = 200 * 1024 * 1024;
+
+/// 格式段之间的分隔标记，占位符还原后用于拆分译文
+const GROUP_MARK: char = '\u{1}';
+
+/// Office 文档格式
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    Docx,
+    Pptx,
+    Xlsx,
+}
+
+impl DocumentFormat {
+    /// 根据包中的主部件判断格式
+    fn from_part(name: &str) -> Option<Self> {
+        match name {
+            "word/document.xml" => Some(DocumentFormat::Docx),
+            "ppt/presentation.xml" => Some(DocumentFormat::Pptx),
+            "xl/workbook.xml" => Some(DocumentFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            DocumentFormat::Docx => {
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+            }
+            DocumentFormat::Pptx => {
+                "application/vnd.openxmlformats-officedocument.presentationml.presentation"
+            }
+            DocumentFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            DocumentFormat::Docx => "docx",
+            DocumentFormat::Pptx => "pptx",
+            DocumentFormat::Xlsx => "xlsx",
+        }
+    }
+
+    /// 是否为需要翻译的部件
+    fn is_text_part(self, name: &str) -> bool {
+        match self {
+            DocumentFormat::Docx => WORD_PART.is_match(name),
+            DocumentFormat::Pptx => SLIDE_PART.is_match(name),
+            DocumentFormat::Xlsx => SHEET_PART.is_match(name),
+        }
+    }
+
+    fn schema(self) -> &'static Schema {
+        match self {
+            DocumentFormat::Docx => &WORD,
+            DocumentFormat::Pptx => &SLIDES,
+            DocumentFormat::Xlsx => &SHEETS,
+        }
+    }
+}
+
+/// 各格式中段落、文字段和格式的元素名（小写）
+struct Schema {
+    paragraphs: &'static [&'static str],
+    run: &'static str,
+    properties: &'static str,
+    text: &'static str,
+    /// 其中的文字不翻译的元素
+    skipped: &'static [&'static str],
+    /// 不打断格式段的元素
+    neutral: &'static [&'static str],
+    /// 文字元素是否需要 `xml:space="preserve"` 保留首尾空格
+    preserve_space: bool,
+}
+
+const WORD: Schema = Schema {
+    paragraphs: &["w:p"],
+    run: "w:r",
+    properties: "w:rpr",
+    text: "w:t",
+    skipped: &["w:ppr", "w:instrtext", "w:deltext"],
+    neutral: &[
+        "w:prooferr",
+        "w:bookmarkstart",
+        "w:bookmarkend",
+        "w:lastrenderedpagebreak",
+        "w:hyperlink",
+        "w:smarttag",
+        "w:ins",
+        "w:permstart",
+        "w:permend",
+        "w:commentrangestart",
+        "w:commentrangeend",
+    ],
+    preserve_space: true,
+};
+
+const SLIDES: Schema = Schema {
+    paragraphs: &["a:p"],
+    run: "a:r",
+    properties: "a:rpr",
+    text: "a:t",
+    skipped: &["a:ppr", "a:fld"],
+    neutral: &["a:endpararpr"],
+    preserve_space: false,
+};
+
+const SHEETS: Schema = Schema {
+    paragraphs: &["si", "is"],
+    run: "r",
+    properties: "rpr",
+    text: "t",
+    skipped: &["rph", "phoneticpr"],
+    neutral: &[],
+    preserve_space: true,
+};
+
+/// 根据包中的部件判断是否为 Office 文档，不解压内容
+pub fn detect(bytes: &[u8]) -> Option<DocumentFormat> {
+    let archive = zip::ZipArchive::new(Cursor::new(bytes)).ok()?;
+    let format = archive.file_names().find_map(DocumentFormat::from_part);
+    format
+}
+
+/// ZIP 包中的一个条目
+pub struct PackageEntry {
+    pub name: String,
+    pub data: Vec<u8>,
+    compression: zip::CompressionMethod,
+    is_dir: bool,
+}
+
+/// 读取 ZIP 包中的全部条目
+pub fn read_package(bytes: &[u8]) -> AppResult<Vec<PackageEntry>> {
+    read_package_limited(bytes, MAX_ENTRY_BYTES, MAX_PACKAGE_BYTES)
+}
+
+/// 读取 ZIP 包，单个条目或全部条目解压后超过上限时拒绝。
+/// 先检查条目头中声明的大小，解压时再按实际读出的字节数检查，防止声明值被伪造
+fn read_package_limited(
+    bytes: &[u8],
+    entry_limit: u64,
+    total_limit: u64,
+) -> AppResult<Vec<PackageEntry>> {
+    let invalid = |e: zip::result::ZipError| AppError::Validation(format!("无法读取文档: {}", e));
+    let too_large = || {
+        AppError::Validation(format!(
+            "文档解压后过大（单个部件上限 {} MB，总计上限 {} MB）",
+            entry_limit / 1024 / 1024,
+            total_limit / 1024 / 1024
+        ))
+    };
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(invalid)?;
+
+    let mut declared = 0u64;
+    for i in 0..archive.len() {
+        let size = archive.by_index_raw(i).map_err(invalid)?.size();
+        declared = declared.saturating_add(size);
+        if size > entry_limit || declared > total_limit {
+            return Err(too_large());
+        }
+    }
+
+    let mut entries = Vec::with_capacity(archive.len());
+    let mut total = 0u64;
+    for i in 0..archive.len() {
+        let file = archive.by_index(i).map_err(invalid)?;
+        let name = file.name().to_string();
+        let compression = file.compression();
+        let is_dir = file.is_dir();
+        let limit = entry_limit.min(total_limit - total);
+        let mut data = Vec::new();
+        file.take(limit + 1).read_to_end(&mut data)?;
+        if data.len() as u64 > limit {
+            return Err(too_large());
+        }
+        total += data.len() as u64;
+        entries.push(PackageEntry {
+            name,
+            data,
+            compression,
+            is_dir,
+        });
+    }
+    Ok(entries)
+}
+
+/// 按原有顺序和压缩方式重新打包
+pub fn write_package(entries: &[PackageEntry]) -> AppResult<Vec<u8>> {
+    let failed = |e: zip::result::ZipError| AppError::Io(std::io::Error::other(e));
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for entry in entries {
+        let options =
+            zip::write::SimpleFileOptions::default().compression_method(entry.compression);
+        if entry.is_dir {
+            writer
+                .add_directory(entry.name.as_str(), options)
+                .map_err(failed)?;
+        } else {
+            writer
+                .start_file(entry.name.as_str(), options)
+                .map_err(failed)?;
+            writer.write_all(&entry.data)?;
+        }
+    }
+    Ok(writer.finish().map_err(failed)?.into_inner())
+}
+
+/// 翻译后的文档及统计
+pub struct DocumentOutput {
+    pub content: Vec<u8>,
+    pub format: DocumentFormat,
+    /// 翻译成功的段落数
+    pub translated: usize,
+    /// 翻译失败、保留原文的段落数
+    pub failed: usize,
+}
+
+/// 一个待翻译的段落
+struct Paragraph {
+    pieces: Vec<Piece>,
+    /// 格式段数量
+    groups: usize,
+}
+
+/// 段落中的一个文字元素
+struct Piece {
+    /// 整个元素
+    element: Range<usize>,
+    /// 元素内容
+    content: Range<usize>,
+    /// 所属格式段
+    group: usize,
+}
+
+/// 需要翻译的部件
+struct Part {
+    entry: usize,
+    text: String,
+    paragraphs: Vec<Paragraph>,
+}
+
+/// 打开文档并找出需要翻译的段落
+fn open(bytes: &[u8]) -> AppResult<(DocumentFormat, Vec<PackageEntry>, Vec<Part>)> {
+    let entries = read_package(bytes)?;
+    let format = entries
+        .iter()
+        .find_map(|e| DocumentFormat::from_part(&e.name))
+        .ok_or_else(|| {
+            AppError::Validation("不支持的文档格式，仅支持 DOCX、PPTX 和 XLSX".to_string())
+        })?;
+    let mut parts = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.is_dir || !format.is_text_part(&entry.name) {
+            continue;
+        }
+        let text = String::from_utf8(entry.data.clone())
+            .map_err(|_| AppError::Validation(format!("{} 不是 UTF-8 编码", entry.name)))?;
+        let paragraphs = paragraphs(&text, format.schema())
+            .into_iter()
+            .filter(|p| masked(&text, p).has_prose())
+            .collect();
+        parts.push(Part {
+            entry: i,
+            text,
+            paragraphs,
+        });
+    }
+    Ok((format, entries, parts))
+}
+
+/// 文档中所有待翻译段落的原文，用于语种检测和长度检查
+pub fn source_text(bytes: &[u8]) -> AppResult<String> {
+    let (_, _, parts) = open(bytes)?;
+    Ok(parts
+        .iter()
+        .flat_map(|part| part.paragraphs.iter().map(|p| masked(&part.text, p).source))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// 翻译 Office 文档
+pub async fn translate(
+    config: &Config,
+    bytes: &[u8],
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<DocumentOutput> {
+    let (format, mut entries, parts) = open(bytes)?;
+    let segments: Vec<Masked> = parts
+        .iter()
+        .flat_map(|part| part.paragraphs.iter().map(|p| masked(&part.text, p)))
+        .collect();
+    let mut results =
+        translate_segments_with_notes(config, &segments, &[], from_lang, to_lang, options)
+            .await?
+            .into_iter();
+
+    let schema = format.schema();
+    let mut failed = 0;
+    for part in &parts {
+        let mut edits = Vec::new();
+        for paragraph in &part.paragraphs {
+            let Some(translated) = results.next().flatten() else {
+                failed += 1;
+                continue;
+            };
+            let groups = split_groups(&translated, paragraph.groups);
+            edits.extend(paragraph.edits(&part.text, schema, &groups));
+        }
+        entries[part.entry].data = markup::apply_edits(&part.text, edits).into_bytes();
+    }
+
+    Ok(DocumentOutput {
+        content: write_package(&entries)?,
+        format,
+        translated: segments.len() - failed,
+        failed,
+    })
+}
+
+impl Paragraph {
+    /// 各格式段的译文写入该段第一个文字元素，其余文字元素清空
+    fn edits(&self, text: &str, schema: &Schema, groups: &[String]) -> Vec<(Range<usize>, String)> {
+        let mut written = vec![false; self.groups];
+        let mut edits = Vec::new();
+        for piece in &self.pieces {
+            if std::mem::replace(&mut written[piece.group], true) {
+                edits.push((piece.content.clone(), String::new()));
+                continue;
+            }
+            let value = &groups[piece.group];
+            // 保留原标签名的大小写和前缀
+            let tag = &text[piece.element.start + 1..piece.element.start + 1 + schema.text.len()];
+            let space = if schema.preserve_space && value.trim() != value {
+                " xml:space=\"preserve\""
+            } else {
+                ""
+            };
+            let start_tag = &text[piece.element.start..piece.content.start];
+            let start_tag = if space.is_empty() || start_tag.contains("xml:space") {
+                start_tag.to_string()
+            } else {
+                format!("<{}{}>", tag, space)
+            };
+            edits.push((
+                piece.element.clone(),
+                format!("{}{}</{}>", start_tag, markup::escape_text(value), tag),
+            ));
+        }
+        edits
+    }
+}
+
+/// 段落的待译文本：第一个格式段之后的每个格式段以占位符开头
+fn masked(text: &str, paragraph: &Paragraph) -> Masked {
+    let mut groups = vec![String::new(); paragraph.groups];
+    for piece in &paragraph.pieces {
+        groups[piece.group].push_str(&markup::unescape(&text[piece.content.clone()]));
+    }
+    let mut masked = groups[0].clone();
+    let mut originals = Vec::new();
+    for (k, group) in groups.iter().enumerate().skip(1) {
+        masked.push_str(&placeholder::token(k - 1));
+        masked.push_str(group);
+        originals.push(format!("{}{}{}", GROUP_MARK, k, GROUP_MARK));
+    }
+    Masked {
+        text: masked,
+        originals,
+        source: groups.concat(),
+        escape: None,
+    }
+}
+
+/// 按分隔标记把译文拆回各格式段；标记顺序被调换时整段译文放入第一个格式段
+fn split_groups(translated: &str, count: usize) -> Vec<String> {
+    let mut groups = vec![String::new(); count];
+    let mut parts = translated.split(GROUP_MARK);
+    groups[0].push_str(parts.next().unwrap_or_default());
+    let mut current = 0;
+    let mut ordered = true;
+    while let (Some(index), Some(part)) = (parts.next(), parts.next()) {
+        match index.parse::<usize>() {
+            Ok(k) if k > current && k < count => {
+                current = k;
+                groups[k].push_str(part);
+            }
+            _ => {
+                ordered = false;
+                break;
+            }
+        }
+    }
+    if !ordered {
+        let all: String = translated
+            .split(GROUP_MARK)
+            .enumerate()
+            .filter(|(i, _)| i % 2 == 0)
+            .map(|(_, part)| part)
+            .collect();
+        groups = vec![String::new(); count];
+        groups[0] = all;
+    }
+    groups
+}
+
+/// 正在收集的段落
+struct OpenParagraph {
+    pieces: Vec<Piece>,
+    groups: usize,
+    /// 上一个文字元素的格式
+    last_properties: Option<String>,
+    /// 自上一个文字元素以来是否出现了制表符、换行、图片等打断格式段的内容
+    interrupted: bool,
+}
+
+/// 找出 XML 部件中的所有段落
+fn paragraphs(text: &str, schema: &Schema) -> Vec<Paragraph> {
+    let tokens = markup::tokenize(text, &[]);
+    let mut result = Vec::new();
+    let mut stack: Vec<OpenParagraph> = Vec::new();
+    let mut properties = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        let (name, self_closing) = match &token.kind {
+            TokenKind::Start {
+                name, self_closing, ..
+            } => (name.as_str(), *self_closing),
+            TokenKind::End { name } => {
+                if schema.paragraphs.contains(&name.as_str()) {
+                    if let Some(open) = stack.pop() {
+                        if !open.pieces.is_empty() {
+                            result.push(Paragraph {
+                                pieces: open.pieces,
+                                groups: open.groups,
+                            });
+                        }
+                    }
+                }
+                i += 1;
+                continue;
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+
+        if schema.paragraphs.contains(&name) && !self_closing {
+            // 文本框等嵌套段落打断外层段落
+            if let Some(outer) = stack.last_mut() {
+                outer.interrupted = true;
+            }
+            stack.push(OpenParagraph {
+                pieces: Vec::new(),
+                groups: 0,
+                last_properties: None,
+                interrupted: false,
+            });
+        } else if name == schema.run {
+            properties.clear();
+        } else if name == schema.properties {
+            // 只比较格式元素的内容，空的格式元素与没有格式元素等同
+            if self_closing {
+                properties.clear();
+            } else {
+                let end = find_end(&tokens, i, name);
+                let inner = &text[token.range.end..tokens[end].range.start];
+                properties = IGNORED_PROPERTIES.replace_all(inner, "").into_owned();
+                i = end;
+            }
+        } else if name == schema.text {
+            if !self_closing {
+                let end = find_end(&tokens, i, name);
+                let content = token.range.end..tokens[end].range.start;
+                if let Some(open) = stack.last_mut().filter(|_| !content.is_empty()) {
+                    let same = !open.interrupted
+                        && open.last_properties.as_deref() == Some(properties.as_str());
+                    if !same {
+                        open.groups += 1;
+                    }
+                    open.pieces.push(Piece {
+                        element: token.range.start..tokens[end].range.end,
+                        content,
+                        group: open.groups - 1,
+                    });
+                    open.last_properties = Some(properties.clone());
+                    open.interrupted = false;
+                }
+                i = end;
+            }
+        } else if schema.skipped.contains(&name) {
+            if let Some(open) = stack.last_mut() {
+                open.interrupted = true;
+            }
+            if !self_closing {
+                i = find_end(&tokens, i, name);
+            }
+        } else if !schema.neutral.contains(&name) {
+            if let Some(open) = stack.last_mut() {
+                open.interrupted = true;
+            }
+        }
+        i += 1;
+    }
+    result
+}
+
+/// 开始标签对应的结束标签，找不到时返回最后一个标记
+fn find_end(tokens: &[Token], start: usize, name: &str) -> usize {
+    let mut depth = 0;
+    for (j, token) in tokens.iter().enumerate().skip(start) {
+        match &token.kind {
+            TokenKind::Start {
+                name: n,
+                self_closing: false,
+                ..
+            } if n == name => depth += 1,
+            TokenKind::End { name: n } if n == name => {
+                depth -= 1;
+                if depth == 0 {
+                    return j;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"<w:document><w:body><w:p><w:pPr><w:rPr><w:b/></w:rPr></w:pPr><w:r><w:t xml:space="preserve">The </w:t></w:r><w:proofErr w:type="spellStart"/><w:r><w:rPr><w:lang w:val="en-US"/></w:rPr><w:t>red</w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t xml:space="preserve"> car</w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>s &amp; bikes</w:t></w:r><w:r><w:tab/><w:t>end</w:t></w:r></w:p><w:p><w:r><w:t>42</w:t></w:r></w:p></w:body></w:document>"#;
+
+    #[test]
+    fn test_paragraph_groups() {
+        let paragraphs = paragraphs(DOCUMENT, &WORD);
+        assert_eq!(paragraphs.len(), 2);
+        let first = masked(DOCUMENT, &paragraphs[0]);
+        assert_eq!(first.text, "The red⟦1⟧ cars & bikes⟦2⟧end");
+        assert!(!masked(DOCUMENT, &paragraphs[1]).has_prose());
+
+        let restored = first.restore("红色的⟦1⟧汽车和自行车⟦2⟧结束").unwrap();
+        let groups = split_groups(&restored, paragraphs[0].groups);
+        assert_eq!(groups, vec!["红色的", "汽车和自行车", "结束"]);
+        let output = markup::apply_edits(DOCUMENT, paragraphs[0].edits(DOCUMENT, &WORD, &groups));
+        assert!(output.contains(r#"<w:t xml:space="preserve">红色的</w:t></w:r><w:proofErr w:type="spellStart"/><w:r><w:rPr><w:lang w:val="en-US"/></w:rPr><w:t></w:t>"#));
+        assert!(output.contains("<w:t xml:space=\"preserve\">汽车和自行车</w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t></w:t>"));
+        assert!(output.contains("<w:tab/><w:t>结束</w:t>"));
+
+        let swapped = first.restore("⟦2⟧结束 ⟦1⟧汽车").unwrap();
+        assert_eq!(
+            split_groups(&swapped, 3),
+            vec!["结束 汽车".to_string(), String::new(), String::new()]
+        );
+    }
+
+    #[test]
+    fn test_package_round_trip() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("word/document.xml", options).unwrap();
+        writer.write_all(DOCUMENT.as_bytes()).unwrap();
+        writer.start_file("word/styles.xml", options).unwrap();
+        writer.write_all(b"<w:styles/>").unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let (format, entries, parts) = open(&bytes).unwrap();
+        assert_eq!(format, DocumentFormat::Docx);
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].paragraphs.len(), 1);
+        let rebuilt = read_package(&write_package(&entries).unwrap()).unwrap();
+        assert_eq!(rebuilt[1].name, "word/styles.xml");
+        assert_eq!(rebuilt[0].data, DOCUMENT.as_bytes());
+        assert!(source_text(&bytes)
+            .unwrap()
+            .contains("The red cars & bikes"));
+    }
+
+    #[test]
+    fn test_package_size_limits() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        writer.start_file("a.xml", options).unwrap();
+        writer.write_all(&[b'a'; 4096]).unwrap();
+        writer.start_file("b.xml", options).unwrap();
+        writer.write_all(&[b'b'; 4096]).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        assert!(bytes.len() < 1024);
+
+        assert_eq!(read_package_limited(&bytes, 4096, 8192).unwrap().len(), 2);
+        assert!(matches!(
+            read_package_limited(&bytes, 4095, 8192),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            read_package_limited(&bytes, 4096, 8191),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+// 占位符保护模块
+//
+// 把不应翻译的片段（行内代码、链接地址、标签等）替换为 ⟦n⟧ 形式的占位符，
+// 翻译后再原样还原。占位符缺失或重复时视为还原失败，由调用方决定如何回退。
+
+use std::ops::Range;
+
+/// 已替换占位符的文本
+#[derive(Debug, Clone)]
+pub struct Masked {
+    /// 发送给模型的文本
+    pub text: String,
+    /// 按编号顺序保存的原始片段（⟦1⟧ 对应第 0 项）
+    pub originals: Vec<String>,
+    /// 未替换的原文，还原失败时使用
+    pub source: String,
+    /// 还原前对译文的转义（如 HTML 中的 `<`、`&`），占位符本身不受影响
+    pub escape: Option<fn(&str) -> String>,
+}
+
+/// 文本含占位符时附加给模型的说明
+pub const INSTRUCTIONS: &str =
+    "⟦n⟧ 形式的占位符代表不可翻译的内容，必须原样保留在译文中合适的位置。";
+
+/// 生成第 `index` 个（从 0 开始）占位符
+pub fn token(index: usize) -> String {
+    format!("⟦{}⟧", index + 1)
+}
+
+impl Masked {
+    /// 将 `text[range]` 中的受保护区间替换为占位符
+    ///
+    /// `protected` 中的区间使用 `text` 的绝对偏移，可以无序或相互重叠。
+    pub fn new(text: &str, range: Range<usize>, protected: &[Range<usize>]) -> Self {
+        let mut spans: Vec<Range<usize>> = protected
+            .iter()
+            .map(|r| r.start.max(range.start)..r.end.min(range.end))
+            .filter(|r| r.start < r.end)
+            .collect();
+        spans.sort_by_key(|r| r.start);
+
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for span in spans {
+            match merged.last_mut() {
+                Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+                _ => merged.push(span),
+            }
+        }
+
+        let mut masked = String::new();
+        let mut originals = Vec::new();
+        let mut cursor = range.start;
+        for span in merged {
+            masked.push_str(&text[cursor..span.start]);
+            masked.push_str(&token(originals.len()));
+            originals.push(text[span.clone()].to_string());
+            cursor = span.end;
+        }
+        masked.push_str(&text[cursor..range.end]);
+
+        Self {
+            text: masked,
+            originals,
+            source: text[range].to_string(),
+            escape: None,
+        }
+    }
+
+    /// 设置还原前对译文的转义
+    pub fn with_escape(mut self, escape: fn(&str) -> String) -> Self {
+        self.escape = Some(escape);
+        self
+    }
+
+    /// 是否含有需要翻译的文字（占位符之外至少有一个字母）
+    pub fn has_prose(&self) -> bool {
+        let mut rest = self.text.clone();
+        for i in 0..self.originals.len() {
+            rest = rest.replacen(&token(i), "", 1);
+        }
+        rest.chars().any(char::is_alphabetic)
+    }
+
+    /// 把译文中的占位符还原为原始片段；任一占位符缺失或重复时返回 None
+    pub fn restore(&self, translated: &str) -> Option<String> {
+        self.problems(translated)
+            .is_empty()
+            .then(|| self.substitute(translated))
+    }
+
+    /// 检查译文中每个占位符是否恰好出现一次，返回问题描述（以原始片段指代占位符）
+    pub fn problems(&self, translated: &str) -> Vec<String> {
+        self.originals
+            .iter()
+            .enumerate()
+            .filter_map(
+                |(i, original)| match translated.matches(&token(i)).count() {
+                    1 => None,
+                    0 => Some(format!("占位符 {} 缺失", original)),
+                    n => Some(format!("占位符 {} 重复出现 {} 次", original, n)),
+                },
+            )
+            .collect()
+    }
+
+    /// 把已还原的译文（如翻译记忆中的审定译文）中的原始片段依次换回占位符，任一片段缺失时返回 None
+    pub fn mask_translation(&self, translated: &str) -> Option<String> {
+        let mut result = translated.to_string();
+        for (i, original) in self.originals.iter().enumerate() {
+            if !result.contains(original.as_str()) {
+                return None;
+            }
+            result = result.replacen(original.as_str(), &token(i), 1);
+        }
+        Some(result)
+    }
+
+    /// 不做检查地替换译文中出现的所有占位符
+    pub fn substitute(&self, translated: &str) -> String {
+        let mut result = match self.escape {
+            Some(escape) => escape(translated),
+            None => translated.to_string(),
+        };
+        for (i, original) in self.originals.iter().enumerate() {
+            result = result.replace(&token(i), original);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_and_restore() {
+        let text = "Run `cargo build` then open <http://localhost>.";
+        let masked = Masked::new(text, 0..text.len(), &[4..17, 28..46, 10..12]);
+        assert_eq!(masked.text, "Run ⟦1⟧ then open ⟦2⟧.");
+        assert!(masked.has_prose());
+
+        assert_eq!(
+            masked.restore("先运行 ⟦1⟧，然后打开 ⟦2⟧。").unwrap(),
+            "先运行 `cargo build`，然后打开 <http://localhost>。"
+        );
+        assert!(masked.restore("先运行 ⟦1⟧。").is_none());
+        assert!(masked.restore("⟦1⟧ ⟦1⟧ ⟦2⟧").is_none());
+        assert_eq!(
+            masked.problems("⟦1⟧ ⟦1⟧"),
+            vec![
+                "占位符 `cargo build` 重复出现 2 次",
+                "占位符 <http://localhost> 缺失"
+            ]
+        );
+
+        assert_eq!(
+            masked
+                .mask_translation("先运行 `cargo build`，然后打开 <http://localhost>。")
+                .unwrap(),
+            "先运行 ⟦1⟧，然后打开 ⟦2⟧。"
+        );
+        assert!(masked.mask_translation("先运行 `cargo build`。").is_none());
+
+        let code = 4..17;
+        let masked = Masked::new(text, code.clone(), std::slice::from_ref(&code));
+        assert!(!masked.has_prose());
+    }
+}
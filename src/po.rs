@@ -0,0 +1,622 @@
+// Gettext PO/POT 翻译模块
+//
+// 解析 PO/POT 文件，翻译未翻译（可选包括 fuzzy）的条目，复数条目按目标语言的复数规则
+// 生成相应数量的 msgstr[n]。msgctxt 和译者注释作为补充说明提供给模型，
+// 字符串中的 printf/花括号变量替换为占位符。只重写被翻译条目的 msgstr 和标记行，
+// 文件的其余部分（注释、引用、已翻译条目、废弃条目）逐行保留。
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::language::language_code;
+use crate::placeholder::Masked;
+use crate::segments::translate_segments_with_notes;
+use crate::translator::TranslateOptions;
+use crate::variables;
+
+/// 复数规则
+///
+/// 以 CLDR 复数类别为准，gettext 的 Plural-Forms 表达式映射到其中的部分类别
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PluralRule {
+    /// CLDR 复数类别及各类别适用的数量，作为说明提供给模型
+    pub categories: &'static [(&'static str, &'static str)],
+    /// Plural-Forms 头中的 C 表达式
+    pub expression: &'static str,
+    /// 表达式结果 0、1、2…… 依次对应的 CLDR 类别
+    pub forms: &'static [&'static str],
+}
+
+impl PluralRule {
+    pub fn nplurals(&self) -> usize {
+        self.forms.len()
+    }
+
+    /// 第 k 种 gettext 形式适用的数量
+    pub fn form_counts(&self, k: usize) -> &'static str {
+        let category = self.forms[k];
+        self.categories
+            .iter()
+            .find(|(name, _)| *name == category)
+            .map_or("", |(_, counts)| counts)
+    }
+}
+
+/// 目标语言的复数规则，未知语言按英语处理
+pub fn plural_rule(to_lang: &str) -> PluralRule {
+    match language_code(to_lang) {
+        Some("zh-Hans" | "zh-Hant" | "ja" | "ko" | "vi") => PluralRule {
+            categories: &[("other", "任意数量")],
+            expression: "0",
+            forms: &["other"],
+        },
+        Some("fr") => PluralRule {
+            categories: &[
+                ("one", "0、1"),
+                ("many", "1000000 等百万的整数倍"),
+                ("other", "其他数量"),
+            ],
+            expression: "(n > 1)",
+            forms: &["one", "other"],
+        },
+        Some("es" | "it" | "pt") => PluralRule {
+            categories: &[
+                ("one", "1"),
+                ("many", "1000000 等百万的整数倍"),
+                ("other", "其他数量"),
+            ],
+            expression: "(n != 1)",
+            forms: &["one", "other"],
+        },
+        Some("ru") => PluralRule {
+            categories: &[
+                ("one", "1、21、31……"),
+                ("few", "2-4、22-24……"),
+                ("many", "0、5-20、25-30……"),
+                ("other", "小数"),
+            ],
+            expression:
+                "(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2)",
+            forms: &["one", "few", "many"],
+        },
+        Some("ar") => PluralRule {
+            categories: &[
+                ("zero", "0"),
+                ("one", "1"),
+                ("two", "2"),
+                ("few", "3-10、103-110……"),
+                ("many", "11-99、111-199……"),
+                ("other", "100-102、200-202……"),
+            ],
+            expression:
+                "(n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : n%100>=3 && n%100<=10 ? 3 : n%100>=11 ? 4 : 5)",
+            forms: &["zero", "one", "two", "few", "many", "other"],
+        },
+        _ => PluralRule {
+            categories: &[("one", "1"), ("other", "0、2 及以上")],
+            expression: "(n != 1)",
+            forms: &["one", "other"],
+        },
+    }
+}
+
+/// PO 文件中的一个条目，行号为文件中的行索引
+#[derive(Debug, Default)]
+struct Entry {
+    /// 条目占用的行 `[start, end)`
+    start: usize,
+    end: usize,
+    /// 译者注释（`# `）和提取的注释（`#.`）
+    comments: Vec<String>,
+    flags_line: Option<usize>,
+    flags: Vec<String>,
+    /// `#|` 之前版本的 msgid
+    previous_lines: Vec<usize>,
+    /// 第一个关键字（msgctxt/msgid）所在行
+    keyword_line: Option<usize>,
+    /// 第一个 msgstr 所在行，其后到条目结束均为 msgstr
+    msgstr_line: Option<usize>,
+    msgctxt: Option<String>,
+    msgid: Option<String>,
+    msgid_plural: Option<String>,
+    msgstr: Vec<String>,
+    obsolete: bool,
+}
+
+impl Entry {
+    fn is_header(&self) -> bool {
+        self.msgctxt.is_none() && self.msgid.as_deref() == Some("")
+    }
+
+    fn is_fuzzy(&self) -> bool {
+        self.flags.iter().any(|f| f == "fuzzy")
+    }
+
+    fn is_translated(&self) -> bool {
+        !self.msgstr.is_empty() && self.msgstr.iter().all(|s| !s.is_empty())
+    }
+
+    /// 提供给模型的补充说明
+    fn note(&self) -> Vec<String> {
+        let mut note = Vec::new();
+        if let Some(ctxt) = &self.msgctxt {
+            note.push(format!("消息上下文：{}", ctxt));
+        }
+        if !self.comments.is_empty() {
+            note.push(format!("注释：{}", self.comments.join(" ")));
+        }
+        note
+    }
+}
+
+/// 当前正在读取的字段
+#[derive(Clone, Copy)]
+enum Field {
+    Ctxt,
+    Id,
+    Plural,
+    Str(usize),
+}
+
+/// 翻译选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PoOptions {
+    /// 同时翻译标记为 fuzzy 的条目（翻译后移除 fuzzy 标记）
+    pub include_fuzzy: bool,
+    /// 为机器翻译的条目加上 fuzzy 标记，留待人工审校
+    pub mark_fuzzy: bool,
+}
+
+/// 翻译结果
+#[derive(Debug)]
+pub struct PoOutput {
+    pub content: String,
+    /// 已翻译的条目数
+    pub translated: usize,
+    /// 占位符无法还原而保持未翻译的条目数
+    pub failed: usize,
+}
+
+/// 所有待翻译条目的 msgid 拼接成的文本，用于语种检测
+pub fn source_text(text: &str) -> AppResult<String> {
+    Ok(parse(text)?
+        .iter()
+        .filter(|e| !e.obsolete && !e.is_header())
+        .filter_map(|e| e.msgid.as_deref())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// 翻译 PO/POT 文件，返回新的 PO 文件内容
+pub async fn translate(
+    config: &Config,
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+    po_options: PoOptions,
+) -> AppResult<PoOutput> {
+    let lines: Vec<&str> = text.lines().collect();
+    let entries = parse(text)?;
+    let rule = plural_rule(to_lang);
+
+    // 每个待翻译条目展开为若干片段（复数条目每种形式一个）
+    let mut units: Vec<(usize, Masked, Option<String>)> = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let Some(msgid) = &entry.msgid else { continue };
+        if entry.obsolete
+            || entry.is_header()
+            || entry.is_translated() && !(po_options.include_fuzzy && entry.is_fuzzy())
+        {
+            continue;
+        }
+        let note = entry.note();
+        match &entry.msgid_plural {
+            Some(plural) => {
+                for k in 0..rule.nplurals() {
+                    let source = if k == 0 && rule.nplurals() > 1 {
+                        msgid
+                    } else {
+                        plural
+                    };
+                    let mut note = note.clone();
+                    note.push(format!(
+                        "这是复数文案的第 {}/{} 种形式，用于数量为 {} 的情况；原文单数形式为「{}」",
+                        k + 1,
+                        rule.nplurals(),
+                        rule.form_counts(k),
+                        msgid
+                    ));
+                    units.push((index, mask(source), Some(note.join("；"))));
+                }
+            }
+            None => {
+                let note = (!note.is_empty()).then(|| note.join("；"));
+                units.push((index, mask(msgid), note));
+            }
+        }
+    }
+
+    let masked: Vec<Masked> = units.iter().map(|(_, m, _)| m.clone()).collect();
+    let notes: Vec<Option<String>> = units.iter().map(|(_, _, n)| n.clone()).collect();
+    let results =
+        translate_segments_with_notes(config, &masked, &notes, from_lang, to_lang, options).await?;
+
+    // 按条目汇总译文，任一形式失败时整个条目保持未翻译
+    let mut translations: Vec<Option<Vec<String>>> = vec![None; entries.len()];
+    let mut failed = vec![false; entries.len()];
+    for ((index, _, _), result) in units.iter().zip(results) {
+        match result {
+            Some(text) => translations[*index].get_or_insert_with(Vec::new).push(text),
+            None => failed[*index] = true,
+        }
+    }
+    for (translation, failed) in translations.iter_mut().zip(&failed) {
+        if *failed {
+            *translation = None;
+        }
+    }
+
+    let mut output = Vec::with_capacity(lines.len());
+    let mut cursor = 0;
+    for (entry, translation) in entries.iter().zip(&translations) {
+        output.extend(lines[cursor..entry.start].iter().map(|l| l.to_string()));
+        cursor = entry.end;
+        if entry.is_header() && !entry.obsolete {
+            let header = update_header(&entry.msgstr.concat(), to_lang, &rule);
+            render_entry(&mut output, &lines, entry, &[header], entry.flags.clone());
+            continue;
+        }
+        let Some(msgstr) = translation else {
+            output.extend(lines[entry.start..entry.end].iter().map(|l| l.to_string()));
+            continue;
+        };
+        let mut flags: Vec<String> = entry
+            .flags
+            .iter()
+            .filter(|f| *f != "fuzzy")
+            .cloned()
+            .collect();
+        if po_options.mark_fuzzy {
+            flags.insert(0, "fuzzy".to_string());
+        }
+        render_entry(&mut output, &lines, entry, msgstr, flags);
+    }
+    output.extend(lines[cursor..].iter().map(|l| l.to_string()));
+
+    let mut content = output.join("\n");
+    if text.ends_with('\n') {
+        content.push('\n');
+    }
+    Ok(PoOutput {
+        content,
+        translated: translations.iter().filter(|t| t.is_some()).count(),
+        failed: failed.iter().filter(|f| **f).count(),
+    })
+}
+
+fn mask(text: &str) -> Masked {
+    Masked::new(text, 0..text.len(), &variables::find(text))
+}
+
+/// 重写条目的标记行和 msgstr，其余行保持不变
+fn render_entry(
+    output: &mut Vec<String>,
+    lines: &[&str],
+    entry: &Entry,
+    msgstr: &[String],
+    flags: Vec<String>,
+) {
+    let flags_line = (!flags.is_empty()).then(|| format!("#, {}", flags.join(", ")));
+    // 移除 fuzzy 标记时，之前版本的 msgid 也不再需要
+    let drop_previous = entry.is_fuzzy() && !flags.iter().any(|f| f == "fuzzy");
+    let msgstr_line = entry.msgstr_line.unwrap_or(entry.end);
+
+    for (line, text) in lines.iter().enumerate().take(msgstr_line).skip(entry.start) {
+        if Some(line) == entry.keyword_line && entry.flags_line.is_none() {
+            output.extend(flags_line.clone());
+        }
+        if Some(line) == entry.flags_line {
+            output.extend(flags_line.clone());
+        } else if !(drop_previous && entry.previous_lines.contains(&line)) {
+            output.push(text.to_string());
+        }
+    }
+    if entry.msgid_plural.is_some() {
+        for (k, value) in msgstr.iter().enumerate() {
+            output.extend(format_field(&format!("msgstr[{}]", k), value));
+        }
+    } else {
+        output.extend(format_field("msgstr", &msgstr.concat()));
+    }
+}
+
+/// 设置头部的 Language 和 Plural-Forms 字段
+fn update_header(header: &str, to_lang: &str, rule: &PluralRule) -> String {
+    let mut fields: Vec<String> = header.lines().map(str::to_string).collect();
+    let mut set = |key: &str, value: String| {
+        let line = format!("{}: {}", key, value);
+        match fields
+            .iter_mut()
+            .find(|f| f.starts_with(&format!("{}:", key)))
+        {
+            Some(field) => *field = line,
+            None => fields.push(line),
+        }
+    };
+    set("Language", po_language(to_lang));
+    set(
+        "Plural-Forms",
+        format!("nplurals={}; plural={};", rule.nplurals(), rule.expression),
+    );
+    fields.iter().map(|f| format!("{}\n", f)).collect()
+}
+
+/// gettext 使用的语言代码（如 `zh_CN`、`pt`）
+fn po_language(to_lang: &str) -> String {
+    match language_code(to_lang) {
+        Some("zh-Hans") => "zh_CN".to_string(),
+        Some("zh-Hant") => "zh_TW".to_string(),
+        Some(code) => code.to_string(),
+        None => to_lang.to_string(),
+    }
+}
+
+/// 解析 PO 文件
+fn parse(text: &str) -> AppResult<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut entry: Option<Entry> = None;
+    let mut field: Option<Field> = None;
+
+    let invalid = |line: usize, message: &str| {
+        AppError::Validation(format!("PO 文件第 {} 行{}", line + 1, message))
+    };
+
+    for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() {
+            if let Some(mut e) = entry.take() {
+                e.end = i;
+                entries.push(e);
+            }
+            field = None;
+            continue;
+        }
+        let e = entry.get_or_insert_with(|| Entry {
+            start: i,
+            ..Default::default()
+        });
+
+        if line.starts_with("#~") {
+            e.obsolete = true;
+        } else if let Some(flags) = line.strip_prefix("#,") {
+            e.flags_line = Some(i);
+            e.flags = flags
+                .split(',')
+                .map(|f| f.trim().to_string())
+                .filter(|f| !f.is_empty())
+                .collect();
+        } else if line.starts_with("#|") {
+            e.previous_lines.push(i);
+        } else if let Some(comment) = line.strip_prefix("#.") {
+            e.comments.push(comment.trim().to_string());
+        } else if line == "#" || line.starts_with("# ") {
+            let comment = line[1..].trim();
+            if !comment.is_empty() {
+                e.comments.push(comment.to_string());
+            }
+        } else if line.starts_with('#') {
+            // 引用（`#:`）等其他注释
+        } else if line.starts_with('"') {
+            let value = unquote(line).ok_or_else(|| invalid(i, "的字符串格式无效"))?;
+            match field.ok_or_else(|| invalid(i, "的字符串不属于任何字段"))? {
+                Field::Ctxt => e.msgctxt.get_or_insert_with(String::new).push_str(&value),
+                Field::Id => e.msgid.get_or_insert_with(String::new).push_str(&value),
+                Field::Plural => e
+                    .msgid_plural
+                    .get_or_insert_with(String::new)
+                    .push_str(&value),
+                Field::Str(n) => e.msgstr[n].push_str(&value),
+            }
+        } else {
+            let (keyword, rest) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| invalid(i, "无法解析"))?;
+            let value = unquote(rest.trim()).ok_or_else(|| invalid(i, "的字符串格式无效"))?;
+            let next = match keyword {
+                "msgctxt" => Field::Ctxt,
+                "msgid" => Field::Id,
+                "msgid_plural" => Field::Plural,
+                "msgstr" => Field::Str(0),
+                _ => match keyword
+                    .strip_prefix("msgstr[")
+                    .and_then(|k| k.strip_suffix(']'))
+                    .and_then(|k| k.parse::<usize>().ok())
+                {
+                    Some(n) => Field::Str(n),
+                    None => return Err(invalid(i, &format!("的关键字 {} 无效", keyword))),
+                },
+            };
+            match next {
+                Field::Ctxt => e.msgctxt = Some(value),
+                Field::Id => e.msgid = Some(value),
+                Field::Plural => e.msgid_plural = Some(value),
+                Field::Str(n) => {
+                    if e.msgid.is_none() {
+                        return Err(invalid(i, "的 msgstr 之前缺少 msgid"));
+                    }
+                    if e.msgstr.len() <= n {
+                        e.msgstr.resize(n + 1, String::new());
+                    }
+                    e.msgstr[n] = value;
+                    e.msgstr_line.get_or_insert(i);
+                }
+            }
+            if !matches!(next, Field::Str(_)) {
+                e.keyword_line.get_or_insert(i);
+            }
+            field = Some(next);
+        }
+    }
+    if let Some(mut e) = entry {
+        e.end = text.lines().count();
+        entries.push(e);
+    }
+    Ok(entries)
+}
+
+/// 解析带引号的 C 风格字符串
+fn unquote(s: &str) -> Option<String> {
+    let inner = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            if c == '"' {
+                return None;
+            }
+            result.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => result.push('\n'),
+            't' => result.push('\t'),
+            'r' => result.push('\r'),
+            'a' => result.push('\u{7}'),
+            'b' => result.push('\u{8}'),
+            'f' => result.push('\u{c}'),
+            'v' => result.push('\u{b}'),
+            other => result.push(other),
+        }
+    }
+    Some(result)
+}
+
+fn quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+/// 输出一个字段；含内部换行的值按 gettext 惯例拆成多行
+fn format_field(keyword: &str, value: &str) -> Vec<String> {
+    let trimmed = value.strip_suffix('\n').unwrap_or(value);
+    if !trimmed.contains('\n') {
+        return vec![format!("{} {}", keyword, quote(value))];
+    }
+    let mut lines = vec![format!("{} \"\"", keyword)];
+    lines.extend(value.split_inclusive('\n').map(quote));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POT: &str = r#"# SOME DESCRIPTIVE TITLE.
+#, fuzzy
+msgid ""
+msgstr ""
+"Project-Id-Version: demo\n"
+"Language: \n"
+"Content-Type: text/plain; charset=UTF-8\n"
+
+#. Button label
+#: src/app.c:10
+msgctxt "menu"
+msgid "Open %s"
+msgstr ""
+
+#, fuzzy, c-format
+#| msgid "Old"
+msgid "One file"
+msgid_plural "%d files"
+msgstr[0] "Un fichier"
+msgstr[1] ""
+
+msgid "Done"
+msgstr "Fait"
+
+#~ msgid "Gone"
+#~ msgstr ""
+"#;
+
+    #[test]
+    fn test_parse() {
+        let entries = parse(POT).unwrap();
+        assert_eq!(entries.len(), 5);
+        assert!(entries[0].is_header());
+        assert_eq!(
+            entries[0].msgstr[0],
+            "Project-Id-Version: demo\nLanguage: \nContent-Type: text/plain; charset=UTF-8\n"
+        );
+        assert_eq!(entries[1].msgctxt.as_deref(), Some("menu"));
+        assert_eq!(
+            entries[1].note(),
+            vec!["消息上下文：menu", "注释：Button label"]
+        );
+        assert!(!entries[1].is_translated());
+        assert!(entries[2].is_fuzzy());
+        assert_eq!(entries[2].msgstr.len(), 2);
+        assert!(entries[3].is_translated());
+        assert!(entries[4].obsolete);
+        assert!(parse("msgstr \"x\"\n").is_err());
+    }
+
+    #[test]
+    fn test_render_and_header() {
+        let entries = parse(POT).unwrap();
+        let lines: Vec<&str> = POT.lines().collect();
+        let mut output = Vec::new();
+        let entry = &entries[2];
+        render_entry(
+            &mut output,
+            &lines,
+            entry,
+            &[
+                "1 файл".to_string(),
+                "%d файла".to_string(),
+                "%d файлов\nещё".to_string(),
+            ],
+            vec!["c-format".to_string()],
+        );
+        assert_eq!(
+            output,
+            vec![
+                "#, c-format",
+                "msgid \"One file\"",
+                "msgid_plural \"%d files\"",
+                "msgstr[0] \"1 файл\"",
+                "msgstr[1] \"%d файла\"",
+                "msgstr[2] \"\"",
+                "\"%d файлов\\n\"",
+                "\"ещё\"",
+            ]
+        );
+
+        let header = update_header(&entries[0].msgstr[0], "俄语", &plural_rule("俄语"));
+        assert!(header.contains("Language: ru\n"));
+        assert!(header.starts_with("Project-Id-Version: demo\n"));
+        assert!(header.contains("Plural-Forms: nplurals=3; plural=(n%10==1"));
+        assert_eq!(plural_rule("中文(简体)").nplurals(), 1);
+    }
+
+    #[test]
+    fn test_gettext_forms_map_to_cldr_categories() {
+        for lang in ["中文(简体)", "法语", "西班牙语", "俄语", "阿拉伯语", "英语"]
+        {
+            let rule = plural_rule(lang);
+            for (k, form) in rule.forms.iter().enumerate() {
+                assert!(rule.categories.iter().any(|(name, _)| name == form));
+                assert!(!rule.form_counts(k).is_empty());
+            }
+        }
+        let fr = plural_rule("法语");
+        assert_eq!(fr.nplurals(), 2);
+        assert_eq!(fr.form_counts(0), "0、1");
+        assert_eq!(fr.categories.len(), 3);
+    }
+}
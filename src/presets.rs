@@ -0,0 +1,235 @@
+// 提示词预设模块
+
+use crate::admin::save_candidate;
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use actix_web::{delete, get, put, web, HttpRequest, HttpResponse};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// 客户端密钥请求头
+pub const CLIENT_KEY_HEADER: &str = "X-Client-Key";
+
+/// 内置的提示词预设
+pub fn default_presets() -> BTreeMap<String, String> {
+    [
+        (
+            "technical",
+            "你是资深的技术文档译者。请将 {{from}} 文本翻译为 {{to}}。术语准确统一，保留代码、命令、API 名称和专有名词原文，句式简洁客观。只返回译文。",
+        ),
+        (
+            "marketing",
+            "你是经验丰富的营销文案译者。请将 {{from}} 文案翻译为地道、有感染力的 {{to}}，可以为了目标市场的表达习惯适度意译，但不得改变卖点和事实。只返回译文。",
+        ),
+        (
+            "legal",
+            "你是严谨的法律文本译者。请将 {{from}} 文本逐句忠实地翻译为 {{to}}，保留条款编号和定义术语，不增删、不意译，措辞正式规范。只返回译文。",
+        ),
+        (
+            "casual",
+            "请把下面的 {{from}} 内容翻译成自然、口语化的 {{to}}，像朋友之间聊天一样，保留原文的语气和情绪。只返回译文。",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, prompt)| (name.to_string(), prompt.to_string()))
+    .collect()
+}
+
+/// 校验预设名称：1-50 个小写字母、数字、下划线或连字符
+pub fn validate_preset_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 50
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "预设名称 \"{}\" 无效，只能包含 1-50 个小写字母、数字、下划线或连字符",
+            name
+        ))
+    }
+}
+
+/// 选择本次请求使用的提示词模板
+///
+/// 优先级：请求指定的预设 > 客户端密钥映射的预设 > 默认系统提示词。
+/// 返回 `(预设名称, 模板)`，使用默认系统提示词时名称为 None。
+pub fn resolve_prompt<'a>(
+    config: &'a Config,
+    preset: Option<&str>,
+    client_key: Option<&str>,
+) -> AppResult<(Option<&'a str>, &'a str)> {
+    if let Some(name) = preset.filter(|p| !p.is_empty()) {
+        return config
+            .prompt_presets
+            .get_key_value(name)
+            .map(|(name, template)| (Some(name.as_str()), template.as_str()))
+            .ok_or_else(|| {
+                AppError::Validation(format!(
+                    "未知的提示词预设 \"{}\"，可选值: {}",
+                    name,
+                    config
+                        .prompt_presets
+                        .keys()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            });
+    }
+
+    if let Some(name) = client_key.and_then(|key| config.client_presets.get(key)) {
+        if let Some(template) = config.prompt_presets.get(name) {
+            return Ok((Some(name.as_str()), template.as_str()));
+        }
+    }
+
+    Ok((None, config.system_prompt()))
+}
+
+/// 从请求头中读取客户端密钥
+pub fn client_key(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(CLIENT_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+#[derive(Debug, Serialize)]
+pub struct PresetsResponse {
+    pub presets: BTreeMap<String, String>,
+    pub client_presets: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PresetUpdate {
+    pub prompt: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientPresetUpdate {
+    pub preset: String,
+}
+
+/// 列出所有预设及客户端映射
+#[get("/admin/presets")]
+pub async fn list_presets(config: web::Data<Arc<RwLock<Config>>>) -> HttpResponse {
+    let config = config.read();
+    HttpResponse::Ok().json(PresetsResponse {
+        presets: config.prompt_presets.clone(),
+        client_presets: config.client_presets.clone(),
+    })
+}
+
+/// 新增或修改预设
+#[put("/admin/presets/{name}")]
+pub async fn upsert_preset(
+    req: HttpRequest,
+    path: web::Path<String>,
+    update: web::Json<PresetUpdate>,
+    config: web::Data<Arc<RwLock<Config>>>,
+) -> HttpResponse {
+    let name = path.into_inner();
+    let mut config = config.write();
+    let mut candidate = config.clone();
+    candidate
+        .prompt_presets
+        .insert(name.clone(), update.into_inner().prompt);
+    save_candidate(&req, &mut config, candidate, &format!("预设 {}", name))
+}
+
+/// 删除预设（仍被客户端映射引用时会被校验拒绝）
+#[delete("/admin/presets/{name}")]
+pub async fn delete_preset(
+    req: HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Arc<RwLock<Config>>>,
+) -> HttpResponse {
+    let name = path.into_inner();
+    let mut config = config.write();
+    let mut candidate = config.clone();
+    if candidate.prompt_presets.remove(&name).is_none() {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "message": format!("预设 {} 不存在", name)
+        }));
+    }
+    save_candidate(&req, &mut config, candidate, &format!("删除预设 {}", name))
+}
+
+/// 设置客户端密钥使用的默认预设
+#[put("/admin/presets/clients/{client}")]
+pub async fn upsert_client_preset(
+    req: HttpRequest,
+    path: web::Path<String>,
+    update: web::Json<ClientPresetUpdate>,
+    config: web::Data<Arc<RwLock<Config>>>,
+) -> HttpResponse {
+    let client = path.into_inner();
+    let mut config = config.write();
+    let mut candidate = config.clone();
+    candidate
+        .client_presets
+        .insert(client.clone(), update.into_inner().preset);
+    save_candidate(
+        &req,
+        &mut config,
+        candidate,
+        &format!("客户端 {} 的预设", client),
+    )
+}
+
+/// 删除客户端预设映射
+#[delete("/admin/presets/clients/{client}")]
+pub async fn delete_client_preset(
+    req: HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Arc<RwLock<Config>>>,
+) -> HttpResponse {
+    let client = path.into_inner();
+    let mut config = config.write();
+    let mut candidate = config.clone();
+    if candidate.client_presets.remove(&client).is_none() {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "message": format!("客户端 {} 没有预设映射", client)
+        }));
+    }
+    save_candidate(
+        &req,
+        &mut config,
+        candidate,
+        &format!("删除客户端 {} 的预设", client),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_prompt_priority() {
+        let mut config = Config::default();
+        config
+            .client_presets
+            .insert("bob".to_string(), "legal".to_string());
+
+        let (name, _) = resolve_prompt(&config, Some("casual"), Some("bob")).unwrap();
+        assert_eq!(name, Some("casual"));
+
+        let (name, template) = resolve_prompt(&config, None, Some("bob")).unwrap();
+        assert_eq!(name, Some("legal"));
+        assert_eq!(template, config.prompt_presets["legal"]);
+
+        let (name, template) = resolve_prompt(&config, None, Some("alice")).unwrap();
+        assert_eq!(name, None);
+        assert_eq!(template, config.system_prompt());
+
+        assert!(resolve_prompt(&config, Some("unknown"), None).is_err());
+    }
+}
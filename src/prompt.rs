@@ -0,0 +1,230 @@
+// 提示词模板模块
+//
+// 提示词使用 Jinja 模板语法（minijinja），兼容原有的 `{{from}}`/`{{to}}`/`{{text}}` 写法，
+// 并支持条件、循环和过滤器，例如：
+//
+//   {% if to_code == "ja" %}敬语统一使用です・ます体。{% endif %}
+//   {% for term in glossary %}{{ term.source }} => {{ term.target }}
+//   {% endfor %}
+//
+// 变量值在渲染时作为数据插入，不会被再次解析，因此待翻译文本中的花括号无需转义。
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::glossary::{self, GlossaryEntry};
+use crate::language::{self, select_target_language};
+use crate::presets;
+use actix_web::{post, web, HttpResponse};
+use minijinja::{Environment, UndefinedBehavior};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// 提示词模板中可用的变量
+pub const TEMPLATE_VARIABLES: &[&str] = &[
+    "from",
+    "to",
+    "from_code",
+    "to_code",
+    "confidence",
+    "client",
+    "preset",
+    "glossary",
+    "context",
+    "text",
+];
+
+/// 模板中允许使用的内置函数
+const TEMPLATE_FUNCTIONS: &[&str] = &["range", "dict", "namespace"];
+
+/// 渲染提示词时可用的变量
+#[derive(Debug, Serialize)]
+pub struct PromptContext<'a> {
+    /// 源语种名称
+    pub from: &'a str,
+    /// 目标语种名称
+    pub to: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_code: Option<&'static str>,
+    /// 源语种检测置信度（请求指定源语种时为 1）
+    pub confidence: f32,
+    /// 客户端密钥
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client: Option<&'a str>,
+    /// 使用的预设名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preset: Option<&'a str>,
+    /// 文本中命中的术语
+    pub glossary: Vec<&'a GlossaryEntry>,
+    /// 调用方提供的上下文
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<&'a str>,
+    /// 待翻译文本
+    pub text: &'a str,
+}
+
+impl<'a> PromptContext<'a> {
+    pub fn new(config: &'a Config, text: &'a str, from_lang: &'a str, to_lang: &'a str) -> Self {
+        Self {
+            from: from_lang,
+            to: to_lang,
+            from_code: language::language_code(from_lang),
+            to_code: language::language_code(to_lang),
+            confidence: 1.0,
+            client: None,
+            preset: None,
+            glossary: glossary::find_hits(&config.glossary, text, to_lang),
+            context: None,
+            text,
+        }
+    }
+}
+
+fn environment() -> Environment<'static> {
+    let mut env = Environment::new();
+    env.set_undefined_behavior(UndefinedBehavior::Lenient);
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env
+}
+
+/// 渲染提示词模板
+pub fn render(template: &str, ctx: &PromptContext) -> AppResult<String> {
+    environment()
+        .render_str(template, ctx)
+        .map_err(|e| AppError::Translation(format!("渲染提示词模板失败: {}", e)))
+}
+
+/// 校验提示词模板：语法正确，且只引用 [`TEMPLATE_VARIABLES`] 中的变量
+pub fn validate_template(template: &str) -> Result<(), String> {
+    let env = environment();
+    let compiled = env
+        .template_from_str(template)
+        .map_err(|e| format!("模板语法错误: {}", e))?;
+
+    let mut unknown: Vec<String> = compiled
+        .undeclared_variables(false)
+        .into_iter()
+        .filter(|name| {
+            !TEMPLATE_VARIABLES.contains(&name.as_str())
+                && !TEMPLATE_FUNCTIONS.contains(&name.as_str())
+        })
+        .collect();
+
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    unknown.sort();
+    Err(format!(
+        "未知的模板变量 {}，可用变量: {}",
+        unknown
+            .iter()
+            .map(|v| format!("{{{{{}}}}}", v))
+            .collect::<Vec<_>>()
+            .join(", "),
+        TEMPLATE_VARIABLES
+            .iter()
+            .map(|v| format!("{{{{{}}}}}", v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewRequest {
+    /// 待预览的模板，缺省时按预设、客户端映射、系统提示词的顺序选择
+    pub template: Option<String>,
+    pub text: String,
+    pub destination: Vec<String>,
+    pub source: Option<String>,
+    pub preset: Option<String>,
+    pub client_key: Option<String>,
+    pub context: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewResponse<'a> {
+    pub variables: PromptContext<'a>,
+    pub rendered: String,
+}
+
+/// 使用示例请求渲染提示词，便于调试模板
+#[post("/admin/prompt/preview")]
+pub async fn preview_prompt(
+    req: web::Json<PreviewRequest>,
+    config: web::Data<Arc<RwLock<Config>>>,
+) -> AppResult<HttpResponse> {
+    let config = config.read().clone();
+
+    let (from_lang, to_lang) =
+        select_target_language(&req.text, &req.destination, req.source.as_deref());
+    let (preset, template) = match req.template.as_deref() {
+        Some(template) => {
+            validate_template(template).map_err(AppError::Validation)?;
+            (None, template)
+        }
+        None => presets::resolve_prompt(&config, req.preset.as_deref(), req.client_key.as_deref())?,
+    };
+
+    let mut ctx = PromptContext::new(&config, &req.text, &from_lang, &to_lang);
+    ctx.confidence = match req.source {
+        Some(_) => 1.0,
+        None => language::detection_confidence(&req.text, &from_lang),
+    };
+    ctx.client = req.client_key.as_deref();
+    ctx.preset = preset;
+    ctx.context = req.context.as_deref();
+
+    let rendered = render(template, &ctx)?;
+    Ok(HttpResponse::Ok().json(PreviewResponse {
+        variables: ctx,
+        rendered,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_conditionals_and_glossary() {
+        let mut config = Config::default();
+        config.glossary.push(GlossaryEntry {
+            source: "crate".to_string(),
+            target: "クレート".to_string(),
+            to: None,
+        });
+        let template = "翻译为{{to}}。\n{% if to_code == \"ja\" %}使用です・ます体。\n{% endif %}{% for term in glossary %}{{ term.source }}={{ term.target }}\n{% endfor %}";
+
+        let ctx = PromptContext::new(&config, "Publish the crate", "英语", "日语");
+        assert_eq!(
+            render(template, &ctx).unwrap(),
+            "翻译为日语。\n使用です・ます体。\ncrate=クレート\n"
+        );
+
+        let ctx = PromptContext::new(&config, "Hello", "英语", "法语");
+        assert_eq!(render(template, &ctx).unwrap(), "翻译为法语。\n");
+    }
+
+    #[test]
+    fn test_render_does_not_evaluate_text() {
+        let config = Config::default();
+        let ctx = PromptContext::new(&config, "{{ to }} {% if %}", "英语", "日语");
+        assert_eq!(
+            render("原文: {{text}}", &ctx).unwrap(),
+            "原文: {{ to }} {% if %}"
+        );
+    }
+
+    #[test]
+    fn test_validate_template() {
+        assert!(validate_template("{{from}} -> {{to}}: {{text}}").is_ok());
+        assert!(validate_template("{% for i in range(3) %}{{ i }}{% endfor %}").is_ok());
+        assert!(validate_template("{% if to %}").is_err());
+        assert!(validate_template("{{ txt }}")
+            .unwrap_err()
+            .contains("{{txt}}"));
+    }
+}
@@ -0,0 +1,455 @@
+// 国际化资源文件翻译模块
+//
+// 支持嵌套 JSON（i18next、vue-i18n）、Rails 风格 YAML 和 Mozilla Fluent（.ftl）。
+// 各格式的扫描器只定位字符串值在原文中的区间，翻译后按区间替换，
+// 因此键、嵌套、顺序、注释和缩进都保持不变；值中的插值变量替换为占位符。
+// 增量模式下提供上一版原文和译文，只有新增或原文有变化的键会发送给模型。
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::fluent;
+use crate::language::language_code;
+use crate::markup;
+use crate::placeholder::Masked;
+use crate::segments::translate_segments_with_notes;
+use crate::translator::TranslateOptions;
+use crate::variables;
+use crate::yaml;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// 资源文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceFormat {
+    Json,
+    Yaml,
+    #[serde(alias = "fluent")]
+    Ftl,
+}
+
+impl ResourceFormat {
+    /// 根据内容猜测格式：以 `{` 开头为 JSON，含 `key = value` 形式的消息为 Fluent，否则为 YAML
+    pub fn detect(text: &str) -> Self {
+        lazy_static::lazy_static! {
+            static ref FLUENT_MESSAGE: regex::Regex =
+                regex::Regex::new(r"(?m)^-?[A-Za-z][\w-]*[ \t]*=").unwrap();
+        }
+        if text
+            .trim_start_matches('\u{feff}')
+            .trim_start()
+            .starts_with('{')
+        {
+            ResourceFormat::Json
+        } else if FLUENT_MESSAGE.is_match(text) {
+            ResourceFormat::Ftl
+        } else {
+            ResourceFormat::Yaml
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ResourceFormat::Json => "application/json; charset=utf-8",
+            ResourceFormat::Yaml => "application/yaml; charset=utf-8",
+            ResourceFormat::Ftl => "text/plain; charset=utf-8",
+        }
+    }
+
+    fn parse(self, text: &str) -> AppResult<Parsed> {
+        match self {
+            ResourceFormat::Json => parse_json(text),
+            ResourceFormat::Yaml => yaml::parse(text),
+            ResourceFormat::Ftl => fluent::parse(text),
+        }
+    }
+}
+
+/// 字符串值在原文中的写法，决定译文如何写回
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    /// JSON 字符串（区间含引号）
+    Json,
+    /// YAML 普通标量
+    YamlPlain,
+    /// YAML 单引号标量（区间含引号）
+    YamlSingle,
+    /// YAML 双引号标量（区间含引号）
+    YamlDouble,
+    /// YAML 块标量的内容行，`indent` 为内容缩进，`folded` 表示 `>` 折叠风格
+    YamlBlock { indent: usize, folded: bool },
+    /// Fluent 消息或属性的值
+    Fluent,
+}
+
+/// 资源文件中的一个字符串值
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    /// 键路径，数组元素为下标
+    pub path: Vec<String>,
+    /// 在原文中需要替换的区间
+    pub range: Range<usize>,
+    /// 解码后的值
+    pub value: String,
+    /// 值中需要保护的区间（相对于 `value`）
+    pub protected: Vec<Range<usize>>,
+    pub style: Style,
+}
+
+/// 扫描结果
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub units: Vec<Unit>,
+    /// 顶层键及其在原文中的区间，用于识别 `en:` 这样的语言根节点
+    pub root_keys: Vec<(String, Range<usize>)>,
+}
+
+/// 增量翻译时提供的上一版文件
+#[derive(Debug, Clone, Copy)]
+pub struct Previous<'a> {
+    pub source: &'a str,
+    pub target: &'a str,
+}
+
+/// 翻译结果
+#[derive(Debug)]
+pub struct ResourceOutput {
+    pub content: String,
+    /// 发送给模型翻译的值数量
+    pub translated: usize,
+    /// 沿用上一版译文的值数量
+    pub reused: usize,
+    /// 占位符无法还原而保留原文的值数量
+    pub failed: usize,
+}
+
+/// 所有字符串值拼接成的文本，用于语种检测
+pub fn source_text(text: &str, format: ResourceFormat) -> AppResult<String> {
+    Ok(format
+        .parse(text)?
+        .units
+        .into_iter()
+        .map(|u| u.value)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// 翻译资源文件
+pub async fn translate(
+    config: &Config,
+    text: &str,
+    format: ResourceFormat,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+    previous: Option<Previous<'_>>,
+) -> AppResult<ResourceOutput> {
+    let parsed = format.parse(text)?;
+    let root = locale_root(&parsed);
+    let units = strip_root(parsed.units, root.is_some());
+
+    // 上一版原文未变且已有译文的键直接沿用
+    let mut reused: HashMap<usize, String> = HashMap::new();
+    if let Some(previous) = previous {
+        let source = values(format, previous.source, "上一版原文")?;
+        let target = values(format, previous.target, "上一版译文")?;
+        for (i, unit) in units.iter().enumerate() {
+            if source.get(&unit.path) == Some(&unit.value) {
+                if let Some(translation) = target.get(&unit.path) {
+                    reused.insert(i, translation.clone());
+                }
+            }
+        }
+    }
+
+    let pending: Vec<usize> = (0..units.len())
+        .filter(|i| !reused.contains_key(i))
+        .collect();
+    let masked: Vec<Masked> = pending
+        .iter()
+        .map(|&i| {
+            let unit = &units[i];
+            let mut protected = unit.protected.clone();
+            protected.extend(variables::find(&unit.value));
+            Masked::new(&unit.value, 0..unit.value.len(), &protected)
+        })
+        .collect();
+    let notes: Vec<Option<String>> = pending
+        .iter()
+        .map(|&i| Some(format!("键：{}", units[i].path.join("."))))
+        .collect();
+    let results =
+        translate_segments_with_notes(config, &masked, &notes, from_lang, to_lang, options).await?;
+
+    let mut translations = reused.clone();
+    let mut failed = 0;
+    for (&i, result) in pending.iter().zip(results) {
+        match result {
+            Some(text) => {
+                translations.insert(i, text);
+            }
+            None => failed += 1,
+        }
+    }
+
+    let mut edits: Vec<(Range<usize>, String)> = units
+        .iter()
+        .enumerate()
+        .filter_map(|(i, unit)| {
+            let translation = translations.get(&i)?;
+            (*translation != unit.value).then(|| (unit.range.clone(), encode(unit, translation)))
+        })
+        .collect();
+    if let Some((name, range)) = root {
+        edits.push((range, target_locale(&name, to_lang)));
+    }
+
+    Ok(ResourceOutput {
+        content: markup::apply_edits(text, edits),
+        translated: pending.len() - failed,
+        reused: reused.len(),
+        failed,
+    })
+}
+
+/// 按键路径索引文件中的值
+fn values(
+    format: ResourceFormat,
+    text: &str,
+    name: &str,
+) -> AppResult<HashMap<Vec<String>, String>> {
+    let parsed = format
+        .parse(text)
+        .map_err(|e| AppError::Validation(format!("{}无效: {}", name, e)))?;
+    let root = locale_root(&parsed).is_some();
+    Ok(strip_root(parsed.units, root)
+        .into_iter()
+        .map(|u| (u.path, u.value))
+        .collect())
+}
+
+/// 唯一的顶层键是语言代码（如 Rails 的 `en:`）时返回该键
+fn locale_root(parsed: &Parsed) -> Option<(String, Range<usize>)> {
+    lazy_static::lazy_static! {
+        static ref LOCALE: regex::Regex =
+            regex::Regex::new(r"^[a-z]{2,3}([-_][A-Za-z0-9]{2,8})*$").unwrap();
+    }
+    match &parsed.root_keys[..] {
+        [(name, range)] if LOCALE.is_match(name) => Some((name.clone(), range.clone())),
+        _ => None,
+    }
+}
+
+fn strip_root(units: Vec<Unit>, root: bool) -> Vec<Unit> {
+    if !root {
+        return units;
+    }
+    units
+        .into_iter()
+        .map(|mut unit| {
+            unit.path.remove(0);
+            unit
+        })
+        .collect()
+}
+
+/// 目标语言的语言代码，沿用原文根节点的分隔符风格
+fn target_locale(original: &str, to_lang: &str) -> String {
+    let code = match language_code(to_lang) {
+        Some("zh-Hans") => "zh-CN",
+        Some("zh-Hant") => "zh-TW",
+        Some(code) => code,
+        None => return original.to_string(),
+    };
+    if original.contains('_') {
+        code.replace('-', "_")
+    } else {
+        code.to_string()
+    }
+}
+
+/// 按原文的写法编码译文
+fn encode(unit: &Unit, text: &str) -> String {
+    match unit.style {
+        Style::Json | Style::YamlDouble => json_string(text),
+        Style::YamlPlain => yaml::encode_plain(text),
+        Style::YamlSingle => yaml::encode_single(text),
+        Style::YamlBlock { indent, folded } => yaml::encode_block(text, indent, folded),
+        Style::Fluent => fluent::encode(text),
+    }
+}
+
+/// JSON 字符串字面量（含引号）
+pub fn json_string(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+/// 扫描 JSON 文件中的字符串值
+fn parse_json(text: &str) -> AppResult<Parsed> {
+    serde_json::from_str::<serde_json::Value>(text)
+        .map_err(|e| AppError::Validation(format!("JSON 解析失败: {}", e)))?;
+    let mut scanner = JsonScanner {
+        text,
+        pos: 0,
+        parsed: Parsed::default(),
+    };
+    scanner.skip_whitespace();
+    scanner.value(&mut Vec::new())?;
+    Ok(scanner.parsed)
+}
+
+/// 在已校验过的 JSON 上定位字符串值
+struct JsonScanner<'a> {
+    text: &'a str,
+    pos: usize,
+    parsed: Parsed,
+}
+
+impl JsonScanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> AppResult<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(AppError::Validation(format!(
+                "JSON 第 {} 字节处应为 `{}`",
+                self.pos, byte as char
+            )));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// 读取字符串，返回区间（含引号）和解码后的值
+    fn string(&mut self) -> AppResult<(Range<usize>, String)> {
+        self.skip_whitespace();
+        let start = self.pos;
+        self.expect(b'"')?;
+        let bytes = self.text.as_bytes();
+        while self.pos < bytes.len() {
+            match bytes[self.pos] {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    let value = serde_json::from_str(&self.text[start..self.pos])?;
+                    return Ok((start..self.pos, value));
+                }
+                _ => self.pos += 1,
+            }
+        }
+        Err(AppError::Validation("JSON 字符串未结束".to_string()))
+    }
+
+    fn value(&mut self, path: &mut Vec<String>) -> AppResult<()> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                loop {
+                    let (range, key) = self.string()?;
+                    if path.is_empty() {
+                        self.parsed
+                            .root_keys
+                            .push((key.clone(), range.start + 1..range.end - 1));
+                    }
+                    self.expect(b':')?;
+                    path.push(key);
+                    self.value(path)?;
+                    path.pop();
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        _ => return self.expect(b'}'),
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                for index in 0.. {
+                    path.push(index.to_string());
+                    self.value(path)?;
+                    path.pop();
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        _ => return self.expect(b']'),
+                    }
+                }
+                Ok(())
+            }
+            Some(b'"') => {
+                let (range, value) = self.string()?;
+                self.parsed.units.push(Unit {
+                    path: path.clone(),
+                    range,
+                    value,
+                    protected: Vec::new(),
+                    style: Style::Json,
+                });
+                Ok(())
+            }
+            _ => {
+                // 数字、布尔值和 null
+                while self
+                    .peek()
+                    .is_some_and(|b| !matches!(b, b',' | b'}' | b']') && !b.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json() {
+        let text = "{\n  \"en\": {\n    \"title\": \"Hello {{name}}\",\n    \"count\": 3,\n    \"days\": [\"Mon\", \"Tue \\\"x\\\"\"],\n    \"empty\": {}\n  }\n}\n";
+        let parsed = parse_json(text).unwrap();
+        let units: Vec<(String, &str, &str)> = parsed
+            .units
+            .iter()
+            .map(|u| (u.path.join("."), u.value.as_str(), &text[u.range.clone()]))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (
+                    "en.title".to_string(),
+                    "Hello {{name}}",
+                    "\"Hello {{name}}\""
+                ),
+                ("en.days.0".to_string(), "Mon", "\"Mon\""),
+                ("en.days.1".to_string(), "Tue \"x\"", "\"Tue \\\"x\\\"\""),
+            ]
+        );
+        let root = locale_root(&parsed).unwrap();
+        assert_eq!(&text[root.1], "en");
+        assert_eq!(target_locale("en", "中文(简体)"), "zh-CN");
+        assert_eq!(target_locale("en_US", "中文(繁体)"), "zh_TW");
+        assert!(parse_json("{\"a\": }").is_err());
+    }
+}
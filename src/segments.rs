@@ -0,0 +1,363 @@
+// 片段批量翻译模块
+//
+// 结构化格式（Markdown、HTML 等）会被拆成许多短小的待译片段。这里把相邻片段
+// 用 `<<<n>>>` 标记拼成一次请求，按标记拆回译文并还原占位符；
+// 标记数量对不上或占位符丢失时，对该片段单独重试，仍失败则由调用方决定（默认保留原文）。
+// 除第一批外，每批都附带上一批原文的结尾作为上下文。翻译记忆中有审定译文的片段不再请求。
+// 在后台任务中执行时，每个片段计为一个进度单元，每批译完后更新任务进度。
+
+use crate::chunker;
+use crate::config::Config;
+use crate::error::AppResult;
+use crate::jobs::Progress;
+use crate::placeholder::{self, Masked};
+use crate::translator::{estimate_text_tokens, memory_exact, translate_with_llm, TranslateOptions};
+use futures::{StreamExt, TryStreamExt};
+use std::collections::HashMap;
+use std::future::Future;
+
+/// 批量翻译时附加的说明
+const BATCH_INSTRUCTIONS: &str = "输入由若干以 <<<n>>> 标记行开头的片段组成。逐个翻译每个片段，\
+原样保留每一个 <<<n>>> 标记行，不要合并、拆分、增加或删除片段。\
+⟦n⟧ 形式的占位符代表不可翻译的内容，必须原样保留在译文中合适的位置。";
+
+/// 片段补充说明的标题
+const NOTES_HEADING: &str = "\n以下是片段的补充说明，仅供理解，不要翻译或输出：\n";
+
+tokio::task_local! {
+    /// 当前后台任务的进度，翻译片段时累加片段总数和已完成数
+    static PROGRESS: Progress;
+}
+
+/// 在报告片段进度的范围内执行 `future`，其中翻译的每个片段计为一个进度单元
+pub async fn with_progress<F: Future>(progress: Progress, future: F) -> F::Output {
+    PROGRESS.scope(progress, future).await
+}
+
+lazy_static::lazy_static! {
+    static ref MARKER: regex::Regex = regex::Regex::new(r"(?m)^[ \t]*<<<(\d+)>>>[ \t]*$").unwrap();
+}
+
+/// 翻译一组片段，返回与输入一一对应的译文（不含需要翻译的文字或翻译失败的片段原样返回）
+pub async fn translate_segments(
+    config: &Config,
+    segments: &[Masked],
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<Vec<String>> {
+    let translated =
+        translate_segments_with_notes(config, segments, &[], from_lang, to_lang, options).await?;
+    Ok(translated
+        .into_iter()
+        .zip(segments)
+        .map(|(text, segment)| text.unwrap_or_else(|| segment.source.clone()))
+        .collect())
+}
+
+/// 翻译一组片段，`notes` 为各片段的补充说明（如消息上下文、译者注释），放在系统消息中供模型参考
+///
+/// 不含需要翻译的文字的片段原样返回，占位符无法还原的片段返回 None。
+pub async fn translate_segments_with_notes(
+    config: &Config,
+    segments: &[Masked],
+    notes: &[Option<String>],
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<Vec<Option<String>>> {
+    let Plan {
+        mut results,
+        batches,
+        ..
+    } = plan(config, segments, notes, from_lang, to_lang, options);
+    // 无需请求的片段直接计为完成
+    let pending: usize = batches.iter().map(|batch| batch.indices.len()).sum();
+    let _ = PROGRESS.try_with(|progress| progress.extend(segments.len(), segments.len() - pending));
+    let tasks = batches.iter().map(|batch| async move {
+        let translated = batch.translate(config, from_lang, to_lang).await;
+        if translated.is_ok() {
+            let _ = PROGRESS.try_with(|progress| progress.advance_by(batch.indices.len()));
+        }
+        translated
+    });
+    let translated: Vec<Vec<(usize, Option<String>)>> = futures::stream::iter(tasks)
+        .buffered(config.chunking.concurrency.max(1))
+        .try_collect()
+        .await?;
+
+    for (i, text) in translated.into_iter().flatten() {
+        results[i] = text;
+    }
+    Ok(results)
+}
+
+/// 不调用大模型，返回翻译这组片段时首轮发送的请求（文本及选项）和复用翻译记忆的片段数
+pub fn plan_requests(
+    config: &Config,
+    segments: &[Masked],
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> (Vec<(String, TranslateOptions)>, usize) {
+    let plan = plan(config, segments, &[], from_lang, to_lang, options);
+    let requests = plan.batches.iter().map(Batch::request).collect();
+    (requests, plan.reused)
+}
+
+/// 片段的翻译计划
+struct Plan<'a> {
+    /// 预填的结果：无需翻译的片段为原文，翻译记忆命中的为审定译文
+    results: Vec<Option<String>>,
+    /// 复用翻译记忆的片段数
+    reused: usize,
+    batches: Vec<Batch<'a>>,
+}
+
+/// 复用翻译记忆中的审定译文，其余含文字的片段按 token 数分批
+fn plan<'a>(
+    config: &Config,
+    segments: &'a [Masked],
+    notes: &'a [Option<String>],
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> Plan<'a> {
+    let mut results: Vec<Option<String>> =
+        segments.iter().map(|s| Some(s.source.clone())).collect();
+    let mut pending = Vec::new();
+    for (i, segment) in segments.iter().enumerate() {
+        if !segment.has_prose() {
+            continue;
+        }
+        // 翻译记忆中有审定译文的片段直接复用
+        match memory_exact(config, &segment.source, from_lang, to_lang) {
+            Some(target) => results[i] = Some(target),
+            None => pending.push(i),
+        }
+    }
+    let reused = segments.iter().filter(|s| s.has_prose()).count() - pending.len();
+
+    let groups = batch(&pending, segments, config.chunking.max_tokens);
+    let batches = groups
+        .iter()
+        .enumerate()
+        .map(|(k, indices)| {
+            // 上一批片段的结尾作为上下文，保持相邻批次间的用语和指代一致
+            let mut options = options.clone();
+            options.previous_text = k.checked_sub(1).map(|prev| {
+                let source = groups[prev]
+                    .iter()
+                    .map(|&i| segments[i].source.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                chunker::context_tail(&source).to_string()
+            });
+            Batch {
+                segments,
+                notes,
+                indices: indices.clone(),
+                options,
+            }
+        })
+        .collect();
+    Plan {
+        results,
+        reused,
+        batches,
+    }
+}
+
+/// 按估算 token 数把片段分批
+fn batch(pending: &[usize], segments: &[Masked], max_tokens: usize) -> Vec<Vec<usize>> {
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    let mut tokens = 0;
+    for &i in pending {
+        let cost = estimate_text_tokens(&segments[i].text) + 4;
+        match batches.last_mut() {
+            Some(current) if tokens + cost <= max_tokens => current.push(i),
+            _ => {
+                batches.push(vec![i]);
+                tokens = 0;
+            }
+        }
+        tokens += cost;
+    }
+    batches
+}
+
+/// 一次请求中翻译的一批片段
+struct Batch<'a> {
+    segments: &'a [Masked],
+    notes: &'a [Option<String>],
+    indices: Vec<usize>,
+    options: TranslateOptions,
+}
+
+impl Batch<'_> {
+    fn note(&self, i: usize) -> Option<&str> {
+        self.notes.get(i).and_then(|note| note.as_deref())
+    }
+
+    /// 本批首轮请求的文本和选项：多个片段以标记拼接，单个片段单独翻译
+    fn request(&self) -> (String, TranslateOptions) {
+        if let [i] = self.indices[..] {
+            return self.single_request(i);
+        }
+
+        let joined = self
+            .indices
+            .iter()
+            .enumerate()
+            .map(|(n, &i)| format!("<<<{}>>>\n{}", n + 1, self.segments[i].text))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut instructions = BATCH_INSTRUCTIONS.to_string();
+        let notes: Vec<String> = self
+            .indices
+            .iter()
+            .enumerate()
+            .filter_map(|(n, &i)| self.note(i).map(|note| format!("<<<{}>>> {}", n + 1, note)))
+            .collect();
+        if !notes.is_empty() {
+            instructions.push_str(NOTES_HEADING);
+            instructions.push_str(&notes.join("\n"));
+        }
+        let mut options = self.options.clone();
+        options.extra_instructions = Some(instructions);
+        (joined, options)
+    }
+
+    /// 单独翻译一个片段时的文本和选项
+    fn single_request(&self, i: usize) -> (String, TranslateOptions) {
+        let segment = &self.segments[i];
+        let mut instructions = Vec::new();
+        if !segment.originals.is_empty() {
+            instructions.push(placeholder::INSTRUCTIONS.to_string());
+        }
+        if let Some(note) = self.note(i) {
+            instructions.push(format!("{}{}", NOTES_HEADING.trim_start(), note));
+        }
+        let mut options = self.options.clone();
+        options.extra_instructions = (!instructions.is_empty()).then(|| instructions.join("\n"));
+        (segment.text.clone(), options)
+    }
+
+    async fn translate(
+        &self,
+        config: &Config,
+        from_lang: &str,
+        to_lang: &str,
+    ) -> AppResult<Vec<(usize, Option<String>)>> {
+        if let [i] = self.indices[..] {
+            let text = self.translate_one(config, i, from_lang, to_lang).await?;
+            return Ok(vec![(i, text)]);
+        }
+
+        let (joined, options) = self.request();
+        let translated = translate_with_llm(config, &joined, from_lang, to_lang, &options).await?;
+        let mut parts = split_markers(&translated);
+
+        let mut results = Vec::with_capacity(self.indices.len());
+        for (n, &i) in self.indices.iter().enumerate() {
+            let restored = parts
+                .remove(&(n + 1))
+                .and_then(|part| self.segments[i].restore(&part));
+            let text = match restored {
+                Some(text) => Some(text),
+                None => self.translate_one(config, i, from_lang, to_lang).await?,
+            };
+            results.push((i, text));
+        }
+        Ok(results)
+    }
+
+    /// 单独翻译一个片段，占位符无法还原时返回 None
+    async fn translate_one(
+        &self,
+        config: &Config,
+        i: usize,
+        from_lang: &str,
+        to_lang: &str,
+    ) -> AppResult<Option<String>> {
+        let segment = &self.segments[i];
+        let (text, options) = self.single_request(i);
+        let translated = translate_with_llm(config, &text, from_lang, to_lang, &options).await?;
+        let restored = segment.restore(&translated);
+        if restored.is_none() {
+            log::warn!("译文中的占位符无法还原: {}", segment.source);
+        }
+        Ok(restored)
+    }
+}
+
+/// 按 `<<<n>>>` 标记拆分译文
+fn split_markers(text: &str) -> HashMap<usize, String> {
+    let mut parts = HashMap::new();
+    let markers: Vec<_> = MARKER.captures_iter(text).collect();
+    for (k, caps) in markers.iter().enumerate() {
+        let whole = caps.get(0).unwrap();
+        let end = markers
+            .get(k + 1)
+            .map_or(text.len(), |next| next.get(0).unwrap().start());
+        let Ok(n) = caps[1].parse::<usize>() else {
+            continue;
+        };
+        if parts
+            .insert(n, text[whole.end()..end].trim().to_string())
+            .is_some()
+        {
+            // 重复的标记说明结构已经错乱，交给逐个重试
+            parts.insert(n, String::new());
+        }
+    }
+    parts.retain(|_, part| !part.is_empty());
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_markers_and_batch() {
+        let parts = split_markers("<<<1>>>\n你好\n<<<2>>>\n世界\n\n<<<3>>>\n");
+        assert_eq!(parts.get(&1).map(String::as_str), Some("你好"));
+        assert_eq!(parts.get(&2).map(String::as_str), Some("世界"));
+        assert!(!parts.contains_key(&3));
+
+        let segments: Vec<Masked> = ["aaaa aaaa", "bbbb", "cccc cccc cccc cccc"]
+            .iter()
+            .map(|t| Masked::new(t, 0..t.len(), &[]))
+            .collect();
+        assert_eq!(batch(&[0, 1, 2], &segments, 12), vec![vec![0, 1], vec![2]]);
+    }
+
+    #[test]
+    fn test_plan_requests() {
+        let config = Config::default();
+        let options = TranslateOptions::default();
+        let text = "Run `make` now";
+        let code = 4..10;
+        let single = [Masked::new(text, 0..text.len(), &[code])];
+        let (requests, reused) = plan_requests(&config, &single, "英语", "中文", &options);
+        assert_eq!(reused, 0);
+        assert_eq!(requests[0].0, "Run ⟦1⟧ now");
+        assert_eq!(
+            requests[0].1.extra_instructions.as_deref(),
+            Some(placeholder::INSTRUCTIONS)
+        );
+
+        let pair: Vec<Masked> = ["Hello", "World"]
+            .iter()
+            .map(|t| Masked::new(t, 0..t.len(), &[]))
+            .collect();
+        let (requests, _) = plan_requests(&config, &pair, "英语", "中文", &options);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].0, "<<<1>>>\nHello\n<<<2>>>\nWorld");
+        assert_eq!(
+            requests[0].1.extra_instructions.as_deref(),
+            Some(BATCH_INSTRUCTIONS)
+        );
+    }
+}
@@ -0,0 +1,197 @@
+// 字幕翻译模块
+//
+// 支持 SRT 和 WebVTT。按空行把文件拆成字幕块，只翻译时间轴之后的字幕文字，
+// 序号、时间轴、WebVTT 的文件头和 NOTE/STYLE/REGION 块逐字节保留。
+// 字幕文字中的样式标签（`<i>`、`<c.yellow>`、`<v Speaker>`、`{\an8}` 等）替换为占位符；
+// 相邻字幕分批发送，每批附带上一批的结尾作为上下文。
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::markup;
+use crate::placeholder::Masked;
+use crate::segments::translate_segments;
+use crate::translator::TranslateOptions;
+use std::ops::Range;
+
+lazy_static::lazy_static! {
+    /// 样式标签、WebVTT 时间戳标签以及 ASS 风格的 `{\...}` 覆盖标签
+    static ref STYLE_TAG: regex::Regex = regex::Regex::new(r"</?[A-Za-z0-9.:_ -]*>|\{\\[^}]*\}").unwrap();
+}
+
+/// 字幕格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    /// 根据文件头识别格式（以 `WEBVTT` 开头为 WebVTT，否则按 SRT 处理）
+    pub fn detect(text: &str) -> Self {
+        if text.trim_start_matches('\u{feff}').starts_with("WEBVTT") {
+            SubtitleFormat::Vtt
+        } else {
+            SubtitleFormat::Srt
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "application/x-subrip; charset=utf-8",
+            SubtitleFormat::Vtt => "text/vtt; charset=utf-8",
+        }
+    }
+}
+
+/// 翻译字幕文件，返回同一格式的字幕
+pub async fn translate(
+    config: &Config,
+    text: &str,
+    format: SubtitleFormat,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<String> {
+    let cues = cue_ranges(text, format)?;
+    let masked: Vec<Masked> = cues
+        .iter()
+        .map(|range| {
+            let masked = Masked::new(text, range.clone(), &protected(text, range.clone()));
+            match format {
+                SubtitleFormat::Vtt => masked.with_escape(markup::escape_text),
+                SubtitleFormat::Srt => masked,
+            }
+        })
+        .collect();
+    let translated = translate_segments(config, &masked, from_lang, to_lang, options).await?;
+
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    Ok(markup::apply_edits(
+        text,
+        cues.into_iter()
+            .zip(translated.iter().map(|t| remove_blank_lines(t, newline)))
+            .collect(),
+    ))
+}
+
+/// 所有字幕文字拼接成的纯文本，用于语种检测
+pub fn plain_text(text: &str, format: SubtitleFormat) -> AppResult<String> {
+    Ok(cue_ranges(text, format)?
+        .into_iter()
+        .map(|range| STYLE_TAG.replace_all(&text[range], "").into_owned())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// 每条字幕文字在原文中的区间
+fn cue_ranges(text: &str, format: SubtitleFormat) -> AppResult<Vec<Range<usize>>> {
+    let mut cues = Vec::new();
+    for (n, block) in blocks(text).into_iter().enumerate() {
+        let first = text[block[0].clone()].trim_start_matches('\u{feff}');
+        if format == SubtitleFormat::Vtt
+            && (n == 0 && first.starts_with("WEBVTT")
+                || ["NOTE", "STYLE", "REGION"]
+                    .iter()
+                    .any(|kind| first == *kind || first.starts_with(&format!("{} ", kind))))
+        {
+            continue;
+        }
+
+        // 时间轴位于第一行（无序号）或第二行（序号/标识之后）
+        let Some(timing) = block
+            .iter()
+            .take(2)
+            .position(|line| text[line.clone()].contains("-->"))
+        else {
+            return Err(AppError::Validation(format!(
+                "第 {} 个字幕块缺少时间轴: {}",
+                n + 1,
+                first
+            )));
+        };
+        let lines = &block[timing + 1..];
+        if let (Some(start), Some(end)) = (lines.first(), lines.last()) {
+            cues.push(start.start..end.end);
+        }
+    }
+    if cues.is_empty() {
+        return Err(AppError::Validation("未找到字幕条目".to_string()));
+    }
+    Ok(cues)
+}
+
+/// 按空行拆分的字幕块，每块为各行的区间（不含换行符）
+fn blocks(text: &str) -> Vec<Vec<Range<usize>>> {
+    let mut blocks: Vec<Vec<Range<usize>>> = Vec::new();
+    let mut current = Vec::new();
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let range = start..start + content.len();
+        start += line.len();
+        if content.trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(range);
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+    blocks
+}
+
+/// 字幕文字中需要保护的样式标签和字符实体
+fn protected(text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut protected: Vec<Range<usize>> = STYLE_TAG
+        .find_iter(&text[range.clone()])
+        .map(|m| range.start + m.start()..range.start + m.end())
+        .collect();
+    protected.extend(markup::entity_ranges(text, range));
+    protected
+}
+
+/// 译文中的空行会把一条字幕拆成两块，需要去掉
+fn remove_blank_lines(text: &str, newline: &str) -> String {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join(newline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cues(text: &str) -> Vec<&str> {
+        cue_ranges(text, SubtitleFormat::detect(text))
+            .unwrap()
+            .into_iter()
+            .map(|r| &text[r])
+            .collect()
+    }
+
+    #[test]
+    fn test_srt_cues() {
+        let text = "1\r\n00:00:01,000 --> 00:00:02,000\r\n<i>Hello</i> there\r\nfriend\r\n\r\n2\r\n00:00:03,000 --> 00:00:04,000\r\n{\\an8}Bye\r\n";
+        assert_eq!(
+            cues(text),
+            vec!["<i>Hello</i> there\r\nfriend", "{\\an8}Bye"]
+        );
+
+        let range = cue_ranges(text, SubtitleFormat::Srt).unwrap()[1].clone();
+        assert_eq!(
+            Masked::new(text, range.clone(), &protected(text, range)).text,
+            "⟦1⟧Bye"
+        );
+    }
+
+    #[test]
+    fn test_vtt_cues() {
+        let text = "WEBVTT - demo\n\nNOTE skip me\n\nSTYLE\n::cue { color: red }\n\nintro\n00:01.000 --> 00:02.000 align:start\n<v Roger>Hi &amp; welcome\n\n00:03.000 --> 00:04.000\nSecond\n";
+        assert_eq!(cues(text), vec!["<v Roger>Hi &amp; welcome", "Second"]);
+        assert!(cue_ranges("1\nno timing\n", SubtitleFormat::Srt).is_err());
+    }
+}
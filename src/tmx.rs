@@ -0,0 +1,406 @@
+// TMX 翻译记忆交换格式模块
+//
+// 读取 CAT 工具导出的 TMX 1.4（兼容 1.1-1.3 的 `lang` 属性），每个翻译单元 `<tu>` 中
+// 源语言的 `<tuv>` 与其余每个语言的 `<tuv>` 各组成一个句段。`<seg>` 中的行内标记
+// （`<bpt>`、`<ept>`、`<ph>`、`<it>`、`<ut>`）还原为其中记录的原始代码（如 `<b>`、`{0}`），
+// `<hi>` 只保留其中的文字，`<sub>` 子流程忽略。支持 UTF-8 和带 BOM 的 UTF-16 文件。
+//
+// 导出时同一原文的各语言译文合并为一个翻译单元。
+
+use crate::error::{AppError, AppResult};
+use crate::markup::{self, TokenKind};
+use crate::memory::{
+    normalize_language, DuplicatePolicy, ImportStats, MemoryEntry, TranslationMemory,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// TMX 文件的 Content-Type
+pub const CONTENT_TYPE: &str = "application/x-tmx+xml; charset=utf-8";
+
+/// 行内代码中的子流程（如属性里的提示文字），不属于所在句段
+const SUBFLOW: &str = "sub";
+
+/// 读取结果
+#[derive(Debug, Default)]
+pub struct TmxContent {
+    pub entries: Vec<MemoryEntry>,
+    /// 找不到源语言或缺少译文的翻译单元数
+    pub invalid: usize,
+}
+
+/// 导入结果
+#[derive(Debug, Serialize)]
+pub struct TmxImport {
+    #[serde(flatten)]
+    pub stats: ImportStats,
+    /// 找不到源语言或缺少译文的翻译单元数
+    pub invalid: usize,
+    /// 不属于指定语种对而忽略的句段数
+    pub ignored: usize,
+}
+
+/// 把 TMX 文件导入翻译记忆，`from`/`to` 指定时只导入该语种对的句段
+pub fn import(
+    memory: &TranslationMemory,
+    bytes: &[u8],
+    from: Option<&str>,
+    to: Option<&str>,
+    duplicates: DuplicatePolicy,
+) -> AppResult<TmxImport> {
+    let content = read(&decode(bytes)?)?;
+    let (from, to) = (from.map(normalize_language), to.map(normalize_language));
+    let total = content.entries.len();
+    let entries: Vec<MemoryEntry> = content
+        .entries
+        .into_iter()
+        .filter(|e| from.as_ref().is_none_or(|from| *from == e.from))
+        .filter(|e| to.as_ref().is_none_or(|to| *to == e.to))
+        .collect();
+    let ignored = total - entries.len();
+    Ok(TmxImport {
+        stats: memory.import(entries, duplicates)?,
+        invalid: content.invalid,
+        ignored,
+    })
+}
+
+/// 把翻译记忆导出为 TMX，可按语种对过滤
+pub fn export(memory: &TranslationMemory, from: Option<&str>, to: Option<&str>) -> String {
+    let mut entries = memory.list(from, to, None, usize::MAX);
+    // 按审定时间先后导出
+    entries.reverse();
+    write(&entries)
+}
+
+/// 一个翻译单元
+#[derive(Default)]
+struct Unit {
+    srclang: Option<String>,
+    date: Option<String>,
+    /// (语言, 文字)
+    variants: Vec<(String, String)>,
+}
+
+/// 解码 TMX 文件：按 BOM 识别 UTF-16，其余按 UTF-8
+pub fn decode(bytes: &[u8]) -> AppResult<String> {
+    let utf16 = |big_endian: bool| {
+        let units = bytes[2..].chunks_exact(2).map(|pair| {
+            if big_endian {
+                u16::from_be_bytes([pair[0], pair[1]])
+            } else {
+                u16::from_le_bytes([pair[0], pair[1]])
+            }
+        });
+        char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(|_| AppError::Validation("TMX 文件不是有效的 UTF-16 文本".to_string()))
+    };
+    match bytes {
+        [0xFF, 0xFE, ..] => utf16(false),
+        [0xFE, 0xFF, ..] => utf16(true),
+        _ => {
+            let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+            String::from_utf8(bytes.to_vec())
+                .map_err(|_| AppError::Validation("TMX 文件不是有效的 UTF-8 文本".to_string()))
+        }
+    }
+}
+
+/// 读取 TMX 中的句段，语言代码已规范化
+pub fn read(text: &str) -> AppResult<TmxContent> {
+    let tokens = markup::tokenize(text, &[]);
+    if !tokens
+        .iter()
+        .any(|t| matches!(&t.kind, TokenKind::Start { name, .. } if name == "tmx"))
+    {
+        return Err(AppError::Validation("不是有效的 TMX 文件".to_string()));
+    }
+
+    let attr = |token: &markup::Token, name: &str| {
+        token
+            .attr(name)
+            .map(|range| markup::unescape(&text[range]))
+            .filter(|value| !value.trim().is_empty())
+    };
+
+    let mut header_srclang = None;
+    let mut units = Vec::new();
+    let mut unit: Option<Unit> = None;
+    let mut lang: Option<String> = None;
+    let mut seg: Option<String> = None;
+    let mut subflow_depth = 0usize;
+    for token in &tokens {
+        match &token.kind {
+            TokenKind::Start {
+                name, self_closing, ..
+            } => match name.as_str() {
+                "header" => header_srclang = attr(token, "srclang"),
+                "tu" => {
+                    unit = Some(Unit {
+                        srclang: attr(token, "srclang"),
+                        date: attr(token, "changedate").or_else(|| attr(token, "creationdate")),
+                        variants: Vec::new(),
+                    })
+                }
+                "tuv" => lang = attr(token, "xml:lang").or_else(|| attr(token, "lang")),
+                "seg" if !self_closing => {
+                    seg = Some(String::new());
+                    subflow_depth = 0;
+                }
+                SUBFLOW if seg.is_some() && !self_closing => subflow_depth += 1,
+                _ => {}
+            },
+            TokenKind::End { name } => match name.as_str() {
+                "seg" => {
+                    if let (Some(unit), Some(lang), Some(seg)) = (&mut unit, &lang, seg.take()) {
+                        unit.variants.push((lang.clone(), seg));
+                    }
+                }
+                "tuv" => lang = None,
+                "tu" => units.extend(unit.take()),
+                SUBFLOW if seg.is_some() => subflow_depth = subflow_depth.saturating_sub(1),
+                _ => {}
+            },
+            TokenKind::Text if subflow_depth == 0 => {
+                if let Some(seg) = &mut seg {
+                    seg.push_str(&markup::unescape(&text[token.range.clone()]));
+                }
+            }
+            TokenKind::Declaration if subflow_depth == 0 => {
+                if let (Some(seg), Some(cdata)) = (
+                    &mut seg,
+                    text[token.range.clone()]
+                        .strip_prefix("<![CDATA[")
+                        .and_then(|rest| rest.strip_suffix("]]>")),
+                ) {
+                    seg.push_str(cdata);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut content = TmxContent::default();
+    for unit in units {
+        let srclang = unit
+            .srclang
+            .as_ref()
+            .or(header_srclang.as_ref())
+            .filter(|lang| *lang != "*all*")
+            .map(|lang| normalize_language(lang))
+            .or_else(|| {
+                unit.variants
+                    .first()
+                    .map(|(lang, _)| normalize_language(lang))
+            });
+        let variants: Vec<(String, &str)> = unit
+            .variants
+            .iter()
+            .map(|(lang, text)| (normalize_language(lang), text.trim()))
+            .filter(|(_, text)| !text.is_empty())
+            .collect();
+        let Some((from, source)) =
+            srclang.and_then(|src| variants.iter().find(|(lang, _)| *lang == src))
+        else {
+            content.invalid += 1;
+            continue;
+        };
+        let created_at = unit
+            .date
+            .as_deref()
+            .and_then(parse_date)
+            .unwrap_or_else(|| now.clone());
+        let before = content.entries.len();
+        content
+            .entries
+            .extend(
+                variants
+                    .iter()
+                    .filter(|(lang, _)| lang != from)
+                    .map(|(to, target)| MemoryEntry {
+                        from: from.clone(),
+                        to: to.clone(),
+                        source: source.to_string(),
+                        target: target.to_string(),
+                        created_at: created_at.clone(),
+                    }),
+            );
+        if content.entries.len() == before {
+            content.invalid += 1;
+        }
+    }
+    Ok(content)
+}
+
+/// 生成 TMX 1.4 文档，同一源语言和原文的句段合并为一个翻译单元
+pub fn write(entries: &[MemoryEntry]) -> String {
+    let mut units: Vec<(&MemoryEntry, Vec<&MemoryEntry>)> = Vec::new();
+    let mut index: HashMap<(&str, &str), usize> = HashMap::new();
+    for entry in entries {
+        let key = (entry.from.as_str(), entry.source.as_str());
+        match index.get(&key) {
+            Some(&i) => units[i].1.push(entry),
+            None => {
+                index.insert(key, units.len());
+                units.push((entry, vec![entry]));
+            }
+        }
+    }
+
+    let mut sources: Vec<&str> = entries.iter().map(|e| e.from.as_str()).collect();
+    sources.sort_unstable();
+    sources.dedup();
+    let srclang = match sources[..] {
+        [single] => single,
+        _ => "*all*",
+    };
+
+    let mut out =
+        String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<tmx version=\"1.4\">\n");
+    out.push_str(&format!(
+        "  <header creationtool=\"Rustrans\" creationtoolversion=\"{}\" datatype=\"plaintext\" segtype=\"sentence\" adminlang=\"en\" srclang=\"{}\" o-tmf=\"Rustrans\"/>\n  <body>\n",
+        env!("CARGO_PKG_VERSION"),
+        markup::escape_attr(srclang)
+    ));
+    for (first, targets) in units {
+        let date = targets
+            .iter()
+            .filter_map(|e| format_date(&e.created_at))
+            .max();
+        match date {
+            Some(date) => out.push_str(&format!(
+                "    <tu srclang=\"{}\" changedate=\"{}\">\n",
+                markup::escape_attr(&first.from),
+                date
+            )),
+            None => out.push_str(&format!(
+                "    <tu srclang=\"{}\">\n",
+                markup::escape_attr(&first.from)
+            )),
+        }
+        let variants = std::iter::once((&first.from, &first.source))
+            .chain(targets.iter().map(|e| (&e.to, &e.target)));
+        for (lang, text) in variants {
+            out.push_str(&format!(
+                "      <tuv xml:lang=\"{}\"><seg>{}</seg></tuv>\n",
+                markup::escape_attr(lang),
+                markup::escape_text(text)
+            ));
+        }
+        out.push_str("    </tu>\n");
+    }
+    out.push_str("  </body>\n</tmx>\n");
+    out
+}
+
+/// TMX 日期（`20240131T120000Z`）转为 RFC 3339
+fn parse_date(date: &str) -> Option<String> {
+    chrono::NaiveDateTime::parse_from_str(date.trim(), "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|date| date.and_utc().to_rfc3339())
+}
+
+/// RFC 3339 日期转为 TMX 日期
+fn format_date(date: &str) -> Option<String> {
+    chrono::DateTime::parse_from_rfc3339(date).ok().map(|date| {
+        date.with_timezone(&chrono::Utc)
+            .format("%Y%m%dT%H%M%SZ")
+            .to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_inline_codes_and_languages() {
+        let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tmx version="1.4">
+  <header creationtool="X" srclang="en-US" datatype="plaintext" segtype="sentence" adminlang="en" o-tmf="X"/>
+  <body>
+    <tu changedate="20240131T120000Z">
+      <tuv xml:lang="en-US"><seg>Click <bpt i="1">&lt;b&gt;</bpt>Save<ept i="1">&lt;/b&gt;</ept> &amp; exit<ph>&lt;img src="x"&gt;<sub>Alt</sub></ph></seg></tuv>
+      <tuv xml:lang="zh-CN"><seg>单击<bpt i="1">&lt;b&gt;</bpt>保存<ept i="1">&lt;/b&gt;</ept>并退出</seg></tuv>
+      <tuv lang="JA-JP"><seg><![CDATA[保存して終了]]></seg></tuv>
+    </tu>
+    <tu srclang="*all*">
+      <tuv xml:lang="de-DE"><seg>Nur Quelle</seg></tuv>
+    </tu>
+  </body>
+</tmx>"#;
+        let content = read(tmx).unwrap();
+        assert_eq!(content.invalid, 1);
+        let pairs: Vec<(&str, &str, &str, &str)> = content
+            .entries
+            .iter()
+            .map(|e| {
+                (
+                    e.from.as_str(),
+                    e.to.as_str(),
+                    e.source.as_str(),
+                    e.target.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (
+                    "en",
+                    "zh-Hans",
+                    "Click <b>Save</b> & exit<img src=\"x\">",
+                    "单击<b>保存</b>并退出"
+                ),
+                (
+                    "en",
+                    "ja",
+                    "Click <b>Save</b> & exit<img src=\"x\">",
+                    "保存して終了"
+                ),
+            ]
+        );
+        assert!(content.entries[0]
+            .created_at
+            .starts_with("2024-01-31T12:00:00"));
+        assert!(read("<xliff/>").is_err());
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let entry = |to: &str, source: &str, target: &str| MemoryEntry {
+            from: "en".to_string(),
+            to: to.to_string(),
+            source: source.to_string(),
+            target: target.to_string(),
+            created_at: "2024-01-31T20:00:00+08:00".to_string(),
+        };
+        let entries = vec![
+            entry("ja", "A < B", "A は B 未満"),
+            entry("zh-Hans", "A < B", "A 小于 B"),
+            entry("ja", "Done", "完了"),
+        ];
+        let tmx = write(&entries);
+        assert_eq!(tmx.matches("<tu ").count(), 2);
+        assert!(tmx.contains("srclang=\"en\" o-tmf"));
+        assert!(tmx.contains("changedate=\"20240131T120000Z\""));
+
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain(tmx.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let content = read(&decode(&utf16).unwrap()).unwrap();
+        let read_back: Vec<(String, String, String)> = content
+            .entries
+            .into_iter()
+            .map(|e| (e.to, e.source, e.target))
+            .collect();
+        let expected: Vec<(String, String, String)> = entries
+            .into_iter()
+            .map(|e| (e.to, e.source, e.target))
+            .collect();
+        assert_eq!(read_back, expected);
+    }
+}
@@ -1,8 +1,20 @@
 // 大模型翻译服务模块
 
-use crate::config::Config;
+use crate::alignment;
+use crate::chunker;
+use crate::config::{Config, ModelParams};
+use crate::error::{AppError, AppResult};
+use crate::examples;
+use crate::memory;
 use crate::models::{LLMRequest, LLMResponse, Message};
+use crate::prompt::{self, PromptContext};
+use futures::{StreamExt, TryStreamExt};
 use pangu::spacing;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 // 全局HTTP客户端，复用连接池
 lazy_static::lazy_static! {
@@ -14,67 +26,242 @@ lazy_static::lazy_static! {
         .expect("Failed to create HTTP client");
 }
 
-/// 处理系统提示词中的模板变量
-fn process_system_prompt(template: &str, from_lang: &str, to_lang: &str, text: &str) -> String {
-    template
-        .replace("{{from}}", from_lang)
-        .replace("{{to}}", to_lang)
-        .replace("{{text}}", text)
+/// 进程内同时进行的大模型请求的许可：单次翻译和后台任务共用，数量为 `chunking.concurrency`，
+/// 配置修改后换用新的信号量（已发出的请求在旧信号量上完成）
+static LLM_PERMITS: parking_lot::Mutex<Option<(usize, Arc<Semaphore>)>> =
+    parking_lot::Mutex::new(None);
+
+/// 按当前配置的并发上限取得请求许可
+async fn acquire_permit(config: &Config) -> OwnedSemaphorePermit {
+    let limit = config.chunking.concurrency.max(1);
+    let semaphore = {
+        let mut permits = LLM_PERMITS.lock();
+        match &*permits {
+            Some((size, semaphore)) if *size == limit => semaphore.clone(),
+            _ => {
+                let semaphore = Arc::new(Semaphore::new(limit));
+                *permits = Some((limit, semaphore.clone()));
+                semaphore
+            }
+        }
+    };
+    semaphore
+        .acquire_owned()
+        .await
+        .expect("请求许可的信号量不会关闭")
+}
+
+tokio::task_local! {
+    /// 当前任务累计的 token 用量，后台任务据此统计消耗
+    static TOKEN_USAGE: Arc<AtomicU64>;
+}
+
+/// 在统计 token 用量的范围内执行 `future`，其中所有大模型请求的用量累加到 `counter`
+pub async fn with_usage<F: Future>(counter: Arc<AtomicU64>, future: F) -> F::Output {
+    TOKEN_USAGE.scope(counter, future).await
+}
+
+/// 单次翻译的请求级选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TranslateOptions {
+    /// 已合并请求级覆盖的模型参数
+    pub params: ModelParams,
+    /// 本次使用的提示词模板（预设），为 None 时使用配置中的系统提示词
+    pub prompt_template: Option<String>,
+    /// 使用的预设名称
+    pub preset: Option<String>,
+    /// 客户端密钥
+    pub client: Option<String>,
+    /// 源语种检测置信度，请求指定源语种时为 None
+    pub confidence: Option<f32>,
+    /// 调用方提供的上下文
+    pub context: Option<String>,
+    /// 领域，用于选择少样本示例
+    pub domain: Option<String>,
+    /// 长文本分块时前一个分块的原文，作为上文提供给模型
+    pub previous_text: Option<String>,
+    /// 附加在系统提示词之后的格式说明（如片段标记、占位符的处理要求）
+    pub extra_instructions: Option<String>,
+}
+
+impl TranslateOptions {
+    /// 本次请求实际使用的提示词模板
+    fn template<'a>(&'a self, config: &'a Config) -> &'a str {
+        self.prompt_template
+            .as_deref()
+            .unwrap_or(config.system_prompt())
+    }
+
+    /// 渲染系统提示词；模板为空时使用内置提示词
+    pub fn system_prompt(
+        &self,
+        config: &Config,
+        text: &str,
+        from_lang: &str,
+        to_lang: &str,
+    ) -> AppResult<String> {
+        let template = self.template(config);
+        if template.is_empty() {
+            return Ok(build_system_prompt(from_lang, to_lang));
+        }
+
+        let mut ctx = PromptContext::new(config, text, from_lang, to_lang);
+        ctx.confidence = self.confidence.unwrap_or(1.0);
+        ctx.client = self.client.as_deref();
+        ctx.preset = self.preset.as_deref();
+        ctx.context = self.context.as_deref();
+        prompt::render(template, &ctx)
+    }
 }
 
-/// 调用大模型进行翻译
+/// 调用大模型进行翻译；翻译记忆中有完全匹配的审定译文时直接返回
 pub async fn translate_with_llm(
     config: &Config,
     text: &str,
     from_lang: &str,
     to_lang: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let system_prompt = if !config.system_prompt().is_empty() {
-        // 如果配置中有自定义系统提示词，处理模板变量
-        process_system_prompt(config.system_prompt(), from_lang, to_lang, text)
-    } else {
-        // 否则使用默认的系统提示词
-        build_system_prompt(from_lang, to_lang)
-    };
+    options: &TranslateOptions,
+) -> AppResult<String> {
+    if let Some(target) = memory_exact(config, text, from_lang, to_lang) {
+        return Ok(target);
+    }
+    let request_body = prepare_llm_request(config, text, from_lang, to_lang, options)?;
+    send_llm_request(config, &request_body).await
+}
 
-    let request_body = LLMRequest {
-        model: config.model().to_string(),
-        messages: vec![
-            Message {
-                role: "system".to_string(),
-                content: system_prompt,
-            },
-            Message {
-                role: "user".to_string(),
-                content: text.to_string(),
-            },
-        ],
-        temperature: 0.3,
-    };
+/// 长文本切分出的一个分块及翻译它时使用的选项
+pub struct Chunk<'a> {
+    /// 分块开头的空白，原样保留
+    pub lead: &'a str,
+    /// 需要翻译的正文，为空时不发送请求
+    pub body: &'a str,
+    /// 分块结尾的空白，原样保留
+    pub trail: &'a str,
+    pub options: TranslateOptions,
+}
 
-    let response = HTTP_CLIENT
-        .post(config.api_url())
-        .header("Authorization", format!("Bearer {}", config.api_key()))
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
+/// 按长度切分文本，前一个分块的原文作为下一个分块的上文；不超过上限时整段作为一个分块
+pub fn plan_chunks<'a>(
+    config: &Config,
+    text: &'a str,
+    options: &TranslateOptions,
+) -> Vec<Chunk<'a>> {
+    let chunks = chunker::split(text, config.chunking.max_tokens);
+    if chunks.len() <= 1 {
+        return vec![Chunk {
+            lead: "",
+            body: text,
+            trail: "",
+            options: options.clone(),
+        }];
+    }
+
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut options = options.clone();
+            options.previous_text = i
+                .checked_sub(1)
+                .map(|prev| chunker::context_tail(chunks[prev]).to_string())
+                .filter(|prev| !prev.is_empty());
+            let (lead, body, trail) = chunker::trim_edges(chunk);
+            Chunk {
+                lead,
+                body,
+                trail,
+                options,
+            }
+        })
+        .collect()
+}
+
+/// 翻译任意长度的文本
+///
+/// 超过分块上限时按段落/句子切分，分块并发翻译（前一个分块的原文作为上文），
+/// 再按原顺序拼接并还原分块首尾的空白和换行。
+pub async fn translate_long_text(
+    config: &Config,
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<String> {
+    let tasks = plan_chunks(config, text, options)
+        .into_iter()
+        .map(|chunk| async move {
+            if chunk.body.is_empty() {
+                return Ok::<_, AppError>(chunk.lead.to_string());
+            }
+            let translated =
+                translate_with_llm(config, chunk.body, from_lang, to_lang, &chunk.options).await?;
+            Ok(format!("{}{}{}", chunk.lead, translated, chunk.trail))
+        });
+
+    let translated: Vec<String> = futures::stream::iter(tasks)
+        .buffered(config.chunking.concurrency.max(1))
+        .try_collect()
         .await?;
+    Ok(translated.concat())
+}
 
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        return Err(format!("LLM API 错误: {}", error_text).into());
+/// 翻译记忆中完全匹配的译文，未启用翻译记忆时返回 None
+pub fn memory_exact(config: &Config, text: &str, from_lang: &str, to_lang: &str) -> Option<String> {
+    if !config.memory.enabled {
+        return None;
     }
+    memory::global()?.exact(from_lang, to_lang, text)
+}
 
-    let llm_response: LLMResponse = response.json().await?;
+/// 渲染提示词并构建单次翻译的大模型请求体（不发送）
+pub fn prepare_llm_request(
+    config: &Config,
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<LLMRequest> {
+    let system_prompt = options.system_prompt(config, text, from_lang, to_lang)?;
+    Ok(build_llm_request(
+        config,
+        options,
+        system_prompt,
+        text,
+        from_lang,
+        to_lang,
+    ))
+}
 
-    if let Some(choice) = llm_response.choices.first() {
-        let translated = choice.message.content.trim();
-        // 使用 pangu 优化排版
-        let formatted = spacing(translated).to_string();
-        Ok(formatted)
-    } else {
-        Err("未收到翻译结果".into())
-    }
+/// 估算请求消耗的输入 token 数
+///
+/// 不依赖具体模型的分词器：CJK 字符按每字 1 个 token，其余字符按每 4 个 1 个 token，
+/// 每条消息另加 4 个 token 的格式开销。结果只用于粗略评估。
+pub fn estimate_tokens(request: &LLMRequest) -> usize {
+    request
+        .messages
+        .iter()
+        .map(|message| 4 + estimate_text_tokens(&message.content))
+        .sum()
+}
+
+/// 估算一段文本的 token 数，规则同 [`estimate_tokens`]
+pub fn estimate_text_tokens(text: &str) -> usize {
+    let (cjk, other) = text.chars().fold((0usize, 0usize), |(cjk, other), c| {
+        if is_cjk(c) {
+            (cjk + 1, other)
+        } else {
+            (cjk, other + 1)
+        }
+    });
+    cjk + other.div_ceil(4)
+}
+
+/// 是否为按每字约 1 个 token 估算的中日韩字符
+pub fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xFF00..=0xFFEF
+    )
 }
 
 /// 批量翻译接口，提高处理效率
@@ -84,86 +271,167 @@ pub async fn translate_batch_with_llm(
     texts: &[String],
     from_lang: &str,
     to_lang: &str,
-) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    options: &TranslateOptions,
+) -> AppResult<Vec<String>> {
     // 对于批量翻译，使用通用提示词，不包含具体文本
-    let system_prompt = if !config.system_prompt().is_empty() {
-        // 处理系统提示词，但批量翻译时文本为空
-        process_system_prompt(config.system_prompt(), from_lang, to_lang, "")
-    } else {
-        build_system_prompt(from_lang, to_lang)
-    };
-
-    let mut results = Vec::new();
+    let system_prompt = options.system_prompt(config, "", from_lang, to_lang)?;
 
     // 批量处理多个文本
     let tasks: Vec<_> = texts
         .iter()
         .map(|text| {
-            let config = config.clone();
-            let system_prompt = system_prompt.clone();
-            let text = text.clone();
-
-            async move {
-                let request_body = LLMRequest {
-                    model: config.model().to_string(),
-                    messages: vec![
-                        Message {
-                            role: "system".to_string(),
-                            content: system_prompt,
-                        },
-                        Message {
-                            role: "user".to_string(),
-                            content: text,
-                        },
-                    ],
-                    temperature: 0.3,
-                };
-
-                match HTTP_CLIENT
-                    .post(config.api_url())
-                    .header("Authorization", format!("Bearer {}", config.api_key()))
-                    .header("Content-Type", "application/json")
-                    .json(&request_body)
-                    .send()
-                    .await
-                {
-                    Ok(response) => {
-                        if response.status().is_success() {
-                            match response.json::<LLMResponse>().await {
-                                Ok(llm_response) => {
-                                    if let Some(choice) = llm_response.choices.first() {
-                                        let translated = choice.message.content.trim();
-                                        Ok(spacing(translated).to_string())
-                                    } else {
-                                        Err("未收到翻译结果".to_string())
-                                    }
-                                }
-                                Err(e) => Err(format!("解析响应失败: {}", e)),
-                            }
-                        } else {
-                            match response.text().await {
-                                Ok(error_text) => Err(format!("LLM API 错误: {}", error_text)),
-                                Err(e) => Err(format!("读取错误响应失败: {}", e)),
-                            }
-                        }
-                    }
-                    Err(e) => Err(format!("网络请求失败: {}", e)),
-                }
-            }
+            let request_body = build_llm_request(
+                config,
+                options,
+                system_prompt.clone(),
+                text,
+                from_lang,
+                to_lang,
+            );
+            async move { send_llm_request(config, &request_body).await }
         })
         .collect();
 
     // 并行执行所有翻译任务
-    let batch_results = futures::future::join_all(tasks).await;
+    futures::future::join_all(tasks).await.into_iter().collect()
+}
 
-    for result in batch_results {
-        match result {
-            Ok(translated) => results.push(translated),
-            Err(e) => return Err(e.into()),
-        }
+/// 构建大模型请求体
+///
+/// 消息顺序：系统提示词、格式说明、少样本示例（user/assistant 交替）、翻译记忆参考、
+/// 上文（分块翻译时）、待翻译文本。
+fn build_llm_request(
+    config: &Config,
+    options: &TranslateOptions,
+    system_prompt: String,
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+) -> LLMRequest {
+    let params = &options.params;
+    let shots = examples::select(
+        &config.few_shot_examples,
+        text,
+        from_lang,
+        to_lang,
+        options.domain.as_deref(),
+        config.few_shot_count,
+    );
+
+    let mut messages = vec![Message {
+        role: "system".to_string(),
+        content: system_prompt,
+    }];
+    if let Some(instructions) = &options.extra_instructions {
+        messages.push(Message {
+            role: "system".to_string(),
+            content: instructions.clone(),
+        });
+    }
+    messages.extend(examples::to_messages(&shots));
+    if let Some(references) = memory_references(config, text, from_lang, to_lang) {
+        messages.push(Message {
+            role: "system".to_string(),
+            content: references,
+        });
+    }
+    if let Some(previous) = &options.previous_text {
+        messages.push(Message {
+            role: "system".to_string(),
+            content: format!(
+                "以下是待翻译文本的上文，仅用于理解语境，不要翻译或输出：\n{}",
+                previous
+            ),
+        });
+    }
+    messages.push(Message {
+        role: "user".to_string(),
+        content: text.to_string(),
+    });
+
+    LLMRequest {
+        model: config.model().to_string(),
+        messages,
+        temperature: params.temperature,
+        max_tokens: params.max_tokens,
+        top_p: params.top_p,
+        seed: params.seed,
+    }
+}
+
+/// 翻译记忆中的相似句段，整理为参考说明
+fn memory_references(
+    config: &Config,
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+) -> Option<String> {
+    let settings = &config.memory;
+    if !settings.enabled {
+        return None;
+    }
+    let matches = memory::global()?.fuzzy(
+        from_lang,
+        to_lang,
+        text,
+        settings.fuzzy_threshold,
+        settings.max_references,
+    );
+    if matches.is_empty() {
+        return None;
+    }
+    let references: Vec<String> = matches
+        .iter()
+        .map(|m| {
+            format!(
+                "原文（相似度 {:.0}%）：{}\n译文：{}",
+                m.score * 100.0,
+                m.source,
+                m.target
+            )
+        })
+        .collect();
+    Some(format!(
+        "以下是翻译记忆中与待译内容相似的原文及其审定译文，请沿用其中的术语和表达，但以待译原文的实际内容为准：\n{}",
+        references.join("\n\n")
+    ))
+}
+
+/// 发送请求并提取翻译结果（经 pangu 排版）
+async fn send_llm_request(config: &Config, request_body: &LLMRequest) -> AppResult<String> {
+    let _permit = acquire_permit(config).await;
+    let response = HTTP_CLIENT
+        .post(config.api_url())
+        .timeout(std::time::Duration::from_secs(config.request_timeout_secs))
+        .header("Authorization", format!("Bearer {}", config.api_key()))
+        .header("Content-Type", "application/json")
+        .json(request_body)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await?;
+        return Err(AppError::Translation(format!(
+            "LLM API 错误: {}",
+            error_text
+        )));
     }
 
-    Ok(results)
+    let llm_response: LLMResponse = response.json().await?;
+
+    if let Some(choice) = llm_response.choices.first() {
+        let translated = choice.message.content.trim();
+        // 接口未返回用量时按估算值统计
+        let tokens = llm_response.usage.map_or_else(
+            || (estimate_tokens(request_body) + estimate_text_tokens(translated)) as u64,
+            |usage| usage.total_tokens,
+        );
+        let _ = TOKEN_USAGE.try_with(|counter| counter.fetch_add(tokens, Ordering::Relaxed));
+        // 使用 pangu 优化排版
+        Ok(spacing(translated).to_string())
+    } else {
+        Err(AppError::Translation("未收到翻译结果".to_string()))
+    }
 }
 
 /// 构建系统提示词
@@ -188,6 +456,8 @@ fn build_system_prompt(from_lang: &str, to_lang: &str) -> String {
 }
 
 /// 处理翻译结果，分段并进行 pangu 格式化
+///
+/// 段落数量一致时逐段返回；不一致时按长度比例对齐，每组对应原文段落的译文作为一项。
 pub fn process_translation_result(original: &str, translated: &str) -> Vec<String> {
     let original_paragraphs: Vec<&str> = original.split('\n').collect();
     let translated_paragraphs: Vec<&str> = translated.split('\n').collect();
@@ -203,8 +473,12 @@ pub fn process_translation_result(original: &str, translated: &str) -> Vec<Strin
             .collect();
     }
 
-    // 如果不匹配，返回整体翻译结果（仍然进行 pangu 处理）
-    vec![format_paragraph(translated)]
+    // 如果不匹配，按对齐结果分组（仍然进行 pangu 处理）
+    alignment::align_units(&original_paragraphs, &translated_paragraphs)
+        .into_iter()
+        .filter(|(_, target)| !target.is_empty())
+        .map(|(_, target)| format_paragraph(&translated_paragraphs[target].join("\n")))
+        .collect()
 }
 
 #[cfg(test)]
@@ -227,6 +501,37 @@ mod tests {
         assert_eq!(result, vec!["Combined translation"]);
     }
 
+    #[test]
+    fn test_estimate_tokens() {
+        let request = LLMRequest {
+            model: "m".to_string(),
+            messages: vec![
+                Message {
+                    role: "system".to_string(),
+                    content: "翻译为英语".to_string(),
+                },
+                Message {
+                    role: "user".to_string(),
+                    content: "Hello, world".to_string(),
+                },
+            ],
+            temperature: 0.3,
+            max_tokens: None,
+            top_p: None,
+            seed: None,
+        };
+        assert_eq!(estimate_tokens(&request), (4 + 5) + (4 + 3));
+    }
+
+    #[test]
+    fn test_process_translation_result_aligns_paragraphs() {
+        let original = "Title\nA fairly long first paragraph with many words in it.\nAnother long paragraph that also has plenty of words.";
+        let translated = "标题\n一个相当长的第一段，里面有很多词。另一个同样有很多词的长段落。";
+        let result = process_translation_result(original, translated);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0], "标题");
+    }
+
     #[test]
     fn test_pangu_spacing() {
         let original = "第一段\n第二段";
@@ -235,4 +540,20 @@ mod tests {
         // pangu 会在中英文之间添加空格
         assert_eq!(result, vec!["这是 test 文本，包含 English 和中文混排"]);
     }
+
+    #[actix_web::test]
+    async fn test_llm_permits_follow_concurrency() {
+        let mut config = Config::default();
+        config.chunking.concurrency = 2;
+        let first = acquire_permit(&config).await;
+        let _second = acquire_permit(&config).await;
+        let wait = std::time::Duration::from_millis(50);
+        assert!(tokio::time::timeout(wait, acquire_permit(&config))
+            .await
+            .is_err());
+        drop(first);
+        assert!(tokio::time::timeout(wait, acquire_permit(&config))
+            .await
+            .is_ok());
+    }
 }
@@ -0,0 +1,213 @@
+// 界面文案变量保护模块
+//
+// 界面文案中常见的插值变量（`{name}`、`{{count}}`、`%s`、`%1$d`、`${var}`）、
+// ICU MessageFormat 的 plural/select 结构以及 HTML 实体，在发送给模型前替换为占位符，
+// 翻译后检查每个占位符是否恰好出现一次再还原，避免变量被翻译、改写或丢失。
+// ICU 结构中只有各分支的文字会被翻译，选择器、变量名和 `#` 都受保护。
+
+use crate::config::Config;
+use crate::error::AppResult;
+use crate::markup;
+use crate::placeholder::{self, Masked};
+use crate::translator::{memory_exact, translate_long_text, TranslateOptions};
+use std::ops::Range;
+
+lazy_static::lazy_static! {
+    /// `{{count}}`、`{{- name}}` 等双花括号变量
+    static ref DOUBLE_BRACE: regex::Regex = regex::Regex::new(r"^\{\{[^{}\n]*\}\}").unwrap();
+    /// `{name}`、`{0}`、`{price:.2f}` 以及 `${name}`
+    static ref BRACE: regex::Regex = regex::Regex::new(r"^\$?\{[\w.$-]*(:[^{}\s]*)?\}").unwrap();
+    /// printf 风格：`%s`、`%1$d`、`%.2f`、`%@`、`%(name)s`、`%%`
+    static ref PRINTF: regex::Regex = regex::Regex::new(
+        r"^%(%|(\d+\$|\([\w.-]+\))?[-+0#]*(\d+|\*)?(\.(\d+|\*))?(hh|h|ll|l|L|z|j|t|q)?[diouxXeEfFgGaAcspn@])",
+    )
+    .unwrap();
+    /// ICU 参数头：`{count, plural,`、`{gender, select,`
+    static ref ICU_HEAD: regex::Regex =
+        regex::Regex::new(r"^\{\s*[\w.-]+\s*,\s*(plural|select|selectordinal)\s*,").unwrap();
+}
+
+/// 文本中需要保护的变量区间
+pub fn find(text: &str) -> Vec<Range<usize>> {
+    find_in(text, 0..text.len(), false)
+}
+
+/// 在 `range` 内查找变量；`plural` 为 true 时 `#` 也视为变量
+fn find_in(text: &str, range: Range<usize>, plural: bool) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut pos = range.start;
+    while pos < range.end {
+        let rest = &text[pos..range.end];
+        let found = match rest.as_bytes()[0] {
+            b'{' => {
+                if let Some((end, icu)) = icu(text, pos, range.end) {
+                    ranges.extend(icu);
+                    pos = end;
+                    continue;
+                }
+                DOUBLE_BRACE
+                    .find(rest)
+                    .or_else(|| BRACE.find(rest))
+                    .map(|m| m.end())
+            }
+            b'$' => BRACE.find(rest).map(|m| m.end()),
+            // 紧跟数字的 `%` 是百分号（如 `20%off`），只有转义的 `%%` 仍需保护
+            b'%' if pos > 0 && text.as_bytes()[pos - 1].is_ascii_digit() => {
+                rest.starts_with("%%").then_some(2)
+            }
+            b'%' => PRINTF.find(rest).map(|m| m.end()),
+            b'&' => markup::entity_ranges(text, pos..range.end)
+                .first()
+                .filter(|r| r.start == pos)
+                .map(|r| r.len()),
+            b'#' if plural => Some(1),
+            _ => None,
+        };
+        match found {
+            Some(len) => {
+                ranges.push(pos..pos + len);
+                pos += len;
+            }
+            None => pos += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    ranges
+}
+
+/// 解析从 `start` 开始的 ICU plural/select 结构，返回结束位置和受保护的区间
+fn icu(text: &str, start: usize, limit: usize) -> Option<(usize, Vec<Range<usize>>)> {
+    let head = ICU_HEAD.captures(&text[start..limit])?;
+    let plural = &head[1] != "select";
+    let bytes = text.as_bytes();
+    let mut ranges = Vec::new();
+    let mut protected_start = start;
+    let mut pos = start + head.get(0)?.end();
+
+    loop {
+        while pos < limit && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        match bytes.get(pos).filter(|_| pos < limit)? {
+            b'}' => {
+                ranges.push(protected_start..pos + 1);
+                return Some((pos + 1, ranges));
+            }
+            b'{' => {
+                // 分支内容：选择器及之前的部分受保护，内容递归查找变量
+                let body_start = pos + 1;
+                let body_end = matching_brace(text, pos, limit)?;
+                ranges.push(protected_start..body_start);
+                ranges.extend(find_in(text, body_start..body_end, plural));
+                protected_start = body_end;
+                pos = body_end + 1;
+            }
+            _ => {
+                // 选择器（`one`、`=0`、`offset:1` 等）
+                while pos < limit && !bytes[pos].is_ascii_whitespace() && bytes[pos] != b'{' {
+                    if bytes[pos] == b'}' {
+                        return None;
+                    }
+                    pos += 1;
+                }
+            }
+        }
+    }
+}
+
+/// `open` 处的 `{` 对应的 `}` 位置
+fn matching_brace(text: &str, open: usize, limit: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, b) in text.as_bytes()[open..limit].iter().enumerate() {
+        match b {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 翻译记忆中原文的审定译文，变量替换回占位符后返回；变量不齐全时返回 None
+pub fn memory_translation(
+    config: &Config,
+    masked: &Masked,
+    from_lang: &str,
+    to_lang: &str,
+) -> Option<String> {
+    memory_exact(config, &masked.source, from_lang, to_lang)
+        .and_then(|target| masked.mask_translation(&target))
+}
+
+/// 翻译含占位符的文本时使用的选项
+pub fn placeholder_options(options: &TranslateOptions) -> TranslateOptions {
+    let mut options = options.clone();
+    options.extra_instructions = Some(placeholder::INSTRUCTIONS.to_string());
+    options
+}
+
+/// 翻译已替换变量的文本，返回仍含占位符的译文
+///
+/// 翻译记忆中有原文的审定译文且变量齐全时直接使用；否则调用大模型，
+/// 占位符缺失或重复时重试一次，取问题较少的一次结果。最终的检查和还原由调用方完成。
+pub async fn translate(
+    config: &Config,
+    masked: &Masked,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<String> {
+    if let Some(target) = memory_translation(config, masked, from_lang, to_lang) {
+        return Ok(target);
+    }
+
+    let options = placeholder_options(options);
+    let first = translate_long_text(config, &masked.text, from_lang, to_lang, &options).await?;
+    let problems = masked.problems(&first).len();
+    if problems == 0 {
+        return Ok(first);
+    }
+    log::warn!("译文中有 {} 个占位符缺失或重复，重试一次", problems);
+    let second = translate_long_text(config, &masked.text, from_lang, to_lang, &options).await?;
+    if masked.problems(&second).len() < problems {
+        Ok(second)
+    } else {
+        Ok(first)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn masked(text: &str) -> String {
+        Masked::new(text, 0..text.len(), &find(text)).text
+    }
+
+    #[test]
+    fn test_find_variables() {
+        assert_eq!(
+            masked("Hello {name}, you have %d new %1$s in {{folder}} &amp; ${total}"),
+            "Hello ⟦1⟧, you have ⟦2⟧ new ⟦3⟧ in ⟦4⟧ ⟦5⟧ ⟦6⟧"
+        );
+        assert_eq!(masked("50% off, 100%% sure"), "50% off, 100⟦1⟧ sure");
+        assert_eq!(masked("Save 20%off, 5%in stock"), "Save 20%off, 5%in stock");
+        assert_eq!(masked("Use { braces } freely"), "Use { braces } freely");
+    }
+
+    #[test]
+    fn test_find_icu() {
+        assert_eq!(
+            masked("{count, plural, =0 {No files} one {# file by {user}} other {# files}} left"),
+            "⟦1⟧No files⟦2⟧ file by ⟦3⟧ files⟦4⟧ left"
+        );
+        assert_eq!(
+            masked("{gender, select, male {He} other {They}} #1"),
+            "⟦1⟧He⟦2⟧They⟦3⟧ #1"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+// 配置热重载模块
+
+use crate::config::Config;
+use crate::error::ConfigError;
+use crate::history;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// 文件变更检查间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 启动配置文件监听任务
+///
+/// 定期检查配置文件及其引用的提示词文件的修改时间，发生变化时重新加载、
+/// 校验并原子替换共享配置。加载或校验失败时保留当前配置。
+pub fn spawn_config_watcher(config: Arc<RwLock<Config>>, config_path: PathBuf) {
+    actix_web::rt::spawn(async move {
+        let mut watched = snapshot(&config.read().watched_files(&config_path));
+        let mut interval = actix_web::rt::time::interval(POLL_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let files: Vec<PathBuf> = watched.keys().cloned().collect();
+            let current = snapshot(&files);
+            if current == watched {
+                continue;
+            }
+            watched = current;
+
+            if !config_path.exists() {
+                log::warn!("配置文件 {} 已被删除，保留当前配置", config_path.display());
+                continue;
+            }
+
+            match reload(&config, &config_path) {
+                Ok(files) => watched = snapshot(&files),
+                Err(e) => log::error!("配置热重载失败，保留当前配置: {}", e),
+            }
+        }
+    });
+}
+
+/// 重新加载配置并替换共享配置，返回新的监听文件列表
+fn reload(config: &RwLock<Config>, config_path: &Path) -> Result<Vec<PathBuf>, ConfigError> {
+    let new_config = Config::load_from_file(config_path)?;
+    new_config.validate()?;
+
+    let files = new_config.watched_files(config_path);
+    let (previous, changes) = {
+        let mut guard = config.write();
+        let changes = guard.diff(&new_config);
+        let previous = std::mem::replace(&mut *guard, new_config.clone());
+        (previous, changes)
+    };
+
+    if changes.is_empty() {
+        log::debug!("配置文件已变更，但内容无差异");
+    } else {
+        log::info!("配置已从磁盘重新加载: {}", changes.join("; "));
+        if let Err(e) = history::record(config_path, &previous, &new_config, "hot-reload") {
+            log::warn!("记录配置历史失败: {}", e);
+        }
+    }
+
+    Ok(files)
+}
+
+/// 获取文件的修改时间快照，不存在的文件记为 None
+fn snapshot(files: &[PathBuf]) -> HashMap<PathBuf, Option<SystemTime>> {
+    files
+        .iter()
+        .map(|path| {
+            let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+            (path.clone(), modified)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_swaps_valid_config_only() {
+        let dir = std::env::temp_dir().join(format!("rustrans-watcher-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("config.json");
+        let mut initial = Config::default();
+        initial.llm_api_url = "https://api.example.com/v1/chat/completions".to_string();
+        initial.llm_api_key = "sk-test".to_string();
+        initial.system_prompt_file = Some("prompt.txt".to_string());
+        initial.save_to_file(&config_path).unwrap();
+        std::fs::write(dir.join("prompt.txt"), "你是译者").unwrap();
+        let config = RwLock::new(Config::load_from_file(&config_path).unwrap());
+
+        let mut edited = config.read().clone();
+        edited.llm_model = "gpt-4o-mini".to_string();
+        edited.save_to_file(&config_path).unwrap();
+        std::fs::write(dir.join("prompt.txt"), "你是资深译者").unwrap();
+        let files = reload(&config, &config_path).unwrap();
+        assert_eq!(files, vec![config_path.clone(), dir.join("prompt.txt")]);
+        assert_eq!(config.read().llm_model, "gpt-4o-mini");
+        assert_eq!(config.read().system_prompt, "你是资深译者");
+
+        let mut invalid = config.read().clone();
+        invalid.llm_api_url = "ftp://example.com".to_string();
+        invalid.save_to_file(&config_path).unwrap();
+        assert!(reload(&config, &config_path).is_err());
+        assert!(config.read().llm_api_url.starts_with("https://"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_marks_missing_files() {
+        let missing = std::env::temp_dir().join("rustrans-watcher-missing.json");
+        let snapshot = snapshot(std::slice::from_ref(&missing));
+        assert_eq!(snapshot.get(&missing), Some(&None));
+    }
+}
@@ -0,0 +1,353 @@
+// 任务回调模块
+//
+// 后台任务完成或失败后，向提交时登记的回调地址 POST 任务信息：
+// - 请求体为 JSON，`X-Rustrans-Signature` 为 `sha256=` 加上对 `{时间戳}.{请求体}` 的
+//   HMAC-SHA256 签名（十六进制），时间戳见 `X-Rustrans-Timestamp`，密钥为 `webhooks.secret`；
+// - 非 2xx 响应或网络错误时按 1、2、4……秒的间隔重试，最多投递 `webhooks.max_attempts` 次；
+// - 回调地址解析到内网、回环或链路本地地址时拒绝投递，除非主机在 `webhooks.allowed_hosts` 中；
+//   提交任务和每次投递前都会检查，投递时连接检查通过的地址（不再重新解析），且不跟随重定向；
+// - 最近的投递记录保存在内存中，可在管理后台查看；投递结束前任务记录标记为待回调，
+//   服务重启后由任务模块重新投递。
+
+use crate::config::{Config, WebhookSettings};
+use crate::jobs::{JobInfo, JobStatus};
+use actix_web::{get, web, HttpResponse};
+use hmac::{Hmac, Mac};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 保留的投递记录数量
+const MAX_DELIVERIES: usize = 200;
+
+/// 投递状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+/// 一次投递尝试
+#[derive(Debug, Clone, Serialize)]
+pub struct Attempt {
+    pub at: String,
+    /// 响应状态码，网络错误时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// 一次回调投递及其所有尝试
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    pub id: u64,
+    pub job_id: String,
+    /// `job.completed` 或 `job.failed`
+    pub event: String,
+    pub url: String,
+    pub status: DeliveryStatus,
+    pub attempts: Vec<Attempt>,
+    pub created_at: String,
+}
+
+/// 投递记录，所有 worker 共享
+#[derive(Clone, Default)]
+pub struct Webhooks {
+    deliveries: Arc<Mutex<VecDeque<Delivery>>>,
+    sequence: Arc<AtomicU64>,
+}
+
+impl Webhooks {
+    /// 任务结束后通知回调地址，投递结束（成功或放弃）后调用 `on_finished`；取消的任务不通知
+    pub fn notify(
+        &self,
+        url: &str,
+        job: &JobInfo,
+        config: &Arc<RwLock<Config>>,
+        on_finished: impl FnOnce() + 'static,
+    ) {
+        let event = match job.status {
+            JobStatus::Completed => "job.completed",
+            JobStatus::Failed => "job.failed",
+            _ => return on_finished(),
+        };
+        let body = serde_json::json!({
+            "event": event,
+            "job": job,
+            "result_url": (job.status == JobStatus::Completed)
+                .then(|| format!("/jobs/{}/result", job.id)),
+        })
+        .to_string();
+
+        let id = self.sequence.fetch_add(1, Ordering::Relaxed) + 1;
+        {
+            let mut deliveries = self.deliveries.lock();
+            if deliveries.len() == MAX_DELIVERIES {
+                deliveries.pop_back();
+            }
+            deliveries.push_front(Delivery {
+                id,
+                job_id: job.id.clone(),
+                event: event.to_string(),
+                url: url.to_string(),
+                status: DeliveryStatus::Pending,
+                attempts: Vec::new(),
+                created_at: chrono::Local::now().to_rfc3339(),
+            });
+        }
+
+        let settings = config.read().webhooks.clone();
+        let webhooks = self.clone();
+        let url = url.to_string();
+        actix_web::rt::spawn(async move {
+            webhooks.deliver(id, &url, event, &body, &settings).await;
+            on_finished();
+        });
+    }
+
+    /// 投递并按指数退避重试
+    async fn deliver(
+        &self,
+        id: u64,
+        url: &str,
+        event: &str,
+        body: &str,
+        settings: &WebhookSettings,
+    ) {
+        for attempt in 1..=settings.max_attempts.max(1) {
+            let start = std::time::Instant::now();
+            let target = check_target(url, &settings.allowed_hosts)
+                .await
+                .and_then(|target| target.client().map_err(|e| e.to_string()));
+            let client = match target {
+                Ok(client) => client,
+                Err(e) => {
+                    log::warn!("回调 {} 已拒绝: {}", url, e);
+                    self.update(id, |delivery| {
+                        delivery.attempts.push(Attempt {
+                            at: chrono::Local::now().to_rfc3339(),
+                            status: None,
+                            error: Some(e),
+                            duration_ms: start.elapsed().as_millis() as u64,
+                        });
+                        delivery.status = DeliveryStatus::Failed;
+                    });
+                    return;
+                }
+            };
+            let timestamp = chrono::Utc::now().timestamp().to_string();
+            let mut request = client
+                .post(url)
+                .timeout(Duration::from_secs(settings.timeout_secs))
+                .header("Content-Type", "application/json")
+                .header("X-Rustrans-Event", event)
+                .header("X-Rustrans-Delivery", id.to_string())
+                .header("X-Rustrans-Timestamp", &timestamp)
+                .body(body.to_string());
+            if !settings.secret.is_empty() {
+                request = request.header(
+                    "X-Rustrans-Signature",
+                    format!("sha256={}", sign(&settings.secret, &timestamp, body)),
+                );
+            }
+            let (status, error) = match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    (Some(response.status().as_u16()), None)
+                }
+                Ok(response) => (
+                    Some(response.status().as_u16()),
+                    Some(format!("响应状态 {}", response.status())),
+                ),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            let succeeded = error.is_none();
+            let finished = succeeded || attempt == settings.max_attempts;
+            self.update(id, |delivery| {
+                delivery.attempts.push(Attempt {
+                    at: chrono::Local::now().to_rfc3339(),
+                    status,
+                    error: error.clone(),
+                    duration_ms: start.elapsed().as_millis() as u64,
+                });
+                if succeeded {
+                    delivery.status = DeliveryStatus::Succeeded;
+                } else if finished {
+                    delivery.status = DeliveryStatus::Failed;
+                }
+            });
+            if finished {
+                if !succeeded {
+                    log::warn!(
+                        "回调 {} 投递失败，已放弃: {}",
+                        url,
+                        error.unwrap_or_default()
+                    );
+                }
+                return;
+            }
+            tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+        }
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut Delivery)) {
+        if let Some(delivery) = self.deliveries.lock().iter_mut().find(|d| d.id == id) {
+            f(delivery);
+        }
+    }
+
+    /// 最近的投递记录，新的在前
+    pub fn list(&self) -> Vec<Delivery> {
+        self.deliveries.lock().iter().cloned().collect()
+    }
+}
+
+/// 检查通过的回调目标
+#[derive(Debug)]
+pub struct Target {
+    host: String,
+    /// 解析出的地址，投递时直接连接这些地址；白名单中的主机为空，按常规解析
+    addresses: Vec<SocketAddr>,
+}
+
+impl Target {
+    /// 投递用的 HTTP 客户端：域名固定解析到检查过的地址，防止再次解析时被换成内网地址
+    fn client(&self) -> reqwest::Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(concat!("Rustrans/", env!("CARGO_PKG_VERSION")))
+            .redirect(reqwest::redirect::Policy::none());
+        if !self.addresses.is_empty() {
+            builder = builder.resolve_to_addrs(&self.host, &self.addresses);
+        }
+        builder.build()
+    }
+}
+
+/// 检查回调地址：主机不在白名单中时，解析出的所有地址都必须是公网地址
+pub async fn check_target(url: &str, allowed_hosts: &[String]) -> Result<Target, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("URL 格式无效: {}", e))?;
+    let host = parsed
+        .host_str()
+        .filter(|host| !host.is_empty())
+        .ok_or("缺少主机名")?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_ascii_lowercase();
+    if allowed_hosts
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(&host))
+    {
+        return Ok(Target {
+            host,
+            addresses: Vec::new(),
+        });
+    }
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let addresses: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((host.as_str(), port))
+            .await
+            .map_err(|e| format!("无法解析主机 {}: {}", host, e))?
+            .collect(),
+    };
+    match addresses.iter().find(|address| !is_public(address.ip())) {
+        Some(address) => Err(format!(
+            "主机 {} 指向内网地址 {}，如需允许请加入 webhooks.allowed_hosts",
+            host,
+            address.ip()
+        )),
+        None if addresses.is_empty() => Err(format!("无法解析主机 {}", host)),
+        None => Ok(Target { host, addresses }),
+    }
+}
+
+/// 是否为公网地址（排除回环、私有、链路本地、共享、文档和保留地址）
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                || a >= 240
+                || a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_unspecified()
+                    || ip.is_loopback()
+                    || ip.is_multicast()
+                    || first & 0xfe00 == 0xfc00
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+/// 对 `{时间戳}.{请求体}` 计算 HMAC-SHA256 签名（十六进制）
+pub fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC 接受任意长度的密钥");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// 列出最近的回调投递记录
+#[get("/admin/webhooks/deliveries")]
+pub async fn list_webhook_deliveries(webhooks: web::Data<Webhooks>) -> HttpResponse {
+    HttpResponse::Ok().json(webhooks.list())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign("secret", "1700000000", r#"{"event":"job.completed"}"#),
+            "e33f34cc0b46f4e752fe75a10d7177366fd795c052ed09dfa63608265c13be69"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_check_target() {
+        for url in [
+            "http://127.0.0.1:9/hook",
+            "http://10.1.2.3/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[::ffff:192.168.1.1]/hook",
+            "http://localhost/hook",
+        ] {
+            assert!(check_target(url, &[]).await.is_err(), "{}", url);
+        }
+        let target = check_target("https://93.184.216.34/hook", &[])
+            .await
+            .unwrap();
+        assert_eq!(target.addresses, vec!["93.184.216.34:443".parse().unwrap()]);
+        assert!(target.client().is_ok());
+        let allowed = vec!["LOCALHOST".to_string(), "10.1.2.3".to_string()];
+        assert!(check_target("http://localhost:8080/hook", &allowed)
+            .await
+            .is_ok());
+        assert!(check_target("http://10.1.2.3/hook", &allowed).await.is_ok());
+    }
+}
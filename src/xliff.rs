@@ -0,0 +1,506 @@
+// XLIFF 翻译模块
+//
+// 作为 CAT 工具流程中的机器翻译步骤：把 XLIFF 1.2 的 <trans-unit> 和 XLIFF 2.0 的 <segment>
+// 中的 <source> 译为 <target>，并标记状态供审校人员识别机器翻译的单元：
+// - 1.2：`<target state="needs-review-translation" state-qualifier="mt-suggestion">`；
+// - 2.0：<segment> 的 `state="translated"`、`subState="rustrans:mt"`。
+// `translate="no"` 的文件、分组和单元不翻译，已有译文的单元保持不变。行内标记
+// （<g>、<x/>、<pc>、<ph/> 等）作为占位符保留，<ph>、<bpt> 等元素中的原生代码整体保护。
+// 文件的其余部分逐字节保留。
+
+use crate::config::Config;
+use crate::error::{AppError, AppResult};
+use crate::language::language_code;
+use crate::markup::{self, Token, TokenKind};
+use crate::placeholder::Masked;
+use crate::segments::translate_segments_with_notes;
+use crate::translator::TranslateOptions;
+use crate::variables;
+use std::ops::Range;
+
+/// 包含原生代码、整体保护的行内元素（XLIFF 1.2）
+const NATIVE_CODE_ELEMENTS: &[&str] = &["ph", "bpt", "ept", "it", "sub"];
+
+/// 可以带 `translate` 属性的结构元素
+const CONTAINERS: &[&str] = &[
+    "file",
+    "group",
+    "trans-unit",
+    "unit",
+    "segment",
+    "ignorable",
+];
+
+/// 表示尚未翻译的状态
+const UNTRANSLATED_STATES: &[&str] = &["new", "needs-translation", "initial"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Version {
+    V1,
+    V2,
+}
+
+/// 翻译后的文件及统计
+#[derive(Debug)]
+pub struct XliffOutput {
+    pub content: String,
+    /// 新翻译的单元数
+    pub translated: usize,
+    /// 翻译失败、未写入译文的单元数
+    pub failed: usize,
+}
+
+/// 一个待翻译的单元
+struct Unit {
+    id: String,
+    notes: Vec<String>,
+    /// <source> 的内容区间
+    source: Range<usize>,
+    protected: Vec<Range<usize>>,
+    /// </source> 的结束位置，没有 <target> 时在其后插入
+    source_end: usize,
+    /// 已有的 <target> 元素（空或未翻译）
+    target: Option<Range<usize>>,
+    /// XLIFF 2.0 <segment> 开始标签，用于设置状态
+    segment: Option<Token>,
+    /// <source> 所在行的缩进
+    indent: Option<String>,
+}
+
+/// 解析结果
+struct Document {
+    version: Version,
+    units: Vec<Unit>,
+    /// 缺少目标语言属性的元素：`(开始标签, 属性名)`
+    language_tags: Vec<(Token, &'static str)>,
+}
+
+/// 所有待翻译单元的原文，用于语种检测
+pub fn source_text(text: &str) -> AppResult<String> {
+    let doc = parse(text)?;
+    Ok(doc
+        .units
+        .iter()
+        .map(|u| &text[u.source.clone()])
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// 翻译 XLIFF 文件
+pub async fn translate(
+    config: &Config,
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+    options: &TranslateOptions,
+) -> AppResult<XliffOutput> {
+    let doc = parse(text)?;
+    let segments: Vec<Masked> = doc
+        .units
+        .iter()
+        .map(|u| Masked::new(text, u.source.clone(), &u.protected).with_escape(markup::escape_text))
+        .collect();
+    let notes: Vec<Option<String>> = doc
+        .units
+        .iter()
+        .map(|u| {
+            let mut note = vec![format!("单元 ID：{}", u.id)];
+            if !u.notes.is_empty() {
+                note.push(format!("注释：{}", u.notes.join(" ")));
+            }
+            Some(note.join("；"))
+        })
+        .collect();
+    let results =
+        translate_segments_with_notes(config, &segments, &notes, from_lang, to_lang, options)
+            .await?;
+
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let mut failed = 0;
+    // 没有可翻译文字的单元直接以原文作为译文
+    for (unit, result) in doc.units.iter().zip(results) {
+        match result {
+            Some(translation) => edits.extend(unit.target_edits(doc.version, &translation)),
+            None => failed += 1,
+        }
+    }
+    let language = language_code(to_lang).unwrap_or(to_lang);
+    for (tag, attr) in &doc.language_tags {
+        edits.push((
+            insert_point(tag),
+            format!(" {}=\"{}\"", attr, markup::escape_attr(language)),
+        ));
+    }
+
+    Ok(XliffOutput {
+        content: markup::apply_edits(text, edits),
+        translated: doc.units.len() - failed,
+        failed,
+    })
+}
+
+impl Unit {
+    /// 写入译文和状态的编辑
+    fn target_edits(&self, version: Version, translation: &str) -> Vec<(Range<usize>, String)> {
+        let mut edits = Vec::new();
+        let target = match version {
+            Version::V1 => format!(
+                "<target state=\"needs-review-translation\" state-qualifier=\"mt-suggestion\">{}</target>",
+                translation
+            ),
+            Version::V2 => format!("<target>{}</target>", translation),
+        };
+        match (&self.target, &self.indent) {
+            (Some(range), _) => edits.push((range.clone(), target)),
+            (None, Some(indent)) => edits.push((
+                self.source_end..self.source_end,
+                format!("\n{}{}", indent, target),
+            )),
+            (None, None) => edits.push((self.source_end..self.source_end, target)),
+        }
+        if let Some(segment) = &self.segment {
+            for (name, value) in [("state", "translated"), ("substate", "rustrans:mt")] {
+                match segment.attr(name) {
+                    Some(range) => edits.push((range, value.to_string())),
+                    None => {
+                        let name = if name == "substate" { "subState" } else { name };
+                        edits.push((insert_point(segment), format!(" {}=\"{}\"", name, value)))
+                    }
+                }
+            }
+        }
+        edits
+    }
+}
+
+/// 在开始标签的 `>` 或 `/>` 之前插入属性的位置
+fn insert_point(tag: &Token) -> Range<usize> {
+    let end = match tag.kind {
+        TokenKind::Start {
+            self_closing: true, ..
+        } => tag.range.end - 2,
+        _ => tag.range.end - 1,
+    };
+    end..end
+}
+
+/// 从 `start` 开始查找与开始标签对应的结束标签（考虑同名元素嵌套）
+fn find_end(tokens: &[Token], start: usize, name: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (j, token) in tokens.iter().enumerate().skip(start) {
+        match &token.kind {
+            TokenKind::Start {
+                name: n,
+                self_closing: false,
+                ..
+            } if n == name => depth += 1,
+            TokenKind::End { name: n } if n == name => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(j);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn attr_is(text: &str, token: &Token, name: &str, value: &str) -> bool {
+    token.attr(name).is_some_and(|r| text[r].trim() == value)
+}
+
+fn parse(text: &str) -> AppResult<Document> {
+    let tokens = markup::tokenize(text, &[]);
+    let root = tokens
+        .iter()
+        .find(|t| matches!(&t.kind, TokenKind::Start { name, .. } if name == "xliff"))
+        .ok_or_else(|| AppError::Validation("不是 XLIFF 文件：缺少 <xliff> 元素".to_string()))?;
+    let version = match root.attr("version").map(|r| &text[r]) {
+        Some(v) if v.starts_with('2') => Version::V2,
+        _ => Version::V1,
+    };
+    let mut doc = Document {
+        version,
+        units: Vec::new(),
+        language_tags: Vec::new(),
+    };
+    if version == Version::V2 && root.attr("trglang").is_none() {
+        doc.language_tags.push((root.clone(), "trgLang"));
+    }
+
+    // 结构元素栈：(元素名, 是否不翻译)
+    let mut stack: Vec<(String, bool)> = Vec::new();
+    let mut unit: Option<(String, Vec<String>)> = None;
+    let mut segment: Option<Token> = None;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        match &token.kind {
+            TokenKind::Start {
+                name, self_closing, ..
+            } if CONTAINERS.contains(&name.as_str()) => {
+                if *self_closing {
+                    i += 1;
+                    continue;
+                }
+                let inherited = stack.last().is_some_and(|(_, no)| *no);
+                let no =
+                    inherited || attr_is(text, token, "translate", "no") || name == "ignorable";
+                stack.push((name.clone(), no));
+                match name.as_str() {
+                    "file" if version == Version::V1 && token.attr("target-language").is_none() => {
+                        doc.language_tags.push((token.clone(), "target-language"));
+                    }
+                    "trans-unit" | "unit" => {
+                        let id = token
+                            .attr("id")
+                            .map(|r| text[r].to_string())
+                            .unwrap_or_default();
+                        unit = Some((id, notes(text, &tokens, i, name)));
+                    }
+                    "segment" => segment = Some(token.clone()),
+                    _ => {}
+                }
+            }
+            TokenKind::End { name } if CONTAINERS.contains(&name.as_str()) => {
+                if let Some(pos) = stack.iter().rposition(|(n, _)| n == name) {
+                    stack.truncate(pos);
+                }
+                if name == "segment" {
+                    segment = None;
+                }
+            }
+            TokenKind::Start {
+                name,
+                self_closing: false,
+                ..
+            } if name == "source" => {
+                let end = find_end(&tokens, i, "source").ok_or_else(|| {
+                    AppError::Validation(format!(
+                        "XLIFF 第 {} 行的 <source> 没有结束标签",
+                        text[..token.range.start].matches('\n').count() + 1
+                    ))
+                })?;
+                let container = match version {
+                    Version::V1 => "trans-unit",
+                    Version::V2 => "segment",
+                };
+                let translatable = matches!(stack.last(), Some((n, false)) if n == container);
+                if translatable {
+                    if let Some(found) =
+                        source_unit(text, &tokens, i, end, container, &unit, &segment)
+                    {
+                        doc.units.push(found);
+                    }
+                }
+                i = end + 1;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(doc)
+}
+
+/// 单元中的 <note> 文字
+fn notes(text: &str, tokens: &[Token], start: usize, name: &str) -> Vec<String> {
+    let end = find_end(tokens, start, name).unwrap_or(tokens.len() - 1);
+    let mut notes = Vec::new();
+    let mut k = start;
+    while k < end {
+        if matches!(&tokens[k].kind, TokenKind::Start { name, self_closing: false, .. } if name == "note")
+        {
+            if let Some(close) = find_end(tokens, k, "note") {
+                let note = text[tokens[k].range.end..tokens[close].range.start].trim();
+                if !note.is_empty() {
+                    notes.push(note.to_string());
+                }
+                k = close;
+            }
+        }
+        k += 1;
+    }
+    notes
+}
+
+/// 从 `tokens[start..=end]`（<source> 元素）构造待翻译单元；已有译文时返回 None
+fn source_unit(
+    text: &str,
+    tokens: &[Token],
+    start: usize,
+    end: usize,
+    container: &str,
+    unit: &Option<(String, Vec<String>)>,
+    segment: &Option<Token>,
+) -> Option<Unit> {
+    let source = tokens[start].range.end..tokens[end].range.start;
+    if text[source.clone()].trim().is_empty() {
+        return None;
+    }
+
+    // 同一单元中的 <target>：(元素区间, 内容, 状态)
+    let mut target = None;
+    for (k, token) in tokens.iter().enumerate().skip(end + 1) {
+        match &token.kind {
+            TokenKind::End { name } if name == container => break,
+            TokenKind::Start {
+                name, self_closing, ..
+            } if name == "target" => {
+                let state = match segment {
+                    Some(segment) => segment.attr("state"),
+                    None => token.attr("state"),
+                };
+                target = Some(if *self_closing {
+                    (token.range.clone(), "", state)
+                } else {
+                    let close = find_end(tokens, k, "target")?;
+                    let content = &text[token.range.end..tokens[close].range.start];
+                    (token.range.start..tokens[close].range.end, content, state)
+                });
+                break;
+            }
+            _ => {}
+        }
+    }
+    if let Some((_, content, state)) = &target {
+        let untranslated = state
+            .clone()
+            .is_some_and(|r| UNTRANSLATED_STATES.contains(&&text[r]));
+        if !content.trim().is_empty() && !untranslated {
+            return None;
+        }
+    }
+
+    let line_start = text[..tokens[start].range.start]
+        .rfind('\n')
+        .map_or(0, |i| i + 1);
+    let before = &text[line_start..tokens[start].range.start];
+    let (id, notes) = unit.clone().unwrap_or_default();
+    Some(Unit {
+        id,
+        notes,
+        protected: inline_ranges(text, &tokens[start + 1..end]),
+        source,
+        source_end: tokens[end].range.end,
+        target: target.map(|(range, _, _)| range),
+        segment: segment.clone(),
+        indent: before.trim().is_empty().then(|| before.to_string()),
+    })
+}
+
+/// <source> 内容中需要保护的区间：行内标记、原生代码、不翻译的标注、字符实体和变量
+fn inline_ranges(text: &str, tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut protected = Vec::new();
+    let mut k = 0;
+    while k < tokens.len() {
+        let token = &tokens[k];
+        match &token.kind {
+            TokenKind::Text => {
+                protected.extend(markup::entity_ranges(text, token.range.clone()));
+                protected.extend(
+                    variables::find(&text[token.range.clone()])
+                        .into_iter()
+                        .map(|r| token.range.start + r.start..token.range.start + r.end),
+                );
+            }
+            TokenKind::Start {
+                name,
+                self_closing: false,
+                ..
+            } if NATIVE_CODE_ELEMENTS.contains(&name.as_str())
+                || name == "mrk"
+                    && (attr_is(text, token, "translate", "no")
+                        || attr_is(text, token, "mtype", "protected")) =>
+            {
+                let close = find_end(tokens, k, name).unwrap_or(tokens.len() - 1);
+                protected.push(token.range.start..tokens[close].range.end);
+                k = close;
+            }
+            _ => protected.push(token.range.clone()),
+        }
+        k += 1;
+    }
+    protected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn masked(text: &str) -> Vec<String> {
+        let doc = parse(text).unwrap();
+        doc.units
+            .iter()
+            .map(|u| Masked::new(text, u.source.clone(), &u.protected).text)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_xliff_12() {
+        let text = r#"<xliff version="1.2"><file source-language="en" datatype="html">
+  <body>
+    <trans-unit id="1">
+      <source>Click <g id="1">here</g> to open <x id="2"/> {name}</source>
+      <note>Button label</note>
+    </trans-unit>
+    <trans-unit id="2" translate="no"><source>Acme</source></trans-unit>
+    <trans-unit id="3"><source>Done</source><target state="translated">完成</target></trans-unit>
+    <trans-unit id="4"><source>Save <ph id="1">&lt;br/&gt;</ph> now</source><target state="new"></target></trans-unit>
+  </body>
+</file></xliff>"#;
+        assert_eq!(
+            masked(text),
+            vec!["Click ⟦1⟧here⟦2⟧ to open ⟦3⟧ ⟦4⟧", "Save ⟦1⟧ now"]
+        );
+        let doc = parse(text).unwrap();
+        assert_eq!(doc.units[0].notes, vec!["Button label"]);
+        assert_eq!(doc.language_tags.len(), 1);
+
+        let unit = &doc.units[0];
+        let edits = unit.target_edits(doc.version, "点击<g id=\"1\">这里</g>");
+        let output = markup::apply_edits(text, edits);
+        assert!(output.contains(
+            "</source>\n      <target state=\"needs-review-translation\" state-qualifier=\"mt-suggestion\">点击<g id=\"1\">这里</g></target>\n      <note>"
+        ));
+    }
+
+    #[test]
+    fn test_parse_xliff_20() {
+        let text = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" version="2.0" srcLang="en">
+ <file id="f1">
+  <unit id="u1">
+   <segment state="initial">
+    <source>Hello <pc id="1">world</pc><ph id="2"/> <mrk id="m1" translate="no">Acme</mrk></source>
+   </segment>
+   <ignorable><source> </source></ignorable>
+  </unit>
+ </file>
+</xliff>"#;
+        assert_eq!(masked(text), vec!["Hello ⟦1⟧world⟦2⟧ ⟦3⟧"]);
+        let doc = parse(text).unwrap();
+        let output = markup::apply_edits(text, doc.units[0].target_edits(doc.version, "你好"));
+        assert!(output.contains("<segment state=\"translated\" subState=\"rustrans:mt\">"));
+        assert!(output.contains("</source>\n    <target>你好</target>"));
+        assert_eq!(doc.language_tags[0].1, "trgLang");
+    }
+
+    #[actix_web::test]
+    async fn test_target_language_is_escaped() {
+        // 只含数字的单元不请求大模型
+        let text = r#"<xliff version="1.2"><file source-language="en"><body><trans-unit id="1"><source>42</source></trans-unit></body></file></xliff>"#;
+        let output = translate(
+            &Config::default(),
+            text,
+            "英语",
+            r#"x" onload="<&"#,
+            &TranslateOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(output.content.contains(
+            r#"<file source-language="en" target-language="x&quot; onload=&quot;&lt;&amp;">"#
+        ));
+    }
+}
@@ -0,0 +1,461 @@
+// YAML 资源文件扫描模块
+//
+// 面向 Rails 风格的翻译文件：按行识别嵌套映射、序列、引号标量和块标量（`|`、`>`），
+// 记录每个字符串值在原文中的区间。注释、锚点、标签和流式集合原样保留，不做翻译；
+// 多行普通标量等不常见的写法会报错，而不是冒险改写文件。
+
+use crate::error::{AppError, AppResult};
+use crate::resources::{json_string, Parsed, Style, Unit};
+
+lazy_static::lazy_static! {
+    /// 按普通标量解析时不是字符串的值
+    static ref NON_STRING: regex::Regex = regex::Regex::new(
+        r"(?i)^(true|false|yes|no|on|off|y|n|null|~|[-+]?(\d[\d_]*)?(\.\d+)?([eE][-+]?\d+)?|0x[0-9a-f]+|[-+]?\.inf|\.nan)$",
+    )
+    .unwrap();
+}
+
+/// 普通标量不能以这些字符开头
+const INDICATORS: &[char] = &[
+    '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`',
+];
+
+/// 映射键或序列项
+struct Node {
+    indent: usize,
+    key: String,
+    /// 已出现的子序列项数量
+    items: usize,
+    is_item: bool,
+}
+
+/// 扫描 YAML 文件中的字符串值
+pub fn parse(text: &str) -> AppResult<Parsed> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        lines.push((offset, line.trim_end_matches(['\n', '\r'])));
+        offset += line.len();
+    }
+
+    let mut scanner = Scanner {
+        lines,
+        stack: Vec::new(),
+        root_items: 0,
+        parsed: Parsed::default(),
+    };
+    let mut i = 0;
+    while i < scanner.lines.len() {
+        let (start, line) = scanner.lines[i];
+        let content = line.trim_start_matches(' ');
+        if content.trim().is_empty()
+            || content.starts_with('#')
+            || line.starts_with("---")
+            || line.starts_with("...")
+            || line.starts_with('%')
+        {
+            i += 1;
+            continue;
+        }
+        let indent = line.len() - content.len();
+        i = scanner.entry(i, start + indent, indent, content)?;
+    }
+    Ok(scanner.parsed)
+}
+
+struct Scanner<'a> {
+    lines: Vec<(usize, &'a str)>,
+    stack: Vec<Node>,
+    root_items: usize,
+    parsed: Parsed,
+}
+
+impl Scanner<'_> {
+    fn error(&self, line: usize, message: &str) -> AppError {
+        AppError::Validation(format!("YAML 第 {} 行{}", line + 1, message))
+    }
+
+    fn path(&self) -> Vec<String> {
+        self.stack.iter().map(|n| n.key.clone()).collect()
+    }
+
+    /// 处理从 `offset` 开始的一个映射项或序列项，返回下一行的行号
+    fn entry(
+        &mut self,
+        line: usize,
+        offset: usize,
+        indent: usize,
+        content: &str,
+    ) -> AppResult<usize> {
+        if content == "-" || content.starts_with("- ") {
+            while self
+                .stack
+                .last()
+                .is_some_and(|top| top.indent > indent || top.indent == indent && top.is_item)
+            {
+                self.stack.pop();
+            }
+            let index = match self.stack.last_mut() {
+                Some(owner) => {
+                    owner.items += 1;
+                    owner.items - 1
+                }
+                None => {
+                    self.root_items += 1;
+                    self.root_items - 1
+                }
+            };
+            self.stack.push(Node {
+                indent,
+                key: index.to_string(),
+                items: 0,
+                is_item: true,
+            });
+
+            let rest = content[1..].trim_start_matches(' ');
+            if rest.is_empty() || rest.starts_with('#') {
+                return Ok(line + 1);
+            }
+            let skipped = content.len() - rest.len();
+            if parse_key(rest).is_some() {
+                return self.entry(line, offset + skipped, indent + skipped, rest);
+            }
+            let path = self.path();
+            return self.value(line, path, offset + skipped, rest, indent);
+        }
+
+        let Some((key, key_range, value_start)) = parse_key(content) else {
+            return Err(self.error(line, "无法解析"));
+        };
+        while self.stack.last().is_some_and(|top| top.indent >= indent) {
+            self.stack.pop();
+        }
+        if self.stack.is_empty() {
+            self.parsed.root_keys.push((
+                key.clone(),
+                offset + key_range.start..offset + key_range.end,
+            ));
+        }
+
+        let rest = content[value_start..].trim_start_matches(' ');
+        if rest.is_empty() || rest.starts_with('#') {
+            self.stack.push(Node {
+                indent,
+                key,
+                items: 0,
+                is_item: false,
+            });
+            return Ok(line + 1);
+        }
+        let mut path = self.path();
+        path.push(key);
+        let value_offset = offset + content.len() - rest.len();
+        self.value(line, path, value_offset, rest, indent)
+    }
+
+    /// 处理标量值，返回下一行的行号
+    fn value(
+        &mut self,
+        line: usize,
+        path: Vec<String>,
+        offset: usize,
+        value: &str,
+        parent_indent: usize,
+    ) -> AppResult<usize> {
+        let (scalar, next) = match value.chars().next() {
+            Some('|' | '>') => {
+                let folded = value.starts_with('>');
+                let explicit = value[1..]
+                    .chars()
+                    .find(char::is_ascii_digit)
+                    .and_then(|d| d.to_digit(10));
+                let mut end = line + 1;
+                let mut last = None;
+                while let Some((_, text)) = self.lines.get(end) {
+                    let indent = text.len() - text.trim_start_matches(' ').len();
+                    if !text.trim().is_empty() {
+                        if indent <= parent_indent {
+                            break;
+                        }
+                        last = Some(end);
+                    }
+                    end += 1;
+                }
+                let Some(last) = last else {
+                    return Ok(line + 1);
+                };
+                let block = &self.lines[line + 1..=last];
+                let first = block
+                    .iter()
+                    .find(|(_, text)| !text.trim().is_empty())
+                    .map_or(0, |(_, text)| {
+                        text.len() - text.trim_start_matches(' ').len()
+                    });
+                let indent = explicit.map_or(first, |d| parent_indent + d as usize);
+                let content: Vec<&str> = block
+                    .iter()
+                    .map(|(_, text)| text.get(indent..).unwrap_or("").trim_end())
+                    .collect();
+                let decoded = if folded {
+                    fold(&content)
+                } else {
+                    content.join("\n")
+                };
+                let (start, _) = block[0];
+                let (last_start, last_text) = self.lines[last];
+                let range = start..last_start + last_text.len();
+                (
+                    Some((range, decoded, Style::YamlBlock { indent, folded })),
+                    last + 1,
+                )
+            }
+            Some('"') => {
+                let end = closing_double(value)
+                    .ok_or_else(|| self.error(line, "的双引号字符串未结束"))?;
+                self.check_trailing(line, &value[end..])?;
+                // 含 YAML 特有转义（如 `\e`）的值保持原样
+                let scalar = serde_json::from_str::<String>(&value[..end])
+                    .ok()
+                    .map(|decoded| (offset..offset + end, decoded, Style::YamlDouble));
+                (scalar, line + 1)
+            }
+            Some('\'') => {
+                let end = closing_single(value)
+                    .ok_or_else(|| self.error(line, "的单引号字符串未结束"))?;
+                self.check_trailing(line, &value[end..])?;
+                let decoded = value[1..end - 1].replace("''", "'");
+                (
+                    Some((offset..offset + end, decoded, Style::YamlSingle)),
+                    line + 1,
+                )
+            }
+            // 锚点、别名、标签和流式集合不翻译
+            Some('&' | '*' | '!' | '[' | '{' | '`' | '@') => (None, line + 1),
+            _ => {
+                let plain = match value.find(" #") {
+                    Some(i) => &value[..i],
+                    None => value,
+                }
+                .trim_end();
+                let scalar = (!NON_STRING.is_match(plain)).then(|| {
+                    (
+                        offset..offset + plain.len(),
+                        plain.to_string(),
+                        Style::YamlPlain,
+                    )
+                });
+                (scalar, line + 1)
+            }
+        };
+
+        if let Some((range, decoded, style)) = scalar {
+            if !decoded.trim().is_empty() {
+                self.parsed.units.push(Unit {
+                    path,
+                    range,
+                    value: decoded,
+                    protected: Vec::new(),
+                    style,
+                });
+            }
+        }
+        Ok(next)
+    }
+
+    fn check_trailing(&self, line: usize, rest: &str) -> AppResult<()> {
+        let rest = rest.trim();
+        if rest.is_empty() || rest.starts_with('#') {
+            Ok(())
+        } else {
+            Err(self.error(line, "的引号字符串之后有多余内容"))
+        }
+    }
+}
+
+/// 解析映射键，返回键名、键在 `content` 中的区间和冒号之后的位置
+fn parse_key(content: &str) -> Option<(String, std::ops::Range<usize>, usize)> {
+    let (key, range, after) = match content.chars().next()? {
+        '"' => {
+            let end = closing_double(content)?;
+            let key = serde_json::from_str(&content[..end]).ok()?;
+            (key, 1..end - 1, end)
+        }
+        '\'' => {
+            let end = closing_single(content)?;
+            (content[1..end - 1].replace("''", "'"), 1..end - 1, end)
+        }
+        '-' | '?' | '#' => return None,
+        _ => {
+            let colon = content
+                .char_indices()
+                .find(|&(i, c)| {
+                    c == ':'
+                        && content[i + 1..]
+                            .chars()
+                            .next()
+                            .is_none_or(|n| n == ' ' || n == '\t')
+                })
+                .map(|(i, _)| i)?;
+            let key = content[..colon].trim_end();
+            (key.to_string(), 0..key.len(), colon)
+        }
+    };
+    let rest = &content[after..];
+    let rest = rest.trim_start_matches(' ');
+    let colon = after + content[after..].len() - rest.len();
+    if !rest.starts_with(':')
+        || !rest[1..]
+            .chars()
+            .next()
+            .is_none_or(|n| n == ' ' || n == '\t')
+    {
+        return None;
+    }
+    Some((key, range, colon + 1))
+}
+
+/// 双引号字符串的结束位置（含引号）
+fn closing_double(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// 单引号字符串的结束位置（含引号），`''` 为转义的单引号
+fn closing_single(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        if bytes[i] == b'\'' {
+            if bytes.get(i + 1) == Some(&b'\'') {
+                i += 2;
+                continue;
+            }
+            return Some(i + 1);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// 折叠块标量：相邻行以空格连接，空行表示换行
+fn fold(lines: &[&str]) -> String {
+    let mut value = String::new();
+    let mut after_break = true;
+    for line in lines {
+        if line.is_empty() {
+            value.push('\n');
+            after_break = true;
+        } else {
+            if !after_break {
+                value.push(' ');
+            }
+            value.push_str(line);
+            after_break = false;
+        }
+    }
+    value
+}
+
+/// 译文写回普通标量，必要时改为双引号
+pub fn encode_plain(text: &str) -> String {
+    let safe = !text.is_empty()
+        && text.trim() == text
+        && !text.contains(['\n', '\t'])
+        && !text.starts_with(INDICATORS)
+        && !text.contains(": ")
+        && !text.contains(" #")
+        && !text.ends_with(':')
+        && !NON_STRING.is_match(text);
+    if safe {
+        text.to_string()
+    } else {
+        json_string(text)
+    }
+}
+
+/// 译文写回单引号标量，含换行时改为双引号
+pub fn encode_single(text: &str) -> String {
+    if text.contains('\n') {
+        json_string(text)
+    } else {
+        format!("'{}'", text.replace('\'', "''"))
+    }
+}
+
+/// 译文写回块标量的内容行
+pub fn encode_block(text: &str, indent: usize, folded: bool) -> String {
+    let prefix = " ".repeat(indent);
+    let lines: Vec<String> = text
+        .trim_end_matches('\n')
+        .split('\n')
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect();
+    // 折叠风格中单个换行会被合并为空格，段落之间需要空行
+    lines.join(if folded { "\n\n" } else { "\n" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rails_yaml() {
+        let text = "# comment\nen:\n  greeting: Hello, %{name}! # trailing\n  count: 3\n  quoted: 'It''s here'\n  double: \"Line\\nbreak\"\n  days:\n  - Mon\n  - \"Tue\"\n  nested:\n    - title: First\n      body: |\n        Line one\n\n        Line two\n  folded: >\n    Folded\n    text\n  alias: *ref\n";
+        let parsed = parse(text).unwrap();
+        let units: Vec<(String, String, &str)> = parsed
+            .units
+            .iter()
+            .map(|u| (u.path.join("."), u.value.clone(), &text[u.range.clone()]))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (
+                    "en.greeting".into(),
+                    "Hello, %{name}!".into(),
+                    "Hello, %{name}!"
+                ),
+                ("en.quoted".into(), "It's here".into(), "'It''s here'"),
+                ("en.double".into(), "Line\nbreak".into(), "\"Line\\nbreak\""),
+                ("en.days.0".into(), "Mon".into(), "Mon"),
+                ("en.days.1".into(), "Tue".into(), "\"Tue\""),
+                ("en.nested.0.title".into(), "First".into(), "First"),
+                (
+                    "en.nested.0.body".into(),
+                    "Line one\n\nLine two".into(),
+                    "        Line one\n\n        Line two"
+                ),
+                (
+                    "en.folded".into(),
+                    "Folded text".into(),
+                    "    Folded\n    text"
+                ),
+            ]
+        );
+        assert_eq!(parsed.root_keys.len(), 1);
+        assert!(parse("a: b\n  continued\n").is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode_plain("你好"), "你好");
+        assert_eq!(encode_plain("注意: 空格"), "\"注意: 空格\"");
+        assert_eq!(encode_plain("yes"), "\"yes\"");
+        assert_eq!(encode_single("l'été"), "'l''été'");
+        assert_eq!(encode_block("一\n二", 4, true), "    一\n\n    二");
+    }
+}